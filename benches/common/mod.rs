@@ -0,0 +1,95 @@
+// stand-in backends for the benches: a scratch XDG tree and fake tool scripts on PATH
+use std::env;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+pub const HANDLER: &str = env!("CARGO_BIN_EXE_distrobox-cnf-handler");
+
+pub struct Sandbox {
+    pub root: PathBuf,
+    pub bin: PathBuf,
+    path: String,
+}
+
+impl Sandbox {
+    pub fn new(name: &str) -> Sandbox {
+        let root = env::temp_dir().join(format!("distrobox-cnf-{}-{}", name, std::process::id()));
+        let bin = root.join("bin");
+        for dir in [
+            &bin,
+            &root.join("config/distrobox-cnf"),
+            &root.join("cache/distrobox-cnf"),
+        ] {
+            fs::create_dir_all(dir).expect("create sandbox dirs");
+        }
+        let path = format!("{}:{}", bin.display(), env::var("PATH").unwrap_or_default());
+        Sandbox { root, bin, path }
+    }
+
+    pub fn script(&self, name: &str, body: &str) {
+        write_script(&self.bin.join(name), body);
+    }
+
+    pub fn config(&self, text: &str) {
+        fs::write(self.root.join("config/distrobox-cnf/config.toml"), text).expect("write config");
+    }
+
+    pub fn cache_file(&self, name: &str) -> PathBuf {
+        self.root.join("cache/distrobox-cnf").join(name)
+    }
+
+    // the handler, or anything else, run against this sandbox only
+    pub fn command(&self, program: impl AsRef<Path>) -> Command {
+        let mut cmd = Command::new(program.as_ref());
+        cmd.env("PATH", &self.path)
+            .env("HOME", &self.root)
+            .env("XDG_CONFIG_HOME", self.root.join("config"))
+            .env("XDG_CACHE_HOME", self.root.join("cache"))
+            .env("XDG_RUNTIME_DIR", self.root.join("runtime"))
+            .env("DBX_CONTAINER_MANAGER", "podman")
+            .env("DISTROBOX_CNF_NON_INTERACTIVE", "resolve")
+            .env_remove("DISTROBOX_CNF_PIN")
+            .env_remove("DISTROBOX_CNF_FAST")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+        cmd
+    }
+}
+
+impl Drop for Sandbox {
+    fn drop(&mut self) {
+        if env::var_os("BENCH_KEEP").is_none() {
+            let _ = fs::remove_dir_all(&self.root);
+        }
+    }
+}
+
+pub fn write_script(path: &Path, body: &str) {
+    fs::write(path, format!("#!/bin/sh\n{}\n", body)).expect("write script");
+    fs::set_permissions(path, fs::Permissions::from_mode(0o755)).expect("chmod script");
+}
+
+pub fn median(mut samples: Vec<Duration>) -> Duration {
+    samples.sort();
+    samples[samples.len() / 2]
+}
+
+// the median of `runs` timed runs, after one untimed run to warm the page cache;
+// `before` runs untimed ahead of each
+pub fn sample(runs: usize, mut before: impl FnMut(), mut run: impl FnMut() -> Command) -> Duration {
+    let _ = run().status();
+    median(
+        (0..runs)
+            .map(|_| {
+                before();
+                let started = Instant::now();
+                let status = run().status().expect("spawn");
+                assert!(status.success(), "run failed: {:?}", status);
+                started.elapsed()
+            })
+            .collect(),
+    )
+}
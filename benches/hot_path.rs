@@ -0,0 +1,79 @@
+// the handler's own overhead on a warm cache, measured end to end against a stand-in
+// distrobox-enter; fails when the median exceeds the budget
+mod common;
+
+use std::fs;
+use std::process::exit;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use common::{sample, Sandbox, HANDLER};
+
+const RUNS: usize = 200;
+const BUDGET: Duration = Duration::from_millis(5);
+
+fn main() {
+    let sandbox = Sandbox::new("hot-path");
+    sandbox.script("distrobox-enter", "exec true");
+    sandbox.script("podman", "exit 1");
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    fs::write(
+        sandbox.cache_file("resolutions"),
+        format!("bench-tool\tbench\t{}\n", now),
+    )
+    .expect("write cache");
+    // what a box's login shell said once, as it would be there after the first run
+    fs::create_dir_all(sandbox.cache_file("login-path")).expect("create login path dir");
+    fs::write(
+        sandbox.cache_file("login-path").join("bench"),
+        "/usr/bin:/bin",
+    )
+    .expect("write login path");
+    // a quadlet box besides, for the default config to look through
+    let units = sandbox.root.join("config/containers/systemd");
+    fs::create_dir_all(&units).expect("create quadlet dir");
+    fs::write(
+        units.join("other.container"),
+        "[Container]\nContainerName=other\nImage=bench:latest\nLabel=manager=distrobox\n",
+    )
+    .expect("write quadlet");
+
+    let baseline = sample(
+        RUNS,
+        || {},
+        || {
+            let mut cmd = sandbox.command(sandbox.bin.join("distrobox-enter"));
+            cmd.args(["bench", "--", "bench-tool"]);
+            cmd
+        },
+    );
+    // the default config, as installed, and the one with every optional lookup off
+    let mut over = false;
+    for (case, config) in [
+        ("default config", ""),
+        ("lookups off", "login_path = false\nquadlets = false\n"),
+    ] {
+        sandbox.config(config);
+        let handler = sample(
+            RUNS,
+            || {},
+            || {
+                let mut cmd = sandbox.command(HANDLER);
+                cmd.arg("bench-tool");
+                cmd
+            },
+        );
+        let overhead = handler.saturating_sub(baseline);
+        println!(
+            "cache hit, {}: {:?} median, {:?} of it the handler's own (budget {:?})",
+            case, handler, overhead, BUDGET
+        );
+        over |= overhead > BUDGET;
+    }
+    if over {
+        eprintln!("over budget");
+        exit(1);
+    }
+}
@@ -0,0 +1,88 @@
+// how long a cold lookup takes with each way of finding a command's box, against a
+// stand-in backend of BOXES boxes where only the last one has it; every container exec
+// costs EXEC_LATENCY, as entering a real box does
+mod common;
+
+use std::fmt::Write;
+use std::fs;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use common::{sample, Sandbox, HANDLER};
+
+const RUNS: usize = 20;
+const BOXES: usize = 5;
+const EXEC_LATENCY: &str = "0.02";
+
+// distrobox-enter <box> -- <command...>, running the command with the box's directory
+// as its whole PATH; boxes are entered by ID, so the IDs are the names
+const ENTER: &str = r#"box="$1"; shift; [ "$1" = "--" ] && shift
+sleep "$EXEC_LATENCY"
+dir="$BENCH_BOXES/$box"
+if [ "$1" = "sh" ]; then shift; PATH="$dir:/usr/bin:/bin" exec /bin/sh "$@"; fi
+[ -x "$dir/$1" ] && exec "$dir/$@"
+echo "sh: $1: command not found" >&2; exit 127"#;
+
+fn main() {
+    let sandbox = Sandbox::new("resolution");
+    let boxes = sandbox.root.join("boxes");
+    let mut table = String::from("ID | NAME | STATUS | IMAGE\n");
+    for n in 0..BOXES {
+        fs::create_dir_all(boxes.join(format!("box{}", n))).expect("create box");
+        let _ = writeln!(table, "box{} | box{} | Up 1 hour | bench:latest", n, n);
+    }
+    let provider = boxes.join(format!("box{}", BOXES - 1));
+    common::write_script(&provider.join("bench-tool"), "exit 0");
+    sandbox.script("distrobox-enter", ENTER);
+    sandbox.script("podman", "exit 1");
+    sandbox.script(
+        "distrobox-list",
+        &format!(
+            "[ \"$1\" = --version ] && {{ echo 'distrobox: 1.7.2.1'; exit 0; }}\ncat <<'LIST'\n{}LIST",
+            table
+        ),
+    );
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    fs::create_dir_all(sandbox.cache_file("index")).expect("create index dir");
+    for n in 0..BOXES {
+        let listing = if n == BOXES - 1 { "bench-tool\n" } else { "" };
+        fs::write(
+            sandbox.cache_file("index").join(format!("box{}", n)),
+            format!("{}\n{}", now, listing),
+        )
+        .expect("write index");
+    }
+    let common_config = "login_path = false\nquadlets = false\n";
+    let full_run: String = (0..BOXES)
+        .map(|n| format!("[probe.box{}]\nmethod = \"full-run\"\n", n))
+        .collect();
+    let forget = || {
+        let _ = fs::remove_file(sandbox.cache_file("resolutions"));
+    };
+    let run = |fast: bool, args: &[&str]| {
+        let mut cmd = sandbox.command(HANDLER);
+        cmd.args(args)
+            .env("BENCH_BOXES", &boxes)
+            .env("EXEC_LATENCY", EXEC_LATENCY);
+        if fast {
+            cmd.env("DISTROBOX_CNF_FAST", "1");
+        }
+        cmd
+    };
+
+    sandbox.config(&format!("{}{}", common_config, full_run));
+    let serial_full_run = sample(RUNS, forget, || run(false, &["bench-tool"]));
+    sandbox.config(common_config);
+    let serial_command_v = sample(RUNS, forget, || run(false, &["resolve", "bench-tool"]));
+    sandbox.config(&format!("parallel_probe = true\n{}", common_config));
+    let parallel_command_v = sample(RUNS, forget, || run(false, &["resolve", "bench-tool"]));
+    let index = sample(RUNS, forget, || run(true, &["bench-tool"]));
+
+    println!("cold lookup over {} boxes, median of {} runs:", BOXES, RUNS);
+    println!("  serial full run      {:?}", serial_full_run);
+    println!("  serial command -v    {:?}", serial_command_v);
+    println!("  parallel command -v  {:?}", parallel_command_v);
+    println!("  index lookup         {:?}", index);
+}
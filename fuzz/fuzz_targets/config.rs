@@ -0,0 +1,16 @@
+// the config file: anything is parsed or rejected with an error, never a panic
+#![no_main]
+#![allow(dead_code)]
+
+#[path = "../../src/config.rs"]
+mod config;
+#[path = "../../src/paths.rs"]
+mod paths;
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(text) = std::str::from_utf8(data) {
+        let _ = config::Config::parse(text);
+    }
+});
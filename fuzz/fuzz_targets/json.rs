@@ -0,0 +1,20 @@
+// the container manager's JSON: whatever is accepted reads back the same once written
+#![no_main]
+#![allow(dead_code)]
+
+#[path = "../../src/json.rs"]
+mod json;
+
+use json::Json;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(text) = std::str::from_utf8(data) else {
+        return;
+    };
+    if let Some(parsed) = Json::parse(text) {
+        let written = parsed.to_string();
+        let again = Json::parse(&written).expect("written JSON parses");
+        assert_eq!(again.to_string(), written);
+    }
+});
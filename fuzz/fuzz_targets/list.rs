@@ -0,0 +1,24 @@
+// distrobox-list's table, by the plain parser and row by row: only named boxes come out
+#![no_main]
+#![allow(dead_code)]
+
+#[path = "../../src/listing.rs"]
+mod listing;
+
+use libfuzzer_sys::fuzz_target;
+use listing::{parse_list_conservative, DistroboxInstance};
+
+fuzz_target!(|data: &[u8]| {
+    let lines: Vec<String> = String::from_utf8_lossy(data)
+        .lines()
+        .map(str::to_string)
+        .collect();
+    let boxes = parse_list_conservative(&lines).expect("the parser never fails");
+    let rows = lines
+        .iter()
+        .enumerate()
+        .filter_map(|row| DistroboxInstance::try_from(row).ok());
+    for dbx in boxes.into_iter().chain(rows) {
+        assert!(!dbx.name.is_empty() && !dbx.name.contains('|'));
+    }
+});
@@ -0,0 +1,71 @@
+# Messages of the command-not-found handler. To translate, copy this file to
+# ~/.config/distrobox-cnf/locale/<language>.ftl (e.g. de.ftl or pt_BR.ftl) and change
+# the text after each "=", keeping the { $placeholders }.
+
+declined = { $command }: command not found
+host-only = --host only works from inside a container
+shell-builtin = { $command } is a { $shell } builtin, not a program; it only works when typed into { $shell } itself
+shell-alias = { $command } is usually an alias for '{ $expansion }'; it is missing from this shell's rc files
+fullscreen-refused = Not running { $command }: it needs a terminal and stdin is not one, so it would wait on a screen nobody sees; fullscreen.policy = "tty" gives it one, "run" runs it anyway
+fullscreen-skipped = Skipping { $command }, it needs a terminal and stdin is not one
+outside-group = Not using box { $box } ({ $reason }): this directory keeps to { $boxes }
+too-many-searches = Too many searches running at once, not searching for { $command }
+box-failed = Box { $box } [{ $priority }] could not be entered: { $reason }
+box-failed-named = Box { $box } could not be entered: { $reason }
+box-skipped = Not running { $command } in box { $box }: { $reason }
+box-cooldown = Leaving box { $box } alone for a while after repeated failures, see 'distrobox-cnf-handler doctor'
+not-found-some-failed = Cannot find { $command } in any working boxes! ({ $failed } could not be entered)
+remembered = Remembered { $box } for { $scope } in { $path }
+not-in-cache = Cannot find { $command } in the cache ({ $reason })
+not-in-box = Cannot find { $command } in box { $box }
+no-display = { $command } is in box { $box }, but the box cannot reach the display: { $sockets } is not mounted in it
+no-display-wsl = On WSL the display lives in { $dir }; a box created with --volume { $dir }:{ $dir } can reach it
+no-gpu = Warning: box { $box } has no GPU passthrough, { $command } may not find a GPU
+hash-hint = { $command } is in box { $box }; for this shell to go straight there next time: { $line }
+not-found = Cannot find { $command } in any boxes!
+did-you-mean = Did you mean { $hints }?
+versioned = There is { $hint }, a versioned { $command }
+searched = Searched { $boxes }
+searched-box = { $box } ({ $result })
+create-box-hint = There are no boxes to search; make one with: distrobox create
+hint-host = '{ $command }' (host)
+hint-box = '{ $command }' (box '{ $box }')
+or = or
+foreign-builtin = Note: { $command } is a { $shell } builtin
+package-hint = To find a package providing it: distrobox enter { $box } -- { $search }
+not-on-host = Cannot find { $command } on the host!
+sudo-explain = { $sudo } is not installed on the host, and { $command } cannot run as host root from a box; set sudo = "box" to run it as root inside the box that has it
+sudo-missing-in-box = { $sudo } is not installed in box { $box }
+path-not-found = { $path } is not an executable file in any box
+interpreter-not-found = { $interpreter }, the interpreter { $script } asks for, is not in any box
+unshared-dir = Box { $box } does not share { $dir }, so files the command wrote there stayed inside the box; --copy-back '<glob>' brings them back
+copy-back-none = No files matching { $pattern } to copy back from box { $box }
+pinned-for = pinned for { $scope }
+set-for = set for { $process }
+scope-session = this session
+scope-directory = this directory
+scope-global = everywhere
+fast-var-set = { $var } is set
+repeated-too-quickly = repeated too quickly
+storage-locked = container storage is locked, try again in a moment
+probe-by-running = Box { $box } can only be searched by running the command
+image-gone = Skipping box { $box }, its image is gone; '{ $cleanup }' removes it
+unknown-step = Ignoring unknown strategy step { $step }
+config-ignored = Ignoring config: { $error }
+distrobox-untested = Warning: distrobox { $version } is untested, listing boxes the careful way
+distrobox-version-unknown = Warning: cannot tell the distrobox version, listing boxes the careful way
+wsl-no-systemd = systemd is not running under WSL, running without CPU and memory limits
+no-systemd-run = Cannot find systemd-run, running without CPU and memory limits
+no-script = Cannot find script, running without a terminal
+cannot-list = Cannot get boxes: { $error }
+cannot-list-from = Cannot list the boxes of { $from }
+cannot-enter = Cannot run distrobox-enter: { $error }
+cannot-chain = Cannot run { $key } { $program }: { $error }
+cannot-forward = Cannot forward to the host: { $error }
+cannot-run-on-host = Cannot run on the host: { $error }
+cannot-run-flatpak = Cannot run flatpak: { $error }
+cannot-write-trace = Cannot write trace: { $error }
+cannot-write-shim = Cannot write the shim for { $command }: { $error }
+cannot-remember = Cannot remember the choice: { $error }
+cannot-copy-back = Cannot copy files back: { $error }
+cannot-start-unit = Cannot start { $unit }: { $error }
@@ -0,0 +1,71 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use crate::config::{self, Config};
+use crate::index;
+use crate::paths;
+
+// a shell may export its own id, e.g. for tmux panes sharing one terminal
+const SESSION_VAR: &str = "DISTROBOX_CNF_SESSION";
+const AFFINITY_DIR: &str = "affinity";
+
+// session_affinity = true keeps a terminal with the box it last ran a command in
+pub fn enabled(config: &Config) -> bool {
+    config.get_bool("session_affinity") == Some(true)
+}
+
+// the terminal as "tty-<device>-<session>", since a tty device is reused by the next
+// login on it; None without a controlling terminal, where there is no session to keep
+fn session_key() -> Option<String> {
+    if let Ok(id) = env::var(SESSION_VAR) {
+        let id: String = id.chars().filter(|c| c.is_ascii_alphanumeric()).collect();
+        return Some(id).filter(|id| !id.is_empty());
+    }
+    let stat = fs::read_to_string("/proc/self/stat").ok()?;
+    let fields: Vec<&str> = stat.rsplit_once(')')?.1.split_whitespace().collect();
+    // session and tty_nr are fields 6 and 7, the 4th and 5th after the command name
+    let (session, tty) = (fields.get(3)?, fields.get(4)?);
+    if *tty == "0" {
+        return None;
+    }
+    Some(format!("tty-{}-{}", tty, session))
+}
+
+fn affinity_path() -> Option<PathBuf> {
+    Some(
+        paths::runtime_dir()
+            .ok()?
+            .join(AFFINITY_DIR)
+            .join(session_key()?),
+    )
+}
+
+pub fn remember(name: &str) -> io::Result<()> {
+    config::writable()?;
+    let Some(path) = affinity_path() else {
+        return Ok(());
+    };
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, name)
+}
+
+// the box to try first for `command` in this terminal; one whose index says it lacks the
+// command is not worth the probe
+pub fn preferred(config: &Config, command: &str) -> Option<String> {
+    if !enabled(config) {
+        return None;
+    }
+    let name = fs::read_to_string(affinity_path()?).ok()?;
+    let name = name.trim();
+    if name.is_empty() {
+        return None;
+    }
+    match index::load(name) {
+        Some(idx) if !idx.commands.iter().any(|c| c == command) => None,
+        _ => Some(name.to_string()),
+    }
+}
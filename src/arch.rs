@@ -0,0 +1,103 @@
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::PathBuf;
+
+use crate::config::{self, Config};
+use crate::distrobox::{self, DistroboxInstance};
+use crate::paths;
+
+const ARCH_DIR: &str = "arch";
+
+#[derive(PartialEq)]
+pub enum Policy {
+    // emulated boxes are searched after every native one
+    PreferNative,
+    // emulated boxes are left out unless emulation.commands names the command
+    OptIn,
+    // architecture plays no part
+    Allow,
+}
+
+pub fn policy(config: &Config) -> Policy {
+    match config.get_str("emulation.policy") {
+        Some("opt-in") => Policy::OptIn,
+        Some("allow") => Policy::Allow,
+        _ => Policy::PreferNative,
+    }
+}
+
+pub fn opted_in(config: &Config, command: &str) -> bool {
+    config
+        .get_list("emulation.commands")
+        .is_some_and(|commands| commands.iter().any(|c| c == command))
+}
+
+// the host's architecture under the names container images use
+pub fn host() -> &'static str {
+    match std::env::consts::ARCH {
+        "x86_64" => "amd64",
+        "aarch64" => "arm64",
+        "x86" => "386",
+        "arm" => "arm",
+        "powerpc64" => "ppc64le",
+        other => other,
+    }
+}
+
+fn cache_path(name: &str) -> io::Result<PathBuf> {
+    Ok(paths::cache_dir()?.join(ARCH_DIR).join(name))
+}
+
+// a box's image architecture, kept as "image\tarch" so a box recreated from another image
+// under the same name is asked again; None when the manager cannot tell
+pub fn of(dbx: &DistroboxInstance) -> Option<String> {
+    let path = cache_path(&dbx.name).ok()?;
+    if let Ok(text) = fs::read_to_string(&path) {
+        if let Some((image, arch)) = text.trim_end().split_once('\t') {
+            if dbx.image.is_empty() || image == dbx.image {
+                return Some(arch.to_string());
+            }
+        }
+    }
+    let arch = distrobox::image_arch(dbx).ok()?;
+    if config::writable().is_ok() {
+        if let Some(dir) = path.parent() {
+            let _ = fs::create_dir_all(dir);
+        }
+        let _ = fs::write(&path, format!("{}\t{}", dbx.image, arch));
+    }
+    Some(arch)
+}
+
+// whether running in this box goes through qemu-user; unknown counts as native
+pub fn emulated(dbx: &DistroboxInstance) -> bool {
+    of(dbx).is_some_and(|arch| arch != host())
+}
+
+// from what is on disk alone, for paths that never list the boxes
+pub fn known_emulated(name: &str) -> bool {
+    cache_path(name)
+        .and_then(fs::read_to_string)
+        .ok()
+        .and_then(|text| Some(text.trim_end().split_once('\t')?.1 != host()))
+        .unwrap_or(false)
+}
+
+// under opt-in, whether `command` must stay out of box `name`
+pub fn barred(config: &Config, command: &str, name: &str) -> bool {
+    policy(config) == Policy::OptIn && !opted_in(config, command) && known_emulated(name)
+}
+
+pub fn remove(name: &str) -> io::Result<()> {
+    match fs::remove_file(cache_path(name)?) {
+        Err(e) if e.kind() != ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+pub fn clear() -> io::Result<()> {
+    match fs::remove_dir_all(paths::cache_dir()?.join(ARCH_DIR)) {
+        Err(e) if e.kind() != ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,183 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+
+// builtins that are not also shipped as standalone programs, so probing boxes for them is pointless
+const BASH: &[&str] = &[
+    "alias",
+    "bind",
+    "builtin",
+    "caller",
+    "cd",
+    "declare",
+    "disown",
+    "enable",
+    "eval",
+    "exec",
+    "exit",
+    "export",
+    "fc",
+    "history",
+    "jobs",
+    "let",
+    "local",
+    "logout",
+    "mapfile",
+    "popd",
+    "pushd",
+    "readarray",
+    "readonly",
+    "return",
+    "set",
+    "shift",
+    "shopt",
+    "source",
+    "suspend",
+    "trap",
+    "typeset",
+    "ulimit",
+    "umask",
+    "unalias",
+    "unset",
+];
+const ZSH: &[&str] = &[
+    "alias",
+    "autoload",
+    "bindkey",
+    "builtin",
+    "cd",
+    "declare",
+    "dirs",
+    "disown",
+    "emulate",
+    "eval",
+    "exec",
+    "exit",
+    "export",
+    "fc",
+    "history",
+    "jobs",
+    "let",
+    "local",
+    "logout",
+    "popd",
+    "pushd",
+    "readonly",
+    "rehash",
+    "return",
+    "set",
+    "setopt",
+    "shift",
+    "source",
+    "trap",
+    "typeset",
+    "ulimit",
+    "umask",
+    "unalias",
+    "unfunction",
+    "unset",
+    "unsetopt",
+    "zle",
+    "zmodload",
+    "zstyle",
+];
+const FISH: &[&str] = &[
+    "abbr",
+    "and",
+    "argparse",
+    "begin",
+    "bind",
+    "block",
+    "builtin",
+    "cd",
+    "commandline",
+    "complete",
+    "contains",
+    "emit",
+    "eval",
+    "exec",
+    "exit",
+    "funced",
+    "funcsave",
+    "functions",
+    "not",
+    "or",
+    "read",
+    "return",
+    "set",
+    "set_color",
+    "source",
+    "status",
+    "string",
+    "ulimit",
+];
+const SH: &[&str] = &[
+    "alias", "cd", "eval", "exec", "exit", "export", "local", "readonly", "return", "set", "shift",
+    "trap", "ulimit", "umask", "unalias", "unset",
+];
+const SHELLS: [(&str, &[&str]); 4] = [("bash", BASH), ("zsh", ZSH), ("fish", FISH), ("sh", SH)];
+// names that are aliases in most distributions' default rc files
+const ALIASES: [(&str, &str); 4] = [
+    ("ll", "ls -l"),
+    ("la", "ls -A"),
+    ("l", "ls -CF"),
+    ("l.", "ls -d .*"),
+];
+
+pub enum Explanation {
+    // a builtin of the shell that invoked us, which can only run inside that shell
+    Builtin(String),
+    Alias(&'static str),
+}
+
+// the shell that invoked the handler: our parent process, falling back to $SHELL
+pub fn invoking_shell() -> Option<String> {
+    let from_proc = fs::read_to_string("/proc/self/stat").ok().and_then(|stat| {
+        // the command name is parenthesised and may contain spaces, the ppid follows it
+        let ppid = stat
+            .rsplit_once(')')?
+            .1
+            .split_whitespace()
+            .nth(1)?
+            .to_string();
+        fs::read_to_string(format!("/proc/{}/comm", ppid)).ok()
+    });
+    let name = match from_proc {
+        Some(comm) => comm.trim().trim_start_matches('-').to_string(),
+        None => env::var("SHELL").ok()?,
+    };
+    let base = Path::new(&name).file_name()?.to_string_lossy().into_owned();
+    // dash, ash, ksh and friends share the POSIX set
+    let normalized = match base.as_str() {
+        "dash" | "ash" | "ksh" | "mksh" | "busybox" => "sh".to_string(),
+        _ => base,
+    };
+    SHELLS
+        .iter()
+        .any(|(shell, _)| *shell == normalized)
+        .then_some(normalized)
+}
+
+// reasons not to bother probing boxes at all
+pub fn explain(command: &str) -> Option<Explanation> {
+    if let Some(shell) = invoking_shell() {
+        let own = SHELLS.iter().find(|(s, _)| *s == shell).map(|(_, b)| *b);
+        if own.is_some_and(|builtins| builtins.contains(&command)) {
+            return Some(Explanation::Builtin(shell));
+        }
+    }
+    ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == command)
+        .map(|(_, expansion)| Explanation::Alias(expansion))
+}
+
+// a builtin of some other shell (e.g. a bash-ism typed into fish); a program of the same name
+// may still exist, so this only serves as a hint once probing came up empty
+pub fn foreign_builtin(command: &str) -> Option<&'static str> {
+    let shell = invoking_shell();
+    SHELLS
+        .iter()
+        .find(|(s, builtins)| Some(*s) != shell.as_deref() && builtins.contains(&command))
+        .map(|(s, _)| *s)
+}
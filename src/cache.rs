@@ -0,0 +1,175 @@
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::config;
+use crate::paths;
+
+// how long "not found anywhere" is trusted before probing again
+const NEGATIVE_TTL_SECS: u64 = 600;
+const CACHE_FILE: &str = "resolutions";
+
+#[derive(Clone, PartialEq, Eq)]
+pub enum Resolution {
+    Found(String),
+    NotFound,
+}
+
+#[derive(Clone)]
+pub struct CacheEntry {
+    pub command: String,
+    pub resolution: Resolution,
+    pub timestamp: u64,
+}
+
+impl CacheEntry {
+    pub fn is_fresh(&self) -> bool {
+        match self.resolution {
+            Resolution::Found(_) => true,
+            Resolution::NotFound => now().saturating_sub(self.timestamp) < NEGATIVE_TTL_SECS,
+        }
+    }
+}
+
+pub struct Cache {
+    // None when there is nowhere to persist to, e.g. HOME is unset
+    path: Option<PathBuf>,
+    pub entries: Vec<CacheEntry>,
+}
+
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+impl Cache {
+    pub fn load() -> io::Result<Cache> {
+        let path = paths::cache_dir()?.join(CACHE_FILE);
+        let contents = match fs::read_to_string(&path) {
+            Ok(s) => s,
+            Err(e) if e.kind() == ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e),
+        };
+        let mut entries = vec![];
+        for line in contents.lines() {
+            // format: command<TAB>box (empty when not found)<TAB>timestamp
+            let mut fields = line.split('\t');
+            let (Some(command), Some(dbx), Some(ts)) =
+                (fields.next(), fields.next(), fields.next())
+            else {
+                continue; // skip malformed lines rather than failing the handler
+            };
+            let Ok(timestamp) = ts.parse() else {
+                continue;
+            };
+            entries.push(CacheEntry {
+                command: command.to_string(),
+                resolution: if dbx.is_empty() {
+                    Resolution::NotFound
+                } else {
+                    Resolution::Found(dbx.to_string())
+                },
+                timestamp,
+            });
+        }
+        Ok(Cache {
+            path: Some(path),
+            entries,
+        })
+    }
+
+    pub fn empty() -> Cache {
+        Cache {
+            path: paths::cache_dir().ok().map(|d| d.join(CACHE_FILE)),
+            entries: vec![],
+        }
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        config::writable()?;
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut out = String::new();
+        for entry in &self.entries {
+            let dbx = match &entry.resolution {
+                Resolution::Found(name) => name.as_str(),
+                Resolution::NotFound => "",
+            };
+            out.push_str(&format!(
+                "{}\t{}\t{}\n",
+                entry.command, dbx, entry.timestamp
+            ));
+        }
+        // write to a temp file first so a concurrent handler never reads half a cache
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, out)?;
+        fs::rename(tmp, path)
+    }
+
+    // take in what other processes wrote since, `cache clear` and `forget` included
+    #[cfg(feature = "daemon")]
+    pub fn reload(&mut self) {
+        if let Ok(saved) = Cache::load() {
+            *self = saved;
+        }
+    }
+
+    pub fn lookup(&self, command: &str) -> Option<&CacheEntry> {
+        self.entries
+            .iter()
+            .find(|e| e.command == command && e.is_fresh())
+    }
+
+    pub fn record(&mut self, command: &str, resolution: Resolution) {
+        self.entries.retain(|e| e.command != command);
+        self.entries.push(CacheEntry {
+            command: command.to_string(),
+            resolution,
+            timestamp: now(),
+        });
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub fn knows_box(&self, name: &str) -> bool {
+        self.entries
+            .iter()
+            .any(|e| e.resolution == Resolution::Found(name.to_string()))
+    }
+
+    // returns the number of entries removed
+    pub fn invalidate_command(&mut self, command: &str) -> usize {
+        let before = self.entries.len();
+        self.entries.retain(|e| e.command != command);
+        before - self.entries.len()
+    }
+
+    // drop entries last confirmed more than `max_age` seconds ago
+    pub fn prune(&mut self, max_age: u64) -> usize {
+        let before = self.entries.len();
+        let now = now();
+        self.entries
+            .retain(|e| now.saturating_sub(e.timestamp) <= max_age);
+        before - self.entries.len()
+    }
+
+    pub fn invalidate_box(&mut self, name: &str) -> usize {
+        let before = self.entries.len();
+        self.entries.retain(|e| match &e.resolution {
+            Resolution::Found(dbx) => dbx != name,
+            // negative entries claim the command is missing from every box,
+            // including this one, so they go stale with it
+            Resolution::NotFound => false,
+        });
+        before - self.entries.len()
+    }
+}
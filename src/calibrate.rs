@@ -0,0 +1,130 @@
+use std::process::exit;
+
+use crate::cache::{Cache, Resolution};
+use crate::color::{self, Style};
+use crate::config::{self, Value};
+use crate::distrobox::{get_boxes, DistroboxInstance};
+use crate::index;
+use crate::pins::{self, Scope};
+
+// boxes ordered by how many of the remembered resolutions they served; those that served
+// none keep their current order behind them
+fn proposed_order(boxes: &[DistroboxInstance], served: &[(String, usize)]) -> Vec<String> {
+    let count = |name: &str| {
+        served
+            .iter()
+            .find(|(n, _)| n == name)
+            .map_or(0, |(_, c)| *c)
+    };
+    let mut order: Vec<&DistroboxInstance> = boxes.iter().collect();
+    // stable, so ties stay in priority order
+    order.sort_by_key(|b| std::cmp::Reverse(count(&b.name)));
+    order.into_iter().map(|b| b.name.clone()).collect()
+}
+
+// commands that, searched in `order`, would land in an earlier box than the one that served
+// them; only the box indexes can tell, so commands of unindexed boxes are left alone
+fn needed_pins(order: &[String], found: &[(String, String)]) -> Vec<(String, String)> {
+    found
+        .iter()
+        .filter(|(command, name)| {
+            let providers = index::boxes_providing(command);
+            let first = order.iter().position(|n| providers.contains(n));
+            let used = order.iter().position(|n| n == name);
+            matches!((first, used), (Some(first), Some(used)) if first < used)
+        })
+        .cloned()
+        .collect()
+}
+
+// `calibrate [--apply]`: propose a box_order and pins from what the cache has seen resolve
+pub fn calibrate_command(args: &[String]) {
+    let apply = match args {
+        [] => false,
+        [flag] if flag == "--apply" => true,
+        _ => {
+            eprintln!("Usage: distrobox-cnf-handler calibrate [--apply]");
+            exit(1);
+        }
+    };
+    let mut boxes = match get_boxes() {
+        Ok(boxes) => boxes,
+        Err(e) => {
+            eprintln!("Cannot get boxes: {:?}", e);
+            exit(2);
+        }
+    };
+    boxes.sort_by_key(|b| b.priority);
+    let cache = Cache::load().unwrap_or_else(|_| Cache::empty());
+    let found: Vec<(String, String)> = cache
+        .entries
+        .iter()
+        .filter_map(|e| match &e.resolution {
+            Resolution::Found(name) if boxes.iter().any(|b| &b.name == name) => {
+                Some((e.command.clone(), name.clone()))
+            }
+            _ => None,
+        })
+        .collect();
+    if found.is_empty() {
+        println!("Nothing has been resolved yet, so there is nothing to go by");
+        return;
+    }
+    let served: Vec<(String, usize)> = boxes
+        .iter()
+        .map(|b| {
+            let n = found.iter().filter(|(_, name)| name == &b.name).count();
+            (b.name.clone(), n)
+        })
+        .collect();
+    println!("Commands each box served:");
+    for (name, n) in &served {
+        println!("  {:<20} {}", color::out(name, Style::BoxName), n);
+    }
+    let current: Vec<String> = boxes.iter().map(|b| b.name.clone()).collect();
+    let order = proposed_order(&boxes, &served);
+    let pins = needed_pins(&order, &found);
+    if order == current && pins.is_empty() {
+        println!("The current order already fits how commands resolve");
+        return;
+    }
+    if order != current {
+        println!(
+            "Proposed box_order: {} (now {})",
+            order.join(", "),
+            current.join(", ")
+        );
+    }
+    if !pins.is_empty() {
+        println!("Pins for commands an earlier box also has:");
+        for (command, name) in &pins {
+            println!(
+                "  {} -> {}",
+                color::out(command, Style::Command),
+                color::out(name, Style::BoxName)
+            );
+        }
+    }
+    if !apply {
+        println!("Run calibrate --apply to use these");
+        return;
+    }
+    let mut failed = false;
+    if order != current {
+        let list = Value::List(order.into_iter().map(Value::Str).collect());
+        if let Err(e) = config::set("box_order", list) {
+            eprintln!("Cannot set box_order: {}", e);
+            failed = true;
+        }
+    }
+    for (command, name) in &pins {
+        if let Err(e) = pins::remember(command, name, Scope::Global) {
+            eprintln!("Cannot pin {}: {}", command, e);
+            failed = true;
+        }
+    }
+    if failed {
+        exit(2);
+    }
+    println!("{}", color::out("Applied", Style::Good));
+}
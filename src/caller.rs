@@ -0,0 +1,183 @@
+use std::env;
+use std::fs;
+
+use crate::config::Config;
+
+// DISTROBOX_CNF_NON_INTERACTIVE=resolve (or decline) overrides non_interactive for one run
+const POLICY_VAR: &str = "DISTROBOX_CNF_NON_INTERACTIVE";
+const SCHEDULERS: [&str; 4] = ["cron", "crond", "anacron", "fcron"];
+const SHELLS: [&str; 7] = ["bash", "zsh", "fish", "sh", "dash", "ksh", "mksh"];
+
+// far enough for make -> sh -> handler and a few wrappers around it
+const MAX_DEPTH: usize = 16;
+
+// what callers.<process> says for the nearest ancestor it names, e.g.
+//   [callers.make]
+//   box = "toolchain"
+//   pick = false
+pub struct Rule {
+    pub process: String,
+    // the box its commands go to, tried first like a pin
+    pub name: Option<String>,
+    // whether the picker may ask, when pick = true allows it at all
+    #[cfg(feature = "tui")]
+    pub pick: Option<bool>,
+}
+
+// the command name and parent of a process, from /proc/<pid>/stat
+fn stat(pid: u32) -> Option<(String, u32)> {
+    let stat = fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    let (head, rest) = stat.rsplit_once(')')?;
+    let comm = head.split_once('(')?.1.to_string();
+    // ppid is field 4, the 2nd after the command name
+    let ppid = rest.split_whitespace().nth(1)?.parse().ok()?;
+    Some((comm, ppid))
+}
+
+// the processes the handler runs under, nearest first
+fn ancestors() -> Vec<String> {
+    let mut names = vec![];
+    let mut pid = std::os::unix::process::parent_id();
+    while pid > 1 && names.len() < MAX_DEPTH {
+        let Some((comm, ppid)) = stat(pid) else {
+            break;
+        };
+        names.push(comm);
+        pid = ppid;
+    }
+    names
+}
+
+// the rule of the nearest ancestor one is written for
+pub fn rule(config: &Config) -> Option<Rule> {
+    ancestors().into_iter().find_map(|process| {
+        let key = format!("callers.{}", process);
+        let name = config.get_str(&format!("{}.box", key)).map(str::to_string);
+        let pick = config.get_bool(&format!("{}.pick", key));
+        (name.is_some() || pick.is_some()).then_some(Rule {
+            process,
+            name,
+            #[cfg(feature = "tui")]
+            pick,
+        })
+    })
+}
+
+// shell options whose value is the next argument, not a script: VS Code's terminal is
+// `bash --init-file <integration script>`, for one
+const VALUED: [&str; 8] = [
+    "--rcfile",
+    "--init-file",
+    "-o",
+    "+o",
+    "-O",
+    "+O",
+    "-C",
+    "--init-command",
+];
+
+// whether the process runs a script or a -c string rather than reading a person's commands
+fn scripted(pid: u32) -> bool {
+    let Ok(cmdline) = fs::read(format!("/proc/{}/cmdline", pid)) else {
+        return false;
+    };
+    let args: Vec<String> = cmdline
+        .split(|b| *b == 0)
+        .filter(|a| !a.is_empty())
+        .map(|a| String::from_utf8_lossy(a).into_owned())
+        .collect();
+    runs_script(&args)
+}
+
+// a shell's own argv; what follows the script's name are the script's arguments
+fn runs_script(args: &[String]) -> bool {
+    let Some((first, rest)) = args.split_first() else {
+        return false;
+    };
+    // a login shell's argv[0] starts with '-'
+    if first.starts_with('-') {
+        return false;
+    }
+    let mut rest = rest.iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "-i" | "--interactive" => return false,
+            "-c" | "--command" => return true,
+            flag if VALUED.contains(&flag) => {
+                rest.next();
+            }
+            flag if flag.starts_with(['-', '+']) => {}
+            _ => return true,
+        }
+    }
+    false
+}
+
+// why nobody is there to see a box take over, if that is the case: no terminal at all,
+// a scheduler among the parents, or a shell running a script
+fn unattended() -> Option<&'static str> {
+    let own = fs::read_to_string("/proc/self/stat").ok()?;
+    // tty_nr is field 7, the 5th after the command name
+    if own.rsplit_once(')')?.1.split_whitespace().nth(4) == Some("0") {
+        return Some("no terminal");
+    }
+    if ancestors().iter().any(|a| SCHEDULERS.contains(&a.as_str())) {
+        return Some("under cron");
+    }
+    let parent = std::os::unix::process::parent_id();
+    match stat(parent) {
+        Some((comm, _)) if SHELLS.contains(&comm.as_str()) && scripted(parent) => {
+            Some("from a script")
+        }
+        _ => None,
+    }
+}
+
+// the reason not to resolve at all: running commands from boxes behind a script's back is
+// more surprising than helpful, unless non_interactive = "resolve" asks for it, or a
+// [callers.<process>] rule, such as one for cron, says what to do for that caller
+pub fn declined(config: &Config) -> Option<&'static str> {
+    let policy = env::var(POLICY_VAR).ok();
+    let policy = policy
+        .as_deref()
+        .or_else(|| config.get_str("non_interactive"));
+    if policy == Some("resolve") {
+        return None;
+    }
+    unattended().filter(|_| rule(config).is_none())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn argv(line: &str) -> Vec<String> {
+        line.split(' ').map(String::from).collect()
+    }
+
+    #[test]
+    fn scripts_and_people() {
+        for (line, script) in [
+            ("-bash", false),
+            ("bash", false),
+            ("bash -i", false),
+            ("bash --login", false),
+            (
+                "bash --init-file /usr/share/code/shellIntegration-bash.sh",
+                false,
+            ),
+            ("bash --rcfile x", false),
+            ("bash -O extglob", false),
+            ("fish -C x", false),
+            ("fish --init-command x", false),
+            ("zsh -o vi", false),
+            ("bash -c make", true),
+            ("fish --command make", true),
+            ("sh ./build.sh", true),
+            ("bash -e ./build.sh -i", true),
+            ("bash --rcfile x ./build.sh", true),
+        ] {
+            assert_eq!(runs_script(&argv(line)), script, "{}", line);
+        }
+    }
+}
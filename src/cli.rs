@@ -0,0 +1,142 @@
+use std::path::PathBuf;
+
+use crate::protocol::Protocol;
+use crate::trace::TraceTarget;
+
+// flags understood by the handler itself; they must come before the command
+#[derive(Default)]
+pub struct Options {
+    pub host: bool,
+    pub quiet: bool,
+    pub trace: Option<TraceTarget>,
+    pub timings: bool,
+    pub shell_protocol: Option<Protocol>,
+    // `run` only: the box to use instead of searching
+    pub box_name: Option<String>,
+    // --output json: reports as JSON on stdout
+    pub json: bool,
+    // files to bring back from a box that does not share the current directory
+    pub copy_back: Option<String>,
+    // set by `run`, which exits with the command's status rather than the handler's
+    pub run: bool,
+}
+
+// split leading handler flags off, returning them together with the wrapped command line
+pub fn parse(args: Vec<String>) -> Result<(Options, Vec<String>), String> {
+    let mut opts = Options::default();
+    let mut rest = args.into_iter().peekable();
+    while let Some(arg) = rest.next_if(|a| a.starts_with('-')) {
+        match arg.as_str() {
+            "--" => break,
+            "--host" => opts.host = true,
+            "--quiet" | "-q" => opts.quiet = true,
+            "--timings" => opts.timings = true,
+            "--trace" => match rest.next().as_deref() {
+                Some("json") => opts.trace = Some(TraceTarget::Stderr),
+                other => {
+                    return Err(format!(
+                        "Unknown trace format {}",
+                        other.unwrap_or("(none)")
+                    ))
+                }
+            },
+            "--shell-protocol" => {
+                let shell = rest.next().unwrap_or_default();
+                opts.shell_protocol = Some(
+                    Protocol::parse(&shell).ok_or_else(|| format!("Unknown shell {}", shell))?,
+                );
+            }
+            "--box" => opts.box_name = Some(rest.next().ok_or("--box needs a box name")?),
+            "--output" => match rest.next().as_deref() {
+                Some("json") => opts.json = true,
+                Some("text") => opts.json = false,
+                other => {
+                    return Err(format!(
+                        "Unknown output format {}",
+                        other.unwrap_or("(none)")
+                    ))
+                }
+            },
+            "--copy-back" => opts.copy_back = Some(rest.next().ok_or("--copy-back needs a glob")?),
+            "--trace-file" => {
+                let path = rest.next().ok_or("--trace-file needs a path")?;
+                opts.trace = Some(TraceTarget::File(PathBuf::from(path)));
+            }
+            // hooks written before they passed `--` hand over the command right after
+            // the protocol; a command named like a flag is the command then, not a mistake
+            _ if opts.shell_protocol.is_some() => {
+                return Ok((opts, std::iter::once(arg).chain(rest).collect()))
+            }
+            flag => return Err(format!("Unknown flag {}", flag)),
+        }
+    }
+    Ok((opts, rest.collect()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parsed(line: &str) -> Result<(Options, Vec<String>), String> {
+        parse(line.split_whitespace().map(String::from).collect())
+    }
+
+    fn rest(line: &str) -> Vec<String> {
+        parsed(line).expect(line).1
+    }
+
+    #[test]
+    fn flags_before_the_command() {
+        let (opts, command) = parsed("--host -q --timings ls -l").expect("parses");
+        assert!(opts.host && opts.quiet && opts.timings);
+        assert_eq!(command, ["ls", "-l"]);
+        let (opts, command) =
+            parsed("--box arch --copy-back *.pdf --output json make all").expect("parses");
+        assert_eq!(opts.box_name.as_deref(), Some("arch"));
+        assert_eq!(opts.copy_back.as_deref(), Some("*.pdf"));
+        assert!(opts.json);
+        assert_eq!(command, ["make", "all"]);
+        assert!(matches!(
+            parsed("--trace-file /tmp/t.json x")
+                .expect("parses")
+                .0
+                .trace,
+            Some(TraceTarget::File(_))
+        ));
+        assert!(parsed("--trace json x").expect("parses").0.trace.is_some());
+        // flags after the command are the command's
+        assert_eq!(rest("grep -q --host x"), ["grep", "-q", "--host", "x"]);
+        assert_eq!(rest(""), Vec::<String>::new());
+    }
+
+    #[test]
+    fn a_command_named_like_a_flag() {
+        assert_eq!(rest("-- -i"), ["-i"]);
+        assert_eq!(rest("-q -- --help x"), ["--help", "x"]);
+        assert_eq!(rest("--shell-protocol bash -- -i"), ["-i"]);
+        // hooks from before `--` was passed
+        assert_eq!(rest("--shell-protocol bash -i"), ["-i"]);
+        assert_eq!(rest("--shell-protocol zsh --help me"), ["--help", "me"]);
+        assert!(parsed("--shell-protocol bash ls")
+            .expect("parses")
+            .0
+            .shell_protocol
+            .is_some());
+    }
+
+    #[test]
+    fn mistakes() {
+        for line in [
+            "-i",
+            "--trace",
+            "--trace yaml x",
+            "--output xml x",
+            "--box",
+            "--copy-back",
+            "--trace-file",
+            "--shell-protocol tcsh x",
+        ] {
+            assert!(parsed(line).is_err(), "{}", line);
+        }
+    }
+}
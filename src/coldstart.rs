@@ -0,0 +1,124 @@
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::config::{self, Config};
+use crate::paths;
+
+const COLD_START_FILE: &str = "cold-starts";
+// how many of the latest cold starts are kept, and how many in a row must be slow
+const SAMPLES: usize = 5;
+const SLOW_IN_A_ROW: usize = 3;
+const DEFAULT_SECONDS: u64 = 10;
+
+// how long the latest cold starts of a box took, oldest first
+pub struct ColdStarts {
+    pub name: String,
+    pub millis: Vec<u64>,
+}
+
+impl ColdStarts {
+    // only a box that is slow every time, not one unlucky start, counts
+    fn slow(&self, limit: u64) -> bool {
+        self.millis.len() >= SLOW_IN_A_ROW
+            && self.millis[self.millis.len() - SLOW_IN_A_ROW..]
+                .iter()
+                .all(|ms| *ms > limit)
+    }
+
+    pub fn median(&self) -> u64 {
+        let mut sorted = self.millis.clone();
+        sorted.sort_unstable();
+        sorted.get(sorted.len() / 2).copied().unwrap_or(0)
+    }
+}
+
+fn cold_start_path() -> io::Result<PathBuf> {
+    Ok(paths::cache_dir()?.join(COLD_START_FILE))
+}
+
+// "box<TAB>ms,ms,..." lines
+pub fn load() -> Vec<ColdStarts> {
+    let Ok(text) = cold_start_path().and_then(fs::read_to_string) else {
+        return vec![];
+    };
+    text.lines()
+        .filter_map(|line| {
+            let (name, millis) = line.split_once('\t')?;
+            Some(ColdStarts {
+                name: name.to_string(),
+                millis: millis.split(',').filter_map(|ms| ms.parse().ok()).collect(),
+            })
+        })
+        .collect()
+}
+
+fn save(starts: &[ColdStarts]) -> io::Result<()> {
+    config::writable()?;
+    let path = cold_start_path()?;
+    if starts.is_empty() {
+        return match fs::remove_file(path) {
+            Err(e) if e.kind() != ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        };
+    }
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let text: String = starts
+        .iter()
+        .map(|s| {
+            let millis: Vec<String> = s.millis.iter().map(u64::to_string).collect();
+            format!("{}\t{}\n", s.name, millis.join(","))
+        })
+        .collect();
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, text)?;
+    fs::rename(tmp, path)
+}
+
+// a stopped box took this long to come up and answer
+pub fn record(name: &str, took: Duration) -> io::Result<()> {
+    let mut starts = load();
+    let at = match starts.iter().position(|s| s.name == name) {
+        Some(at) => at,
+        None => {
+            starts.push(ColdStarts {
+                name: name.to_string(),
+                millis: vec![],
+            });
+            starts.len() - 1
+        }
+    };
+    let millis = &mut starts[at].millis;
+    millis.push(u64::try_from(took.as_millis()).unwrap_or(u64::MAX));
+    if millis.len() > SAMPLES {
+        millis.drain(..millis.len() - SAMPLES);
+    }
+    save(&starts)
+}
+
+pub fn limit_millis(config: &Config) -> u64 {
+    config
+        .get_int("cold_start.seconds")
+        .and_then(|n| u64::try_from(n).ok())
+        .unwrap_or(DEFAULT_SECONDS)
+        * 1000
+}
+
+// boxes whose init is slow every time they start
+pub fn slow(config: &Config) -> Vec<ColdStarts> {
+    let limit = limit_millis(config);
+    load().into_iter().filter(|s| s.slow(limit)).collect()
+}
+
+pub fn forget(name: &str) -> io::Result<()> {
+    let mut starts = load();
+    let before = starts.len();
+    starts.retain(|s| s.name != name);
+    if starts.len() == before {
+        return Ok(());
+    }
+    save(&starts)
+}
@@ -0,0 +1,63 @@
+use std::env;
+use std::io::{self, IsTerminal};
+
+#[derive(Clone, Copy)]
+pub enum Stream {
+    Stdout,
+    Stderr,
+}
+
+#[derive(Clone, Copy)]
+pub enum Style {
+    BoxName,
+    Command,
+    Good,
+    Bad,
+    Dim,
+}
+
+impl Style {
+    fn code(self) -> &'static str {
+        match self {
+            Style::BoxName => "1;36",
+            Style::Command => "1",
+            Style::Good => "32",
+            Style::Bad => "31",
+            Style::Dim => "2",
+        }
+    }
+}
+
+// https://no-color.org and https://bixense.com/clicolors
+pub fn enabled(stream: Stream) -> bool {
+    if env::var("NO_COLOR").is_ok_and(|v| !v.is_empty()) {
+        return false;
+    }
+    if env::var("CLICOLOR_FORCE").is_ok_and(|v| !v.is_empty() && v != "0") {
+        return true;
+    }
+    if env::var("TERM").is_ok_and(|t| t == "dumb") {
+        return false;
+    }
+    match stream {
+        Stream::Stdout => io::stdout().is_terminal(),
+        Stream::Stderr => io::stderr().is_terminal(),
+    }
+}
+
+pub fn paint(text: &str, style: Style, stream: Stream) -> String {
+    if enabled(stream) {
+        format!("\x1b[{}m{}\x1b[0m", style.code(), text)
+    } else {
+        text.to_string()
+    }
+}
+
+// shorthands for the common case of decorating a diagnostic or a listing
+pub fn err(text: &str, style: Style) -> String {
+    paint(text, style, Stream::Stderr)
+}
+
+pub fn out(text: &str, style: Style) -> String {
+    paint(text, style, Stream::Stdout)
+}
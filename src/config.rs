@@ -0,0 +1,547 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Error, ErrorKind};
+use std::path::PathBuf;
+use std::process::exit;
+use std::sync::OnceLock;
+
+use crate::paths;
+
+const CONFIG_FILE: &str = "config.toml";
+// for locked-down machines: keep to the containers that exist, never reach the network
+const OFFLINE_VAR: &str = "DISTROBOX_CNF_OFFLINE";
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Str(String),
+    Int(i64),
+    Bool(bool),
+    List(Vec<Value>),
+}
+
+// a deliberately small TOML subset: [tables], key = value, strings, integers,
+// booleans and single-line arrays; keys are stored flattened as "table.key"
+#[derive(Default)]
+pub struct Config {
+    values: BTreeMap<String, Value>,
+}
+
+pub fn config_path() -> io::Result<PathBuf> {
+    Ok(paths::config_dir()?.join(CONFIG_FILE))
+}
+
+// the environment wins either way, so one shell can go offline or back online
+pub fn offline(config: &Config) -> bool {
+    match std::env::var(OFFLINE_VAR).as_deref() {
+        Ok("1" | "true" | "yes") => true,
+        Ok("0" | "false" | "no") => false,
+        _ => config.get_bool("offline") == Some(true),
+    }
+}
+
+// read_only = true, or a build with the read-only feature, for shared machines whose
+// policy allows looking but not touching: no cache, history or other state is written,
+// and only boxes that are already running are searched or entered
+pub fn read_only() -> bool {
+    static READ_ONLY: OnceLock<bool> = OnceLock::new();
+    cfg!(feature = "read-only")
+        || *READ_ONLY
+            .get_or_init(|| Config::load().is_ok_and(|c| c.get_bool("read_only") == Some(true)))
+}
+
+// for the writers of state to bail out with under read_only
+pub fn writable() -> io::Result<()> {
+    if read_only() {
+        return Err(Error::new(
+            ErrorKind::PermissionDenied,
+            "read_only is set, the handler keeps its state as it is",
+        ));
+    }
+    Ok(())
+}
+
+impl Config {
+    pub fn load() -> io::Result<Config> {
+        match fs::read_to_string(config_path()?) {
+            Ok(s) => Config::parse(&s),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Config::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn parse(text: &str) -> io::Result<Config> {
+        let mut values = BTreeMap::new();
+        let mut table = String::new();
+        for (n, raw) in text.lines().enumerate() {
+            let line = strip_comment(raw).trim();
+            let bad = |what: &str| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!("{} line {}: {}", CONFIG_FILE, n + 1, what),
+                )
+            };
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(header) = line.strip_prefix('[') {
+                let header = header
+                    .strip_suffix(']')
+                    .ok_or_else(|| bad("unclosed table"))?;
+                table = parse_key(header).ok_or_else(|| bad("bad table name"))?;
+                continue;
+            }
+            let (key, value) = split_key(line).ok_or_else(|| bad("expected key = value"))?;
+            let key = parse_key(key).ok_or_else(|| bad("bad key"))?;
+            let value = parse_value(value.trim()).ok_or_else(|| bad("bad value"))?;
+            let full = if table.is_empty() {
+                key
+            } else {
+                format!("{}.{}", table, key)
+            };
+            values.insert(full, value);
+        }
+        Ok(Config { values })
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.values.get(key)
+    }
+
+    pub fn get_bool(&self, key: &str) -> Option<bool> {
+        match self.get(key) {
+            Some(Value::Bool(b)) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn get_str(&self, key: &str) -> Option<&str> {
+        match self.get(key) {
+            Some(Value::Str(s)) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn get_int(&self, key: &str) -> Option<i64> {
+        match self.get(key) {
+            Some(Value::Int(i)) => Some(*i),
+            _ => None,
+        }
+    }
+
+    // the keys of a table, without the table's name
+    pub fn keys_under(&self, table: &str) -> Vec<&str> {
+        let prefix = format!("{}.", table);
+        self.values
+            .range(prefix.clone()..)
+            .map_while(|(full, _)| full.strip_prefix(&prefix))
+            .collect()
+    }
+
+    // the string items of a list; anything else in it is ignored
+    pub fn get_list(&self, key: &str) -> Option<Vec<String>> {
+        match self.get(key) {
+            Some(Value::List(items)) => Some(
+                items
+                    .iter()
+                    .filter_map(|v| match v {
+                        Value::Str(s) => Some(s.clone()),
+                        _ => None,
+                    })
+                    .collect(),
+            ),
+            _ => None,
+        }
+    }
+}
+
+// a '#' outside of a string starts a comment
+fn strip_comment(line: &str) -> &str {
+    let mut in_str = false;
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+        match c {
+            '\\' if in_str => escaped = true,
+            '"' => in_str = !in_str,
+            '#' if !in_str => return &line[..i],
+            _ => {}
+        }
+    }
+    line
+}
+
+// dotted keys with optionally quoted segments, e.g. boxes."my box".priority
+fn parse_key(key: &str) -> Option<String> {
+    let mut parts = vec![];
+    for part in split_outside_quotes(key.trim(), '.') {
+        let part = part.trim();
+        let part = match part.strip_prefix('"') {
+            Some(quoted) => unescape(quoted.strip_suffix('"')?)?,
+            None => part.to_string(),
+        };
+        if part.is_empty() {
+            return None;
+        }
+        parts.push(part);
+    }
+    Some(parts.join("."))
+}
+
+// `key = value` split at the first '=' that is not in a quoted key
+fn split_key(line: &str) -> Option<(&str, &str)> {
+    let key = split_outside_quotes(line, '=')[0];
+    Some((key, line[key.len()..].strip_prefix('=')?))
+}
+
+fn split_outside_quotes(s: &str, sep: char) -> Vec<&str> {
+    let mut parts = vec![];
+    let mut in_str = false;
+    let mut escaped = false;
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' && in_str {
+            escaped = true;
+        } else if c == '"' {
+            in_str = !in_str;
+        } else if c == sep && !in_str {
+            parts.push(&s[start..i]);
+            start = i + c.len_utf8();
+        }
+    }
+    parts.push(&s[start..]);
+    parts
+}
+
+fn parse_value(value: &str) -> Option<Value> {
+    if let Some(inner) = value.strip_prefix('[') {
+        let inner = inner.strip_suffix(']')?.trim();
+        if inner.is_empty() {
+            return Some(Value::List(vec![]));
+        }
+        let items = split_outside_quotes(inner, ',')
+            .into_iter()
+            .map(|v| v.trim())
+            .filter(|v| !v.is_empty()) // trailing comma
+            // lists of lists are outside the subset, and would only recurse on bad input
+            .map(|v| {
+                if v.starts_with('[') {
+                    None
+                } else {
+                    parse_value(v)
+                }
+            })
+            .collect::<Option<Vec<Value>>>()?;
+        return Some(Value::List(items));
+    }
+    if let Some(inner) = value.strip_prefix('"') {
+        return unescape(inner.strip_suffix('"')?).map(Value::Str);
+    }
+    match value {
+        "true" => Some(Value::Bool(true)),
+        "false" => Some(Value::Bool(false)),
+        _ => value.replace('_', "").parse().ok().map(Value::Int),
+    }
+}
+
+fn unescape(s: &str) -> Option<String> {
+    let mut out = String::new();
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next()? {
+            'n' => out.push('\n'),
+            't' => out.push('\t'),
+            '"' => out.push('"'),
+            '\\' => out.push('\\'),
+            _ => return None,
+        }
+    }
+    Some(out)
+}
+
+// the value as it would be written in the file
+fn render(value: &Value) -> String {
+    match value {
+        Value::Str(s) => format!("\"{}\"", escape(s)),
+        Value::Int(i) => i.to_string(),
+        Value::Bool(b) => b.to_string(),
+        Value::List(items) => {
+            let items: Vec<String> = items.iter().map(render).collect();
+            format!("[{}]", items.join(", "))
+        }
+    }
+}
+
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace('\t', "\\t")
+}
+
+// one segment of a dotted key, quoted unless it is a bare key
+fn render_key(key: &str) -> String {
+    key.split('.')
+        .map(|part| {
+            if part
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+            {
+                part.to_string()
+            } else {
+                format!("\"{}\"", escape(part))
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(".")
+}
+
+// `text` with `key` set to `value`, leaving every other line, comments included, as it
+// was: an existing key is rewritten in place, a new one joins its table or starts one
+fn set_in(text: &str, key: &str, value: &Value) -> Vec<String> {
+    let mut lines: Vec<String> = text.lines().map(String::from).collect();
+    let mut headers: Vec<(usize, String)> = vec![];
+    let mut table = String::new();
+    for (n, raw) in lines.iter().enumerate() {
+        let line = strip_comment(raw).trim();
+        if let Some(header) = line.strip_prefix('[').and_then(|h| h.strip_suffix(']')) {
+            table = parse_key(header).unwrap_or_default();
+            headers.push((n, table.clone()));
+            continue;
+        }
+        let Some(own) = split_key(line).and_then(|(k, _)| parse_key(k)) else {
+            continue;
+        };
+        let full = match table.is_empty() {
+            true => own,
+            false => format!("{}.{}", table, own),
+        };
+        if full == key {
+            let (lhs, _) = split_key(raw).unwrap_or_default();
+            let comment = &raw[strip_comment(raw).len()..];
+            let mut line = format!("{}= {}", lhs, render(value));
+            if !comment.is_empty() {
+                line = format!("{} {}", line, comment);
+            }
+            lines[n] = line;
+            return lines;
+        }
+    }
+    let section = headers
+        .iter()
+        .enumerate()
+        .filter(|(_, (_, name))| key.starts_with(&format!("{}.", name)))
+        .max_by_key(|(_, (_, name))| name.len());
+    let line = |own: &str| format!("{} = {}", render_key(own), render(value));
+    match (section, key.rsplit_once('.')) {
+        (Some((i, (start, name))), _) => {
+            let end = headers.get(i + 1).map_or(lines.len(), |(n, _)| *n);
+            let last = (start + 1..end)
+                .rev()
+                .find(|n| !strip_comment(&lines[*n]).trim().is_empty())
+                .unwrap_or(*start);
+            lines.insert(last + 1, line(&key[name.len() + 1..]));
+        }
+        (None, Some((table, own))) if !headers.is_empty() => {
+            if lines.last().is_some_and(|l| !l.trim().is_empty()) {
+                lines.push(String::new());
+            }
+            lines.push(format!("[{}]", render_key(table)));
+            lines.push(line(own));
+        }
+        // top-level keys have to come before the first table
+        (None, _) => {
+            let first = headers.first().map_or(lines.len(), |(n, _)| *n);
+            let at = (0..first)
+                .rev()
+                .find(|n| !strip_comment(&lines[*n]).trim().is_empty())
+                .map_or(if headers.is_empty() { first } else { 0 }, |n| n + 1);
+            lines.insert(at, line(key));
+        }
+    }
+    lines
+}
+
+// set `key` in the config file through a temp file, so a concurrent reader sees either
+// the old file or the new one; the result must still parse, or nothing is written
+pub fn set(key: &str, value: Value) -> io::Result<()> {
+    let path = config_path()?;
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e),
+    };
+    let mut out = set_in(&text, key, &value).join("\n");
+    out.push('\n');
+    if Config::parse(&out)?.get(key) != Some(&value) {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "Cannot set {} without changing what else the file means",
+                key
+            ),
+        ));
+    }
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, out)?;
+    fs::rename(tmp, path)
+}
+
+// `config get <key>` / `config set <key> <value>`; a value that is not TOML is a string
+pub fn config_command(args: &[String]) {
+    let usage = || -> ! {
+        eprintln!("Usage: distrobox-cnf-handler config get <key> | set <key> <value>");
+        exit(1);
+    };
+    match args {
+        [op, key] if op == "get" => {
+            let config = match Config::load() {
+                Ok(config) => config,
+                Err(e) => {
+                    eprintln!("Cannot read config: {}", e);
+                    exit(2);
+                }
+            };
+            let key = parse_key(key).unwrap_or_else(|| usage());
+            if let Some(value) = config.get(&key) {
+                println!("{}", render(value));
+                return;
+            }
+            // a table prints its keys
+            let prefix = format!("{}.", key);
+            let mut found = false;
+            for (full, value) in config.values.range(prefix.clone()..) {
+                let Some(own) = full.strip_prefix(&prefix) else {
+                    break;
+                };
+                println!("{} = {}", render_key(own), render(value));
+                found = true;
+            }
+            if !found {
+                exit(3);
+            }
+        }
+        [op, key, value] if op == "set" => {
+            let key = parse_key(key).unwrap_or_else(|| usage());
+            let value = parse_value(value.trim()).unwrap_or_else(|| Value::Str(value.clone()));
+            if let Err(e) = set(&key, value) {
+                eprintln!("Cannot set {}: {}", key, e);
+                exit(2);
+            }
+        }
+        _ => usage(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testgen::{Gen, CASES};
+
+    // what the parser treats specially, for inputs that get past its first checks
+    const SPECIAL: &str = "[]=\"#,.\\_-";
+
+    fn value(gen: &mut Gen, nested: bool) -> Value {
+        match gen.below(if nested { 4 } else { 3 }) {
+            0 => Value::Str(gen.text(SPECIAL, 12)),
+            1 => Value::Int(gen.next() as i64),
+            2 => Value::Bool(gen.chance(50)),
+            _ => Value::List((0..gen.below(4)).map(|_| value(gen, false)).collect()),
+        }
+    }
+
+    // a key segment; '.' is where flattened keys split, so it cannot be in one
+    fn segment(gen: &mut Gen) -> String {
+        match gen.chance(50) {
+            true => gen.word("abc_-09", 6),
+            false => gen.word(&SPECIAL.replace('.', ""), 6) + &gen.text("= ", 3),
+        }
+    }
+
+    #[test]
+    fn arbitrary_text_parses_or_is_rejected() {
+        for seed in 0..CASES {
+            let mut gen = Gen::new(seed);
+            let lines: Vec<String> = (0..gen.below(6)).map(|_| gen.text(SPECIAL, 24)).collect();
+            let text = lines.join("\n");
+            // once accepted, everything it holds can be written back and read again
+            if let Ok(config) = Config::parse(&text) {
+                for (key, value) in &config.values {
+                    let line = format!("{} = {}", render_key(key), render(value));
+                    let again = Config::parse(&line).unwrap_or_else(|e| {
+                        panic!("seed {}: {:?} from {:?}: {}", seed, line, text, e)
+                    });
+                    assert_eq!(again.get(key), Some(value), "seed {}: {:?}", seed, line);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn written_values_read_back() {
+        for seed in 0..CASES {
+            let mut gen = Gen::new(seed);
+            let table: Vec<String> = (0..gen.below(3)).map(|_| segment(&mut gen)).collect();
+            let key: Vec<String> = (0..1 + gen.below(2)).map(|_| segment(&mut gen)).collect();
+            let value = value(&mut gen, true);
+            let mut text = String::new();
+            if !table.is_empty() {
+                text = format!("[{}]  # table\n", render_key(&table.join(".")));
+            }
+            text.push_str(&format!(
+                "{} = {} # comment\n",
+                render_key(&key.join(".")),
+                render(&value)
+            ));
+            let config =
+                Config::parse(&text).unwrap_or_else(|e| panic!("seed {}: {:?}: {}", seed, text, e));
+            let full = table.into_iter().chain(key).collect::<Vec<_>>().join(".");
+            assert_eq!(config.get(&full), Some(&value), "seed {}: {:?}", seed, text);
+        }
+    }
+
+    #[test]
+    fn set_keeps_the_rest() {
+        for seed in 0..CASES / 4 {
+            let mut gen = Gen::new(seed);
+            let text = "top = 1\n[a]\nb = \"c\" # kept\n";
+            let key = format!("{}.{}", segment(&mut gen), segment(&mut gen));
+            let value = value(&mut gen, true);
+            let written = set_in(text, &key, &value).join("\n");
+            let config = Config::parse(&written)
+                .unwrap_or_else(|e| panic!("seed {}: {:?}: {}", seed, written, e));
+            assert_eq!(
+                config.get(&key),
+                Some(&value),
+                "seed {}: {:?}",
+                seed,
+                written
+            );
+            assert_eq!(
+                config.get_int("top"),
+                Some(1),
+                "seed {}: {:?}",
+                seed,
+                written
+            );
+            assert_eq!(
+                config.get_str("a.b"),
+                Some("c"),
+                "seed {}: {:?}",
+                seed,
+                written
+            );
+        }
+    }
+}
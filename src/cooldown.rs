@@ -0,0 +1,121 @@
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::PathBuf;
+
+use crate::cache::now;
+use crate::config::{self, Config};
+use crate::paths;
+
+const COOLDOWN_FILE: &str = "cooldown";
+const DEFAULT_FAILURES: u64 = 3;
+const DEFAULT_SECONDS: u64 = 600;
+
+// a box that failed to enter `failures` times in a row; it is left alone until `until`
+pub struct Failing {
+    pub name: String,
+    pub failures: u64,
+    pub until: u64,
+}
+
+impl Failing {
+    pub fn cooling(&self) -> bool {
+        self.until > now()
+    }
+}
+
+fn cooldown_path() -> io::Result<PathBuf> {
+    Ok(paths::cache_dir()?.join(COOLDOWN_FILE))
+}
+
+// "box<TAB>failures<TAB>until" lines
+pub fn load() -> Vec<Failing> {
+    let Ok(text) = cooldown_path().and_then(fs::read_to_string) else {
+        return vec![];
+    };
+    text.lines()
+        .filter_map(|line| {
+            let mut fields = line.split('\t');
+            Some(Failing {
+                name: fields.next()?.to_string(),
+                failures: fields.next()?.parse().ok()?,
+                until: fields.next()?.parse().ok()?,
+            })
+        })
+        .collect()
+}
+
+fn save(failing: &[Failing]) -> io::Result<()> {
+    config::writable()?;
+    let path = cooldown_path()?;
+    if failing.is_empty() {
+        return match fs::remove_file(path) {
+            Err(e) if e.kind() != ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        };
+    }
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let text: String = failing
+        .iter()
+        .map(|f| format!("{}\t{}\t{}\n", f.name, f.failures, f.until))
+        .collect();
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, text)?;
+    fs::rename(tmp, path)
+}
+
+pub fn cooling(name: &str) -> bool {
+    load().iter().any(|f| f.name == name && f.cooling())
+}
+
+// another failure to enter the box; returns true when that puts it on cooldown
+pub fn failed(config: &Config, name: &str) -> bool {
+    let limit = config
+        .get_int("cooldown.failures")
+        .and_then(|n| u64::try_from(n).ok())
+        .unwrap_or(DEFAULT_FAILURES);
+    let seconds = config
+        .get_int("cooldown.seconds")
+        .and_then(|n| u64::try_from(n).ok())
+        .unwrap_or(DEFAULT_SECONDS);
+    // cooldown.failures = 0 turns it off
+    if limit == 0 {
+        return false;
+    }
+    let mut failing = load();
+    let at = match failing.iter().position(|f| f.name == name) {
+        Some(at) => at,
+        None => {
+            failing.push(Failing {
+                name: name.to_string(),
+                failures: 0,
+                until: 0,
+            });
+            failing.len() - 1
+        }
+    };
+    // a box back from its cooldown gets the full count again before the next one
+    if failing[at].until != 0 && !failing[at].cooling() {
+        failing[at].failures = 0;
+        failing[at].until = 0;
+    }
+    failing[at].failures += 1;
+    let cooled = failing[at].failures >= limit;
+    if cooled {
+        failing[at].until = now() + seconds;
+    }
+    let _ = save(&failing);
+    cooled
+}
+
+// the box worked, whatever happened before
+pub fn recovered(name: &str) -> io::Result<()> {
+    let mut failing = load();
+    let before = failing.len();
+    failing.retain(|f| f.name != name);
+    if failing.len() == before {
+        return Ok(());
+    }
+    save(&failing)
+}
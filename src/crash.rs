@@ -0,0 +1,43 @@
+use std::backtrace::Backtrace;
+use std::env;
+use std::fs;
+use std::io;
+use std::panic::{self, PanicHookInfo};
+use std::path::PathBuf;
+
+use crate::cache::now;
+use crate::config;
+use crate::paths;
+
+const CRASH_DIR: &str = "crashes";
+// what a shell itself returns for a command it cannot find
+pub const CRASH_EXIT: i32 = 127;
+
+fn write_report(info: &PanicHookInfo) -> io::Result<PathBuf> {
+    config::writable()?;
+    let dir = paths::cache_dir()?.join(CRASH_DIR);
+    fs::create_dir_all(&dir)?;
+    let path = dir.join(format!("{}-{}.txt", now(), std::process::id()));
+    let args: Vec<String> = env::args().collect();
+    let report = format!(
+        "distrobox-cnf-handler {}\nargs: {:?}\n{}\n\n{}\n",
+        env!("CARGO_PKG_VERSION"),
+        args,
+        info,
+        Backtrace::force_capture()
+    );
+    fs::write(&path, report)?;
+    Ok(path)
+}
+
+// a bug must not dump a backtrace into someone's interactive shell: the details go to a
+// report file and the user gets one line
+pub fn install_hook() {
+    panic::set_hook(Box::new(|info| match write_report(info) {
+        Ok(path) => eprintln!(
+            "distrobox-cnf-handler: internal error, report written to {}",
+            path.display()
+        ),
+        Err(_) => eprintln!("distrobox-cnf-handler: internal error: {}", info),
+    }));
+}
@@ -0,0 +1,391 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::PathBuf;
+use std::process::exit;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::cache::Cache;
+use crate::config::Config;
+use crate::distrobox::{self, get_boxes, DistroboxInstance};
+use crate::metrics::Metrics;
+use crate::paths;
+use crate::resolve::{self, Resolved};
+use crate::tasks;
+
+const SOCKET_FILE: &str = "daemon.sock";
+// probes may have to start a container, so be generous before giving up on the daemon
+const QUERY_TIMEOUT: Duration = Duration::from_secs(60);
+// how long a client, a handler or a metrics scraper, may take to send its request, and
+// how much of one is read; a stalled one only costs its own task
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+const MAX_REQUEST: u64 = 16 * 1024;
+// how long a `top` may take to read one line before it is dropped
+const WATCH_TIMEOUT: Duration = Duration::from_secs(1);
+// how long to wait before watching events again once the stream ended
+const EVENTS_RETRY: Duration = Duration::from_secs(10);
+// events that change which boxes exist or whether they run
+const LIFECYCLE: [&str; 6] = ["create", "start", "stop", "died", "remove", "rename"];
+
+struct Shared {
+    config: Config,
+    cache: Mutex<Cache>,
+    metrics: Mutex<Metrics>,
+    // resolutions under way, so identical requests wait for the first instead of probing
+    inflight: Mutex<HashMap<String, Arc<Flight>>>,
+    // kept current from the manager's events while they are being watched, None otherwise
+    boxes: Mutex<Option<Vec<DistroboxInstance>>>,
+    // `top` connections, each told about every request as it is answered
+    watchers: Mutex<Vec<UnixStream>>,
+}
+
+#[derive(Default)]
+struct Flight {
+    reply: Mutex<Option<String>>,
+    done: Condvar,
+}
+
+// a `top` connection: the daemon's stream of answered requests
+pub fn watch_stream() -> io::Result<BufReader<UnixStream>> {
+    let mut stream = UnixStream::connect(socket_path()?)?;
+    writeln!(stream, "watch")?;
+    Ok(BufReader::new(stream))
+}
+
+pub fn socket_path() -> io::Result<PathBuf> {
+    Ok(paths::runtime_dir()?.join(SOCKET_FILE))
+}
+
+// ask a running daemon to resolve `command`; None when there is no daemon or it misbehaves.
+// The handler's parent goes along, so `top` can show who keeps asking
+pub fn query(command: &str) -> Option<Resolved> {
+    let mut stream = UnixStream::connect(socket_path().ok()?).ok()?;
+    stream.set_read_timeout(Some(QUERY_TIMEOUT)).ok()?;
+    writeln!(
+        stream,
+        "resolve {}\t{}",
+        command,
+        std::os::unix::process::parent_id()
+    )
+    .ok()?;
+    let mut reply = String::new();
+    BufReader::new(stream).read_line(&mut reply).ok()?;
+    match reply.trim_end().split_once(' ') {
+        Some(("found", name)) => Some(Resolved::Found(name.to_string())),
+        _ => match reply.trim_end() {
+            "notfound" => Some(Resolved::NotFound),
+            "incomplete" => Some(Resolved::Incomplete),
+            _ => None,
+        },
+    }
+}
+
+// `daemon [--metrics-port <port>]`
+pub fn daemon_command(args: &[String]) {
+    let config = Config::load().unwrap_or_else(|e| {
+        eprintln!("Ignoring config: {}", e);
+        Config::default()
+    });
+    let mut metrics_port = config
+        .get_int("daemon.metrics_port")
+        .and_then(|p| u16::try_from(p).ok());
+    let mut rest = args.iter();
+    while let Some(arg) = rest.next() {
+        match (arg.as_str(), rest.next().map(|p| p.parse::<u16>())) {
+            ("--metrics-port", Some(Ok(port))) => metrics_port = Some(port),
+            _ => {
+                eprintln!("Usage: distrobox-cnf-handler daemon [--metrics-port <port>]");
+                exit(1);
+            }
+        }
+    }
+    let listener = match bind() {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("Cannot listen on daemon socket: {:?}", e);
+            exit(2);
+        }
+    };
+    let shared = Arc::new(Shared {
+        config,
+        cache: Mutex::new(Cache::load().unwrap_or_else(|_| Cache::empty())),
+        metrics: Mutex::new(Metrics::default()),
+        inflight: Mutex::new(HashMap::new()),
+        boxes: Mutex::new(None),
+        watchers: Mutex::new(vec![]),
+    });
+    // daemon.watch_events = false lists the boxes for every request instead
+    if shared.config.get_bool("daemon.watch_events") != Some(false) {
+        let shared = Arc::clone(&shared);
+        tasks::spawn("watch", move || watch(&shared));
+    }
+    if let Some(port) = metrics_port {
+        let metrics = match TcpListener::bind(("127.0.0.1", port)) {
+            Ok(metrics) => metrics,
+            Err(e) => {
+                eprintln!("Cannot listen on metrics port {}: {:?}", port, e);
+                exit(2);
+            }
+        };
+        let shared = Arc::clone(&shared);
+        tasks::spawn("metrics", move || serve_metrics(metrics, shared));
+    }
+    for stream in listener.incoming().flatten() {
+        let shared = Arc::clone(&shared);
+        tasks::spawn("client", move || {
+            let _ = serve_client(stream, &shared);
+        });
+    }
+}
+
+fn bind() -> io::Result<UnixListener> {
+    let path = socket_path()?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    // a socket nobody answers on is left over from a daemon that died
+    if path.exists() && UnixStream::connect(&path).is_err() {
+        fs::remove_file(&path)?;
+    }
+    UnixListener::bind(path)
+}
+
+fn serve_client(stream: UnixStream, shared: &Shared) -> io::Result<()> {
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?.take(MAX_REQUEST));
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    let line = line.trim_end();
+    if line == "watch" {
+        // a watcher that stops reading must not hold up the answers
+        stream.set_write_timeout(Some(WATCH_TIMEOUT))?;
+        if let Ok(mut watchers) = shared.watchers.lock() {
+            watchers.push(stream);
+        }
+        return Ok(());
+    }
+    let reply = match line.split_once(' ') {
+        Some(("resolve", request)) if !request.is_empty() => {
+            // "command<TAB>caller pid", the pid missing from older handlers
+            let (command, caller) = request.split_once('\t').unwrap_or((request, ""));
+            let started = Instant::now();
+            let reply = answer(command, shared);
+            publish(shared, command, &reply, started.elapsed(), caller);
+            reply
+        }
+        _ => "error unknown request".to_string(),
+    };
+    let mut stream = stream;
+    writeln!(stream, "{}", reply)
+}
+
+// one "command<TAB>reply<TAB>ms<TAB>pid<TAB>caller" line to every watcher, dropping those gone
+fn publish(shared: &Shared, command: &str, reply: &str, took: Duration, pid: &str) {
+    let Ok(mut watchers) = shared.watchers.lock() else {
+        return;
+    };
+    if watchers.is_empty() {
+        return;
+    }
+    let caller = pid
+        .parse::<u32>()
+        .ok()
+        .and_then(|pid| fs::read_to_string(format!("/proc/{}/comm", pid)).ok())
+        .unwrap_or_default();
+    let line = format!(
+        "{}\t{}\t{:.1}\t{}\t{}\n",
+        command,
+        reply,
+        took.as_secs_f64() * 1000.0,
+        pid,
+        caller.trim()
+    );
+    watchers.retain_mut(|w| w.write_all(line.as_bytes()).is_ok());
+}
+
+// the reply for `command`, shared with any identical request that comes in meanwhile
+fn answer(command: &str, shared: &Shared) -> String {
+    let Ok(mut inflight) = shared.inflight.lock() else {
+        return "incomplete".to_string();
+    };
+    if let Some(flight) = inflight.get(command).cloned() {
+        drop(inflight);
+        if let Ok(mut metrics) = shared.metrics.lock() {
+            metrics.coalesced();
+        }
+        let Ok(reply) = flight.reply.lock() else {
+            return "incomplete".to_string();
+        };
+        // the asking handler stops waiting after as long, and searches on its own
+        return match flight
+            .done
+            .wait_timeout_while(reply, QUERY_TIMEOUT, |r| r.is_none())
+        {
+            Ok((reply, _)) => reply.clone().unwrap_or_else(|| "incomplete".to_string()),
+            Err(_) => "incomplete".to_string(),
+        };
+    }
+    let flight = Arc::new(Flight::default());
+    inflight.insert(command.to_string(), Arc::clone(&flight));
+    drop(inflight);
+    let mut landing = Landing {
+        command,
+        shared,
+        flight,
+        reply: None,
+    };
+    let reply = resolve_now(command, shared);
+    landing.reply = Some(reply.clone());
+    reply
+}
+
+// ends a flight however its resolution ends, a panic included, so nobody waits forever
+struct Landing<'a> {
+    command: &'a str,
+    shared: &'a Shared,
+    flight: Arc<Flight>,
+    reply: Option<String>,
+}
+
+impl Drop for Landing<'_> {
+    fn drop(&mut self) {
+        let mut inflight = self
+            .shared
+            .inflight
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        inflight.remove(self.command);
+        drop(inflight);
+        let mut reply = self.flight.reply.lock().unwrap_or_else(|e| e.into_inner());
+        *reply = Some(
+            self.reply
+                .take()
+                .unwrap_or_else(|| "incomplete".to_string()),
+        );
+        drop(reply);
+        self.flight.done.notify_all();
+    }
+}
+
+fn resolve_now(command: &str, shared: &Shared) -> String {
+    let started = Instant::now();
+    let boxes = match current_boxes(shared) {
+        Ok(boxes) => resolve::order_boxes(boxes, None, &shared.config),
+        Err(_) => return "incomplete".to_string(),
+    };
+    // the cache is locked to read and to write, never across probes, which other requests
+    // must not wait for; the search gets a copy of this command's entry
+    let mut scratch = Cache::empty();
+    match shared.cache.lock() {
+        Ok(mut cache) => {
+            cache.reload();
+            scratch.entries.extend(cache.lookup(command).cloned());
+        }
+        Err(_) => return "incomplete".to_string(),
+    }
+    let before = scratch.entries.first().map(|e| e.timestamp);
+    let hit = before.is_some();
+    let resolved = resolve::resolve(command, &boxes, &mut scratch, &shared.config);
+    let recorded = scratch
+        .entries
+        .into_iter()
+        .find(|e| e.command == command && Some(e.timestamp) != before);
+    if let (Some(entry), Ok(mut cache)) = (recorded, shared.cache.lock()) {
+        cache.reload();
+        cache.record(command, entry.resolution);
+        let _ = cache.save();
+    }
+    let (result, reply) = match &resolved {
+        Resolved::Found(name) => ("found", format!("found {}", name)),
+        Resolved::NotFound => ("not_found", "notfound".to_string()),
+        Resolved::Incomplete => ("incomplete", "incomplete".to_string()),
+    };
+    if let Ok(mut metrics) = shared.metrics.lock() {
+        metrics.cache_lookup(hit);
+        let found_in = match &resolved {
+            Resolved::Found(name) => Some(name.as_str()),
+            _ => None,
+        };
+        metrics.record(result, found_in, started.elapsed());
+    }
+    reply
+}
+
+fn current_boxes(shared: &Shared) -> io::Result<Vec<DistroboxInstance>> {
+    match shared.boxes.lock().ok().and_then(|boxes| boxes.clone()) {
+        Some(boxes) => Ok(boxes),
+        None => get_boxes(),
+    }
+}
+
+fn set_boxes(shared: &Shared, boxes: Option<Vec<DistroboxInstance>>) {
+    if let Ok(mut current) = shared.boxes.lock() {
+        *current = boxes;
+    }
+}
+
+// follow container starts, stops and removals so requests never route by a stale list
+fn watch(shared: &Shared) {
+    loop {
+        if let Ok(events) = distrobox::events() {
+            // listed after subscribing, so nothing that happens in between is missed
+            set_boxes(shared, get_boxes().ok());
+            for (name, status) in events {
+                if !LIFECYCLE.contains(&status.as_str()) {
+                    continue;
+                }
+                if status == "remove" {
+                    if let Ok(mut cache) = shared.cache.lock() {
+                        cache.reload();
+                        cache.invalidate_box(&name);
+                        let _ = cache.save();
+                    }
+                }
+                set_boxes(shared, get_boxes().ok());
+            }
+        }
+        // no events to go by, so list fresh for each request until they are back
+        set_boxes(shared, None);
+        thread::sleep(EVENTS_RETRY);
+    }
+}
+
+fn serve_metrics(listener: TcpListener, shared: Arc<Shared>) {
+    for stream in listener.incoming().flatten() {
+        let shared = Arc::clone(&shared);
+        tasks::spawn("scrape", move || serve_scrape(stream, &shared));
+    }
+}
+
+fn serve_scrape(mut stream: TcpStream, shared: &Shared) {
+    // the request itself is irrelevant, every path serves the metrics
+    let _ = read_request_head(&mut stream);
+    let body = match shared.metrics.lock() {
+        Ok(metrics) => metrics.render(),
+        Err(_) => String::new(),
+    };
+    let _ = write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        body.len(),
+        body
+    );
+}
+
+fn read_request_head(stream: &mut TcpStream) -> io::Result<()> {
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+    let mut head = vec![];
+    let mut buf = [0u8; 1024];
+    while !head.windows(4).any(|w| w == b"\r\n\r\n") && head.len() < MAX_REQUEST as usize {
+        let n = stream.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        head.extend_from_slice(&buf[..n]);
+    }
+    Ok(())
+}
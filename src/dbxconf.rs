@@ -0,0 +1,97 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+// distrobox's own configuration chain, lowest precedence first
+// (see distrobox(1), "CONFIG FILES")
+const SYSTEM_FILES: [&str; 3] = [
+    "/usr/share/distrobox/distrobox.conf",
+    "/usr/etc/distrobox/distrobox.conf",
+    "/etc/distrobox/distrobox.conf",
+];
+const MANAGERS: [&str; 3] = ["podman", "docker", "lilipod"];
+
+// the subset of distrobox's settings the handler cares about
+#[derive(Default)]
+pub struct DistroboxConf {
+    values: BTreeMap<String, String>,
+}
+
+fn user_files() -> Vec<PathBuf> {
+    let Ok(home) = env::var("HOME") else {
+        return vec![];
+    };
+    let config_home = env::var("XDG_CONFIG_HOME")
+        .ok()
+        .filter(|d| !d.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(&home).join(".config"));
+    vec![
+        config_home.join("distrobox").join("distrobox.conf"),
+        PathBuf::from(home).join(".distroboxrc"),
+    ]
+}
+
+// distrobox.conf is sourced as shell, but in practice it is plain key="value" lines
+fn parse_into(values: &mut BTreeMap<String, String>, text: &str) {
+    for line in text.lines() {
+        let line = line.trim();
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        if line.starts_with('#') {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let value = value.trim();
+        let value = value
+            .strip_prefix('"')
+            .and_then(|v| v.strip_suffix('"'))
+            .or_else(|| value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')))
+            .unwrap_or(value);
+        values.insert(key.trim().to_string(), value.to_string());
+    }
+}
+
+impl DistroboxConf {
+    pub fn load() -> DistroboxConf {
+        let mut values = BTreeMap::new();
+        let files = SYSTEM_FILES.iter().map(PathBuf::from).chain(user_files());
+        for file in files {
+            if let Ok(text) = fs::read_to_string(file) {
+                parse_into(&mut values, &text);
+            }
+        }
+        // the DBX_* environment overrides every file, as in distrobox itself
+        if let Ok(manager) = env::var("DBX_CONTAINER_MANAGER") {
+            values.insert("container_manager".to_string(), manager);
+        }
+        DistroboxConf { values }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.values
+            .get(key)
+            .map(|v| v.as_str())
+            .filter(|v| !v.is_empty())
+    }
+
+    // the container manager distrobox would pick: configured, else the first one installed
+    pub fn container_manager(&self) -> Option<String> {
+        if let Some(manager) = self.get("container_manager").filter(|m| *m != "autodetect") {
+            return Some(manager.to_string());
+        }
+        MANAGERS
+            .iter()
+            .find(|m| find_in_path(m).is_some())
+            .map(|m| m.to_string())
+    }
+}
+
+pub fn find_in_path(name: &str) -> Option<PathBuf> {
+    let path = env::var("PATH").ok()?;
+    env::split_paths(&path)
+        .map(|dir| dir.join(name))
+        .find(|candidate| candidate.is_file())
+}
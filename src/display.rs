@@ -0,0 +1,79 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::config::Config;
+use crate::distrobox;
+use crate::wsl;
+
+// common graphical programs, unless gui.commands overrides them
+const DEFAULT_COMMANDS: [&str; 14] = [
+    "firefox",
+    "chromium",
+    "google-chrome",
+    "code",
+    "codium",
+    "gimp",
+    "inkscape",
+    "krita",
+    "libreoffice",
+    "thunderbird",
+    "vlc",
+    "mpv",
+    "obs",
+    "steam",
+];
+const MOUNTS: &str = "{{range .Mounts}}{{.Destination}} {{end}}";
+
+pub fn wanted(config: &Config, command: &str) -> bool {
+    match config.get_list("gui.commands") {
+        Some(commands) => commands.iter().any(|c| c == command),
+        None => DEFAULT_COMMANDS.contains(&command),
+    }
+}
+
+// the display sockets the host session offers; empty outside a graphical session
+pub fn sockets() -> Vec<PathBuf> {
+    let mut sockets = vec![];
+    if let Ok(wayland) = env::var("WAYLAND_DISPLAY") {
+        if Path::new(&wayland).is_absolute() {
+            sockets.push(PathBuf::from(wayland));
+        } else if let Ok(runtime) = env::var("XDG_RUNTIME_DIR") {
+            sockets.push(Path::new(&runtime).join(wayland));
+        }
+    }
+    // ":1" or ":1.0" is /tmp/.X11-unix/X1; a host:display pair goes over TCP, nothing to mount
+    if let Ok(display) = env::var("DISPLAY") {
+        if let Some(number) = display.strip_prefix(':') {
+            let number = number.split('.').next().unwrap_or(number);
+            sockets.push(PathBuf::from(format!("/tmp/.X11-unix/X{}", number)));
+        }
+    }
+    // WSLg links them into /mnt/wslg, and a box needs that mounted for them to work
+    if wsl::detected() {
+        for socket in &mut sockets {
+            if let Ok(target) = fs::canonicalize(&socket) {
+                *socket = target;
+            }
+        }
+    }
+    sockets
+}
+
+// the names among `names` with at least one of `sockets` under a mount
+pub fn capable<'a>(
+    names: impl IntoIterator<Item = &'a str>,
+    sockets: &[PathBuf],
+) -> io::Result<Vec<String>> {
+    Ok(distrobox::inspect(names, MOUNTS)?
+        .into_iter()
+        .filter(|(_, mounts)| {
+            let mounts: Vec<&Path> = mounts.split_whitespace().map(Path::new).collect();
+            sockets
+                .iter()
+                .any(|s| mounts.iter().any(|m| s.starts_with(m)))
+        })
+        .map(|(name, _)| name)
+        .collect())
+}
@@ -0,0 +1,1473 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Error, ErrorKind, Read, Write};
+#[cfg(feature = "daemon")]
+use std::io::{BufRead, BufReader, Lines};
+use std::os::unix::process::ExitStatusExt;
+use std::path::{Path, PathBuf};
+#[cfg(feature = "daemon")]
+use std::process::{Child, ChildStdout};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{self, AtomicBool};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, UNIX_EPOCH};
+
+use crate::config::{self, Config};
+use crate::dbxconf::{find_in_path, DistroboxConf};
+use crate::envfilter;
+use crate::events;
+use crate::i18n::tr;
+use crate::index;
+use crate::json::Json;
+use crate::listing::parse_list_conservative;
+pub use crate::listing::DistroboxInstance;
+use crate::loginpath;
+use crate::msg::say;
+use crate::pathmap;
+use crate::paths;
+use crate::quadlet;
+use crate::relay::Relay;
+use crate::tasks;
+use crate::transcript::{self, Transcript};
+use crate::workdir;
+use crate::wsl;
+
+// how distrobox itself is set up, read once per process
+struct Backend {
+    manager: Option<String>,
+    root: bool,
+    priority_labels: bool,
+    // [alias.<box>] tables, e.g. fd = "fdfind" for a debian box
+    config: Config,
+    // systemd-run properties for the scope a boxed command runs in, empty for none
+    scope: Vec<String>,
+    nice: Option<i64>,
+    umask: bool,
+    // distrobox_prefix, for an install the PATH does not reach
+    prefix: Option<PathBuf>,
+    // how long a probe, lookup or helper script may run in a box before it is killed
+    deadline: Duration,
+}
+
+static BACKEND: OnceLock<Backend> = OnceLock::new();
+
+fn backend() -> &'static Backend {
+    BACKEND.get_or_init(|| {
+        let config = Config::load().unwrap_or_default();
+        Backend {
+            manager: DistroboxConf::load().container_manager(),
+            root: config.get_bool("rootful") == Some(true),
+            priority_labels: config.get_bool("priority_labels") == Some(true),
+            scope: scope_properties(&config),
+            nice: config.get_int("limits.nice"),
+            umask: config.get_bool("limits.umask") == Some(true),
+            prefix: config.get_str("distrobox_prefix").map(expand_home),
+            deadline: Duration::from_secs(
+                config
+                    .get_int("limits.probe_seconds")
+                    .and_then(|s| u64::try_from(s).ok())
+                    .unwrap_or(PROBE_SECONDS),
+            ),
+            config,
+        }
+    })
+}
+
+fn scope_properties(config: &Config) -> Vec<String> {
+    [
+        ("limits.cpu_quota", "CPUQuota"),
+        ("limits.memory_max", "MemoryMax"),
+    ]
+    .iter()
+    .filter_map(|(key, property)| Some(format!("--property={}={}", property, config.get_str(key)?)))
+    .collect()
+}
+
+// the daemon gives a query as long, then stops waiting for it
+const PROBE_SECONDS: u64 = 60;
+
+// where distrobox's install script puts it when not installed as a package
+const COMMON_PREFIXES: [&str; 4] = [
+    "~/.local",
+    "~/.local/share/distrobox",
+    "/usr/local",
+    "/opt/distrobox",
+];
+
+fn expand_home(prefix: &str) -> PathBuf {
+    match (prefix.strip_prefix("~/"), paths::home()) {
+        (Some(rest), Ok(home)) => home.join(rest),
+        _ => PathBuf::from(prefix),
+    }
+}
+
+// a prefix may name the bin directory itself or the directory above it
+fn in_prefix(prefix: &Path, name: &str) -> Option<PathBuf> {
+    [prefix.join(name), prefix.join("bin").join(name)]
+        .into_iter()
+        .find(|candidate| candidate.is_file())
+}
+
+pub fn has_distrobox(prefix: &Path) -> bool {
+    in_prefix(prefix, "distrobox-enter").is_some()
+}
+
+// the first common prefix holding distrobox, for when PATH does not lead to it
+pub fn detect_prefix() -> Option<PathBuf> {
+    COMMON_PREFIXES
+        .iter()
+        .map(|prefix| expand_home(prefix))
+        .find(|prefix| has_distrobox(prefix))
+}
+
+// the configured prefix, as doctor reports it
+pub fn configured_prefix() -> Option<&'static Path> {
+    backend().prefix.as_deref()
+}
+
+// a distrobox-* tool: from distrobox_prefix when set, else found on PATH like distrobox's
+// own installer expects, else from a common prefix
+fn tool_path(name: &str) -> PathBuf {
+    static DETECTED: OnceLock<Option<PathBuf>> = OnceLock::new();
+    backend()
+        .prefix
+        .as_deref()
+        .and_then(|prefix| in_prefix(prefix, name))
+        .or_else(|| find_in_path(name))
+        .or_else(|| {
+            DETECTED
+                .get_or_init(detect_prefix)
+                .as_deref()
+                .and_then(|prefix| in_prefix(prefix, name))
+        })
+        .unwrap_or_else(|| PathBuf::from("/usr/bin").join(name))
+}
+
+// pin the manager so list and enter agree even if the environment differs
+fn pin(cmd: &mut Command) {
+    if let Some(manager) = &backend().manager {
+        cmd.env("DBX_CONTAINER_MANAGER", manager);
+    }
+    if backend().root {
+        cmd.arg("--root");
+    }
+}
+
+fn tool(name: &str) -> Command {
+    let mut cmd = Command::new(tool_path(name));
+    pin(&mut cmd);
+    cmd
+}
+
+// like enter_cmd, but under the configured [limits] and shell; only real runs get these,
+// probes stay cheap
+fn run_cmd(name: &str, command: &str) -> Command {
+    let backend = backend();
+    let systemd_run = find_in_path("systemd-run").filter(|_| !backend.scope.is_empty());
+    // WSL runs without systemd unless wsl.conf turns it on, and then systemd-run only fails
+    let systemd_run = match systemd_run {
+        Some(_) if wsl::detected() && !Path::new("/run/systemd/system").exists() => {
+            say!("{}", tr!("wsl-no-systemd"));
+            None
+        }
+        Some(systemd_run) => Some(systemd_run),
+        None => {
+            if !backend.scope.is_empty() {
+                say!("{}", tr!("no-systemd-run"));
+            }
+            None
+        }
+    };
+    let mut cmd = match systemd_run {
+        // the scope holds distrobox-enter; whether the command itself stays inside it
+        // depends on the manager keeping exec'd processes in the caller's cgroup
+        Some(systemd_run) => {
+            let mut cmd = Command::new(systemd_run);
+            cmd.args(["--user", "--scope", "--quiet", "--collect"])
+                .args(&backend.scope)
+                .arg("--")
+                .arg(tool_path("distrobox-enter"));
+            pin(&mut cmd);
+            address(&mut cmd, name);
+            cmd
+        }
+        None => enter_cmd(name),
+    };
+    // niceness and umask do not cross into the container, so set them from inside it
+    let mut script = String::new();
+    if let Some(dir) = workdir::in_box(name) {
+        script.push_str(&format!(
+            "cd {} 2>/dev/null; ",
+            sh_quote(&dir.to_string_lossy())
+        ));
+    }
+    if let Some(umask) = host_umask().filter(|_| backend.umask) {
+        script.push_str(&format!("umask {}; ", umask));
+    }
+    if let Some(nice) = backend.nice {
+        script.push_str(&format!("renice -n {} -p $$ >/dev/null 2>&1; ", nice));
+    }
+    // whatever a probe found on the login PATH has to be found by the run as well
+    if let Some(path) = search_path(name) {
+        script.push_str(&format!("PATH={}:$PATH; export PATH; ", sh_quote(&path)));
+    }
+    if login_shell(name, command) {
+        script.push_str(LOGIN_EXEC);
+    } else if !script.is_empty() {
+        script.push_str("exec \"$@\"");
+    }
+    if !script.is_empty() {
+        cmd.args(["sh", "-c", &script, "sh"]);
+    }
+    cmd
+}
+
+// hand the command to the user's shell inside the box as a login shell, so its profile
+// (PATH additions such as ~/.cargo/bin) applies; fish takes its arguments differently
+const LOGIN_EXEC: &str = r#"case "${SHELL##*/}" in
+fish) exec "$SHELL" -l -c 'exec $argv' "$@" ;;
+*) exec "${SHELL:-/bin/sh}" -l -c 'exec "$0" "$@"' "$@" ;;
+esac"#;
+
+// [login_shell] default = false, with overrides in [login_shell.box] and, winning over
+// both, [login_shell.command]
+fn login_shell(name: &str, command: &str) -> bool {
+    let config = &backend().config;
+    config
+        .get_bool(&format!("login_shell.command.{}", command))
+        .or_else(|| config.get_bool(&format!("login_shell.box.{}", name)))
+        .or_else(|| config.get_bool("login_shell.default"))
+        .unwrap_or(false)
+}
+
+// extra directories to look commands up in for box `name`: the box user's login PATH,
+// searched ahead of the default one unless login_path = false
+fn search_path(name: &str) -> Option<String> {
+    if backend().config.get_bool("login_path") == Some(false) {
+        return None;
+    }
+    loginpath::login_path(name)
+}
+
+pub fn sh_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+fn host_umask() -> Option<String> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    status
+        .lines()
+        .find_map(|l| l.strip_prefix("Umask:"))
+        .map(|u| u.trim().to_string())
+}
+
+// what the distro in box `name` calls `command`: an [alias.<box>] entry, or the program
+// an alternatives group of that name points to when the group has no link on PATH
+fn alias<'a>(name: &str, command: &'a str) -> Cow<'a, str> {
+    match backend()
+        .config
+        .get_str(&format!("alias.{}.{}", name, command))
+    {
+        Some(target) => Cow::Borrowed(target),
+        None => match index::alternative(name, command) {
+            Some(path) => Cow::Owned(path),
+            None => Cow::Borrowed(command),
+        },
+    }
+}
+
+// how the boxes seen by get_boxes in this process are addressed, by box name
+static HANDLES: Mutex<BTreeMap<String, Handle>> = Mutex::new(BTreeMap::new());
+
+#[derive(Clone)]
+struct Handle {
+    manager: Option<String>,
+    connection: Option<String>,
+    id: String,
+    // the systemd unit to start first, for a quadlet box that is not running
+    unit: Option<String>,
+    running: bool,
+}
+
+// who to ask about a box: its manager (None for the configured one) and, for podman,
+// the system connection it lives on (None for the default)
+type Owner = (Option<String>, Option<String>);
+
+// the owner of box `name` and what to hand it: the ID when known, since an ID cannot
+// be mistaken for another container the way a name can
+fn target(name: &str) -> (Owner, String) {
+    match HANDLES.lock().ok().and_then(|h| h.get(name).cloned()) {
+        Some(handle) if !handle.id.is_empty() => ((handle.manager, handle.connection), handle.id),
+        Some(handle) => ((handle.manager, handle.connection), name.to_string()),
+        None => ((None, None), name.to_string()),
+    }
+}
+
+// a box picked without a listing, by a pin, the cache or the daemon, is known only by
+// name; where the name alone does not say which container, as for a duplicate renamed
+// name@manager or a quadlet whose unit may need starting, list once to find out
+fn learn(name: &str) {
+    let known = HANDLES.lock().is_ok_and(|h| h.contains_key(name));
+    if !known && !direct(name) {
+        let _ = get_boxes();
+    }
+}
+
+// append box `name` and the separator to a distrobox-enter command line
+fn address(cmd: &mut Command, name: &str) {
+    learn(name);
+    start_unit(name);
+    let ((manager, connection), target) = target(name);
+    if let Some(manager) = manager {
+        cmd.env("DBX_CONTAINER_MANAGER", manager);
+    }
+    // distrobox runs plain podman, which picks the connection up from here
+    if let Some(connection) = connection {
+        cmd.env("CONTAINER_CONNECTION", connection);
+    }
+    cmd.arg(target).arg("--");
+    // whatever enters the box, a run or a probe, takes the same environment in
+    envfilter::apply(&backend().config, name, cmd);
+}
+
+// a quadlet box has no container until its unit runs, and distrobox-enter would offer to
+// create one from scratch instead; started once, whether or not that worked
+fn start_unit(name: &str) {
+    let unit = HANDLES
+        .lock()
+        .ok()
+        .and_then(|mut h| h.get_mut(name).and_then(|handle| handle.unit.take()));
+    if let Some(unit) = unit {
+        if let Err(e) = quadlet::start(&unit, backend().root) {
+            say!("{}", tr!("cannot-start-unit", unit = &unit, error = e));
+        }
+    }
+}
+
+// read_only leaves stopped boxes stopped, whichever way they were picked: a pin, a
+// cached answer or the daemon's
+fn may_enter(name: &str) -> io::Result<()> {
+    if !config::read_only() {
+        return Ok(());
+    }
+    let running = || {
+        HANDLES
+            .lock()
+            .ok()
+            .and_then(|h| h.get(name).map(|h| h.running))
+    };
+    let running = match running() {
+        Some(running) => running,
+        None => {
+            get_boxes()?;
+            running().unwrap_or(false)
+        }
+    };
+    if !running {
+        return Err(Error::new(
+            ErrorKind::PermissionDenied,
+            format!("{} is not running, and read_only keeps it that way", name),
+        ));
+    }
+    Ok(())
+}
+
+fn enter_cmd(name: &str) -> Command {
+    let mut cmd = tool("distrobox-enter");
+    address(&mut cmd, name);
+    cmd
+}
+
+// scrape distrobox-list's table, the only listing every setup has
+fn list_table() -> io::Result<Vec<DistroboxInstance>> {
+    let version = version();
+    let mut list = tool("distrobox-list");
+    if version.is_some_and(|v| v >= NO_COLOR_SINCE) {
+        list.arg("--no-color");
+    }
+    let out = list.output()?;
+    if !out.status.success() {
+        let err_output = String::from_utf8_lossy(&out.stderr).to_lowercase();
+        if STORAGE_LOCKED.iter().any(|pat| err_output.contains(pat)) {
+            return Err(Error::new(
+                ErrorKind::ResourceBusy,
+                "container storage is locked",
+            ));
+        }
+        return Err(Error::other(format!("{:?}", out.status)));
+    }
+    let result: String = match String::from_utf8(out.stdout) {
+        Ok(s) => s,
+        Err(_) => {
+            return Err(Error::new(ErrorKind::InvalidData, "Bad UTF-8"));
+        }
+    };
+    // parse command output
+    let lines: Vec<String> = result.lines().map(|x| x.to_string()).collect();
+    let header_first = lines.first().is_some_and(|l| l.contains("NAME"));
+    if header_first && version.is_some_and(|v| (KNOWN_SINCE..KNOWN_BEFORE).contains(&v)) {
+        let known: io::Result<Vec<DistroboxInstance>> = lines
+            .iter()
+            .enumerate()
+            .skip(1)
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(DistroboxInstance::try_from)
+            .collect();
+        // a stray warning or a mangled line is no reason to lose the whole list
+        if let Ok(boxes) = known {
+            return Ok(boxes);
+        }
+    }
+    parse_list_conservative(&lines)
+}
+
+// the manager's own JSON listing of distrobox-managed containers, in the same order
+// distrobox-list shows them; None when the manager cannot provide it
+fn list_json(manager: Option<&str>, connection: Option<&str>) -> Option<Vec<DistroboxInstance>> {
+    // rootful boxes go through sudo, which distrobox-list already knows how to drive
+    if backend().root {
+        return None;
+    }
+    let out = owner_cmd(manager, connection)
+        .ok()?
+        .args([
+            "ps",
+            "-a",
+            "--filter",
+            "label=manager=distrobox",
+            "--format",
+            "json",
+        ])
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    if !out.status.success() {
+        return None;
+    }
+    let text = String::from_utf8(out.stdout).ok()?;
+    // docker prints nothing for no containers; let distrobox-list confirm that
+    if text.trim().is_empty() {
+        return None;
+    }
+    // podman prints one array, docker one object per line
+    let containers = match Json::parse(&text) {
+        Some(Json::Arr(items)) => items,
+        _ => text
+            .lines()
+            .filter(|l| !l.trim().is_empty())
+            .map(Json::parse)
+            .collect::<Option<_>>()?,
+    };
+    containers
+        .iter()
+        .enumerate()
+        .map(|(priority, c)| {
+            let name = match c.get("Names")? {
+                Json::Arr(names) => names.first()?.as_str()?,
+                names => names.as_str()?.split(',').next()?,
+            };
+            let state = c.get("State").and_then(Json::as_str).unwrap_or_default();
+            let status = c.get("Status").and_then(Json::as_str).unwrap_or_default();
+            let id = c.get("Id").or_else(|| c.get("ID")).and_then(Json::as_str);
+            Some(DistroboxInstance {
+                name: name.trim_start_matches('/').to_string(),
+                id: id.unwrap_or_default().to_string(),
+                manager: manager.map(String::from),
+                connection: connection.map(String::from),
+                priority,
+                running: state == "running" || status.starts_with("Up"),
+                image: c
+                    .get("Image")
+                    .and_then(Json::as_str)
+                    .unwrap_or_default()
+                    .to_string(),
+            })
+        })
+        .collect()
+}
+
+// the configured manager's boxes; storage held by another podman command (a pull, an
+// image build) is waited out for a moment before giving up with ResourceBusy
+fn list_boxes() -> io::Result<Vec<DistroboxInstance>> {
+    let mut tries = 0;
+    loop {
+        let listed = match list_json(None, None) {
+            Some(boxes) => Ok(boxes),
+            None => list_table(),
+        };
+        match listed {
+            Err(e) if e.kind() == ErrorKind::ResourceBusy && tries < LIST_RETRIES => {
+                tries += 1;
+                thread::sleep(RETRY_BACKOFF * tries);
+            }
+            listed => return listed,
+        }
+    }
+}
+
+// container lifecycle events of the configured manager as (container name, status),
+// e.g. ("fedora", "start"); ends when the manager's event stream does
+#[cfg(feature = "daemon")]
+pub struct Events {
+    child: Child,
+    lines: Lines<BufReader<ChildStdout>>,
+}
+
+#[cfg(feature = "daemon")]
+pub fn events() -> io::Result<Events> {
+    // rootful boxes would need sudo for every event stream, which cannot prompt here
+    if backend().root {
+        return Err(Error::new(
+            ErrorKind::Unsupported,
+            "Cannot watch events of rootful boxes",
+        ));
+    }
+    let mut cmd = manager_cmd(None)?;
+    let docker = backend()
+        .manager
+        .as_deref()
+        .is_some_and(|m| m.contains("docker"));
+    cmd.args(["events", "--filter", "type=container", "--format"])
+        .arg(if docker { "{{json .}}" } else { "json" })
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null());
+    let mut child = cmd.spawn()?;
+    let stdout = child.stdout.take().expect("stdout was piped");
+    Ok(Events {
+        child,
+        lines: BufReader::new(stdout).lines(),
+    })
+}
+
+#[cfg(feature = "daemon")]
+impl Iterator for Events {
+    type Item = (String, String);
+
+    fn next(&mut self) -> Option<(String, String)> {
+        for line in self.lines.by_ref() {
+            let Some(event) = Json::parse(&line.ok()?) else {
+                continue;
+            };
+            // podman names its fields Name and Status, docker nests the name under Actor
+            let name = event.get("Name").or_else(|| {
+                event
+                    .get("Actor")
+                    .and_then(|a| a.get("Attributes"))
+                    .and_then(|a| a.get("name"))
+            });
+            let status = event.get("Status").or_else(|| event.get("Action"));
+            if let (Some(name), Some(status)) = (
+                name.and_then(|n| n.as_str()),
+                status.and_then(|s| s.as_str()),
+            ) {
+                return Some((name.to_string(), status.to_string()));
+            }
+        }
+        None
+    }
+}
+
+#[cfg(feature = "daemon")]
+impl Drop for Events {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+// a box whose image is gone: entering it only fails, and slowly
+struct Broken {
+    name: String,
+    // the manager command that removes it
+    cleanup: String,
+}
+
+// the broken boxes get_boxes left out in this process
+static BROKEN: Mutex<Vec<Broken>> = Mutex::new(Vec::new());
+
+pub fn broken() -> Vec<(String, String)> {
+    BROKEN
+        .lock()
+        .map(|broken| {
+            broken
+                .iter()
+                .map(|b| (b.name.clone(), b.cleanup.clone()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// the architecture of the image a box was made from, as the manager names it (amd64, arm64);
+// a listing without the image asks the container for it first
+pub fn image_arch(dbx: &DistroboxInstance) -> io::Result<String> {
+    let image = match dbx.image.is_empty() {
+        false => dbx.image.clone(),
+        true => inspect([dbx.name.as_str()], "{{.Image}}")?
+            .pop()
+            .map(|(_, image)| image.trim().to_string())
+            .ok_or_else(|| Error::new(ErrorKind::NotFound, "container has no image"))?,
+    };
+    let out = owner_cmd(dbx.manager.as_deref(), dbx.connection.as_deref())?
+        .args(["image", "inspect", "--format", "{{.Architecture}}", &image])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()?;
+    let arch = String::from_utf8_lossy(&out.stdout).trim().to_string();
+    if !out.status.success() || arch.is_empty() {
+        return Err(Error::other("cannot inspect the image"));
+    }
+    Ok(arch)
+}
+
+// the manager lists a container whose image was removed under the bare image ID; only
+// such boxes pay for asking whether that image still exists
+fn lost_image(dbx: &DistroboxInstance) -> Option<Broken> {
+    let id = dbx.image.trim_start_matches("sha256:");
+    if id.len() < 12 || !id.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let mut cmd = owner_cmd(dbx.manager.as_deref(), dbx.connection.as_deref()).ok()?;
+    let exists = cmd
+        .args(["image", "inspect", "--format", "{{.Id}}", &dbx.image])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .ok()?
+        .success();
+    if exists {
+        return None;
+    }
+    let container = if dbx.id.is_empty() {
+        &dbx.name
+    } else {
+        &dbx.id
+    };
+    let words: Vec<String> = std::iter::once(cmd.get_program())
+        .chain(cmd.get_args().take_while(|a| *a != "image"))
+        .map(|w| w.to_string_lossy().into_owned())
+        .collect();
+    Some(Broken {
+        name: dbx.name.clone(),
+        cleanup: format!("{} rm {}", words.join(" "), container),
+    })
+}
+
+pub fn get_boxes() -> io::Result<Vec<DistroboxInstance>> {
+    let mut boxes = list_boxes()?;
+    // extra_managers = ["docker"] adds another manager's boxes after the configured one's,
+    // then come those on further podman connections
+    let extra_managers = backend()
+        .config
+        .get_list("extra_managers")
+        .unwrap_or_default()
+        .into_iter()
+        .map(|manager| (Some(manager), None));
+    let connections = connections().into_iter().map(|c| (None, Some(c)));
+    for (manager, connection) in extra_managers.chain(connections) {
+        let from = manager
+            .as_deref()
+            .or(connection.as_deref())
+            .unwrap_or_default()
+            .to_string();
+        let Some(extra) = list_json(manager.as_deref(), connection.as_deref()) else {
+            say!("{}", tr!("cannot-list-from", from = from));
+            continue;
+        };
+        let offset = boxes.len();
+        for mut dbx in extra {
+            // the same name under two owners must never reach the wrong container
+            if boxes.iter().any(|b| b.name == dbx.name) {
+                dbx.name = format!("{}@{}", dbx.name, from);
+            }
+            dbx.priority += offset;
+            boxes.push(dbx);
+        }
+    }
+    // quadlet boxes join after the listed ones; stopped ones are started through systemd
+    let quadlets = if backend().config.get_bool("quadlets") == Some(false) {
+        Arc::default()
+    } else {
+        quadlet::known(backend().root)
+    };
+    for quadlet in quadlets.iter() {
+        if !boxes.iter().any(|b| b.name == quadlet.name) {
+            boxes.push(DistroboxInstance {
+                name: quadlet.name.clone(),
+                id: String::new(),
+                manager: None,
+                connection: None,
+                priority: boxes.len(),
+                running: false,
+                image: quadlet.image.clone(),
+            });
+        }
+    }
+    boxes.retain(|dbx| {
+        let Some(broken) = lost_image(dbx) else {
+            return true;
+        };
+        if let Ok(mut known) = BROKEN.lock() {
+            if !known.iter().any(|b| b.name == broken.name) {
+                say!(
+                    "{}",
+                    tr!("image-gone", box = &broken.name, cleanup = &broken.cleanup)
+                );
+                known.push(broken);
+            }
+        }
+        false
+    });
+    if let Ok(mut handles) = HANDLES.lock() {
+        for dbx in &boxes {
+            let unit = quadlets
+                .iter()
+                .find(|q| q.name == dbx.name && !dbx.running)
+                .map(|q| q.unit.clone());
+            let handle = Handle {
+                manager: dbx.manager.clone(),
+                connection: dbx.connection.clone(),
+                id: dbx.id.clone(),
+                unit,
+                running: dbx.running,
+            };
+            handles.insert(dbx.name.clone(), handle);
+        }
+    }
+    if backend().priority_labels && !boxes.is_empty() {
+        // a failed inspect only costs the labels, the list itself is still good
+        if let Ok(labels) = label_priorities(&boxes) {
+            for dbx in boxes.iter_mut() {
+                if let Some(priority) = labels.iter().find(|(n, _)| n == &dbx.name).map(|l| l.1) {
+                    dbx.priority = priority;
+                }
+            }
+        }
+    }
+    // an explicit box_order beats both; unlisted boxes keep their relative order after it
+    if let Some(order) = backend().config.get_list("box_order") {
+        for dbx in boxes.iter_mut() {
+            dbx.priority = match order.iter().position(|n| n == &dbx.name) {
+                Some(pos) => pos,
+                None => order.len() + dbx.priority,
+            };
+        }
+    }
+    Ok(boxes)
+}
+
+// releases whose distrobox-list table the plain parser is known to handle
+const KNOWN_SINCE: Version = (1, 3, 0);
+const KNOWN_BEFORE: Version = (2, 0, 0);
+// older releases colour the table unconditionally and reject the flag
+const NO_COLOR_SINCE: Version = (1, 3, 0);
+const VERSION_FILE: &str = "distrobox-version";
+
+pub type Version = (u32, u32, u32);
+
+// "distrobox: 1.7.2.1" -> (1, 7, 2)
+fn parse_version(text: &str) -> Option<Version> {
+    let word = text
+        .lines()
+        .find_map(|l| l.trim().strip_prefix("distrobox:"))?
+        .split_whitespace()
+        .next()?;
+    let mut parts = word.split('.').map(|p| p.parse::<u32>().ok());
+    Some((
+        parts.next()??,
+        parts.next().flatten().unwrap_or(0),
+        parts.next().flatten().unwrap_or(0),
+    ))
+}
+
+// the installed distrobox release, remembered per distrobox-list binary so it costs one
+// extra process only after distrobox itself changed
+pub fn version() -> Option<Version> {
+    static VERSION: OnceLock<Option<Version>> = OnceLock::new();
+    *VERSION.get_or_init(|| {
+        let path = tool_path("distrobox-list");
+        let stamp = fs::metadata(&path)
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| format!("{}\t{}", path.display(), d.as_secs()));
+        let cache = paths::cache_dir().ok().map(|d| d.join(VERSION_FILE));
+        let cached = cache.as_ref().and_then(|c| fs::read_to_string(c).ok());
+        if let (Some(stamp), Some(cached)) = (&stamp, &cached) {
+            if let Some(version) = cached.strip_prefix(stamp.as_str()) {
+                return parse_version(version);
+            }
+        }
+        let out = tool("distrobox-list").arg("--version").output().ok()?;
+        let text = String::from_utf8_lossy(&out.stdout).into_owned();
+        let version = parse_version(&text);
+        match version {
+            Some(v) if !(KNOWN_SINCE..KNOWN_BEFORE).contains(&v) => say!(
+                "{}",
+                tr!(
+                    "distrobox-untested",
+                    version = format!("{}.{}.{}", v.0, v.1, v.2)
+                )
+            ),
+            Some(_) => {}
+            None => {
+                say!("{}", tr!("distrobox-version-unknown"))
+            }
+        }
+        if let (Some(stamp), Some(cache), Some(_), Ok(())) =
+            (stamp, cache, version, config::writable())
+        {
+            if let Some(dir) = cache.parent() {
+                let _ = fs::create_dir_all(dir);
+            }
+            let _ = fs::write(cache, format!("{}\t{}", stamp, text.trim()));
+        }
+        version
+    })
+}
+
+// containers may carry their own priority as a label, e.g. `--label cnf.priority=10`;
+// lower values are tried first, like positions in the list
+const PRIORITY_LABEL: &str = "cnf.priority";
+
+// `manager`, or the configured one for None
+fn manager_cmd(manager: Option<&str>) -> io::Result<Command> {
+    let manager = manager
+        .or(backend().manager.as_deref())
+        .ok_or_else(|| Error::new(ErrorKind::NotFound, "No container manager found"))?;
+    // the manager setting may carry a prefix such as "sudo podman"
+    let mut words = manager.split_whitespace();
+    let mut cmd = Command::new(words.next().unwrap_or("podman"));
+    cmd.args(words);
+    Ok(cmd)
+}
+
+// the extra podman connections to list boxes from: podman.connections names them, or
+// is ["*"] for all of them, minus podman.disabled_connections. The default connection is
+// listed anyway and never counted twice
+fn connections() -> Vec<String> {
+    let config = &backend().config;
+    let wanted = config.get_list("podman.connections").unwrap_or_default();
+    if wanted.is_empty() {
+        return vec![];
+    }
+    let disabled = config
+        .get_list("podman.disabled_connections")
+        .unwrap_or_default();
+    let known: Vec<(String, bool)> = manager_cmd(None)
+        .and_then(|mut cmd| {
+            cmd.args([
+                "system",
+                "connection",
+                "list",
+                "--format",
+                "{{.Name}}\t{{.Default}}",
+            ])
+            .stderr(Stdio::null())
+            .output()
+        })
+        .map(|out| String::from_utf8_lossy(&out.stdout).into_owned())
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|l| l.split_once('\t'))
+        .map(|(name, default)| (name.to_string(), default.trim() == "true"))
+        .collect();
+    let all = wanted.iter().any(|w| w == "*");
+    known
+        .into_iter()
+        .filter(|(name, default)| {
+            !default && (all || wanted.contains(name)) && !disabled.contains(name)
+        })
+        .map(|(name, _)| name)
+        .collect()
+}
+
+// whether box `name` can be entered without listing first: true unless boxes come from
+// more than one place, where the same name may mean another container, or it is a
+// quadlet box whose unit may need starting
+pub fn direct(name: &str) -> bool {
+    let config = &backend().config;
+    let single = config
+        .get_list("extra_managers")
+        .unwrap_or_default()
+        .is_empty()
+        && config
+            .get_list("podman.connections")
+            .unwrap_or_default()
+            .is_empty();
+    single
+        && (config.get_bool("quadlets") == Some(false)
+            || !quadlet::known(backend().root)
+                .iter()
+                .any(|q| q.name == name))
+}
+
+// the configured container manager, e.g. "podman" or "sudo docker"
+pub fn manager() -> Option<&'static str> {
+    backend().manager.as_deref()
+}
+
+pub fn rootful() -> bool {
+    backend().root
+}
+
+// what the container manager says about itself, e.g. "podman version 5.2.2"
+pub fn manager_version() -> Option<String> {
+    let out = manager_cmd(None)
+        .ok()?
+        .arg("--version")
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    let text = String::from_utf8_lossy(&out.stdout).trim().to_string();
+    Some(text).filter(|t| out.status.success() && !t.is_empty())
+}
+
+fn owner_cmd(manager: Option<&str>, connection: Option<&str>) -> io::Result<Command> {
+    let mut cmd = manager_cmd(manager)?;
+    if let Some(connection) = connection {
+        cmd.args(["--connection", connection]);
+    }
+    Ok(cmd)
+}
+
+fn label_priorities(boxes: &[DistroboxInstance]) -> io::Result<Vec<(String, usize)>> {
+    let format = format!("{{{{index .Config.Labels \"{}\"}}}}", PRIORITY_LABEL);
+    Ok(inspect(boxes.iter().map(|b| b.name.as_str()), &format)?
+        .into_iter()
+        .filter_map(|(name, value)| Some((name, value.trim().parse().ok()?)))
+        .collect())
+}
+
+// evaluate a container manager template for each box, in one call per manager, as
+// (name, output) pairs
+pub fn inspect<'a>(
+    names: impl IntoIterator<Item = &'a str>,
+    template: &str,
+) -> io::Result<Vec<(String, String)>> {
+    let mut by_owner: BTreeMap<Owner, Vec<(&str, String)>> = BTreeMap::new();
+    for name in names {
+        let (owner, target) = target(name);
+        by_owner.entry(owner).or_default().push((name, target));
+    }
+    let mut results = vec![];
+    for ((manager, connection), boxes) in by_owner {
+        let out = owner_cmd(manager.as_deref(), connection.as_deref())?
+            .args(["inspect", "--format", template])
+            .args(boxes.iter().map(|(_, target)| target))
+            .stderr(Stdio::null())
+            .output()?;
+        if !out.status.success() {
+            return Err(Error::other(format!("{:?}", out.status)));
+        }
+        // one line per container, in the order they were asked for
+        let text = String::from_utf8_lossy(&out.stdout);
+        let lines: Vec<&str> = text.lines().collect();
+        if lines.len() != boxes.len() {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "Unexpected inspect output",
+            ));
+        }
+        for ((name, _), line) in boxes.iter().zip(lines) {
+            results.push((name.to_string(), line.to_string()));
+        }
+    }
+    Ok(results)
+}
+
+pub enum EnterOutcome {
+    // with the command's exit code
+    Ran(i32),
+    NotFound,
+    // the box itself could not be entered, so nothing is known about the command
+    BoxFailed(String),
+    // not tried in this box, which is fine: an argument names a host path it cannot see
+    Skipped(String),
+}
+
+// stderr fragments distrobox-enter/podman emit when the container, not the command, is broken
+const INFRA_ERRORS: [&str; 8] = [
+    "Error: ",
+    "Error response from daemon",
+    "Cannot find container",
+    "could not start",
+    "unable to start container",
+    "OCI runtime error",
+    "cannot connect to",
+    "failed to start",
+];
+// podman reports a missing executable as an "Error: " too, that one is a genuine miss
+const MISSING_COMMAND: [&str; 3] = [
+    "command not found",
+    "not found in $PATH",
+    "command that was not found",
+];
+// podman failures that usually go away on their own after a moment
+const TRANSIENT_ERRORS: [&str; 5] = [
+    "database is locked",
+    "storage lock",
+    "cgroup",
+    "resource temporarily unavailable",
+    "timed out",
+];
+// what podman says while another podman command holds its storage
+const STORAGE_LOCKED: [&str; 3] = ["database is locked", "storage lock", "error acquiring lock"];
+const ENTER_RETRIES: u32 = 1;
+const LIST_RETRIES: u32 = 2;
+const RETRY_BACKOFF: Duration = Duration::from_millis(500);
+// how much of the child's stderr is kept around for classification
+const STDERR_TAIL: usize = 8192;
+
+static FORCE_PTY: AtomicBool = AtomicBool::new(false);
+
+// fullscreen.policy = "tty": runs from here on get a pty even when stdin is no terminal
+pub fn force_pty() {
+    FORCE_PTY.store(true, atomic::Ordering::Relaxed);
+}
+
+pub fn enter(name: &str, args: &[String]) -> io::Result<EnterOutcome> {
+    may_enter(name)?;
+    let copy_from = workdir::before_run(name);
+    let transcript = transcript::wanted(&backend().config, &args[0])
+        .then(|| Transcript::create(&backend().config, name, args))
+        .and_then(|t| t.ok());
+    let args = match backend().config.get_bool("path_translation") {
+        Some(false) => args.to_vec(),
+        _ => match pathmap::translate_args(name, args) {
+            Ok(args) => args,
+            // another box may see the path, this one cannot run the command as asked
+            Err(e) => return Ok(EnterOutcome::Skipped(e.to_string())),
+        },
+    };
+    let mut cmd = run_cmd(name, &args[0]);
+    cmd.arg(&*alias(name, &args[0])).args(&args[1..]);
+    events::emit(
+        "executed",
+        vec![("command", Json::str(&args[0])), ("box", Json::str(name))],
+    );
+    // on a terminal, script(1) keeps one in front of the command while it records, so
+    // curses, key modes and escapes such as OSC 52 clipboard writes work as without it
+    let pty = transcript.as_ref().and_then(|t| t.under_pty(&cmd));
+    let recording = match pty {
+        Some(_) => None,
+        None => transcript.map(|t| Arc::new(Mutex::new(t))),
+    };
+    let mut cmd = match pty {
+        Some(pty) => pty,
+        None if FORCE_PTY.load(atomic::Ordering::Relaxed) => match transcript::under_script(&cmd) {
+            Some(mut pty) => {
+                pty.arg("/dev/null");
+                pty
+            }
+            None => {
+                say!("{}", tr!("no-script"));
+                cmd
+            }
+        },
+        None => cmd,
+    };
+    // a transcript without a pty means stdout is a pipe, which some programs notice
+    if recording.is_some() {
+        cmd.stdout(Stdio::piped());
+    }
+    // kept for diagnosis while it is passed on: what a box says when it cannot run the
+    // command is held back and reported, not shown. Under a pty, stderr is the pty and
+    // script(1) only says its own things here; a transcript records it too
+    cmd.stderr(Stdio::piped());
+    let mut child = cmd.spawn()?;
+    let tee = child.stdout.take().map(|mut stdout| {
+        let recording = recording.clone();
+        tasks::spawn("tee", move || {
+            let mut buf = [0u8; 4096];
+            while let Ok(n) = stdout.read(&mut buf) {
+                if n == 0 {
+                    break;
+                }
+                let mut out = io::stdout().lock();
+                let _ = out.write_all(&buf[..n]).and_then(|_| out.flush());
+                if let Some(Ok(mut t)) = recording.as_ref().map(|t| t.lock()) {
+                    t.record(&buf[..n]);
+                }
+            }
+        })
+    });
+    let relay = Relay::default();
+    let reader = child.stderr.take().map(|mut stderr| {
+        let relay = relay.clone();
+        tasks::spawn("stderr", move || {
+            let mut tail: Vec<u8> = vec![];
+            let mut buf = [0u8; 4096];
+            while let Ok(n) = stderr.read(&mut buf) {
+                if n == 0 {
+                    break;
+                }
+                relay.write(&buf[..n]);
+                if let Some(Ok(mut t)) = recording.as_ref().map(|t| t.lock()) {
+                    t.record(&buf[..n]);
+                }
+                tail.extend_from_slice(&buf[..n]);
+                if tail.len() > STDERR_TAIL {
+                    tail.drain(..tail.len() - STDERR_TAIL);
+                }
+            }
+            String::from_utf8_lossy(&tail).into_owned()
+        })
+    });
+    let status = match child.wait() {
+        Ok(status) => status,
+        // does not exist in this box, try the next one
+        Err(_) => return Ok(EnterOutcome::NotFound),
+    };
+    let err_output = reader
+        .map(|r| r.join().unwrap_or_default())
+        .unwrap_or_default();
+    if let Some(tee) = tee {
+        let _ = tee.join();
+    }
+    // a command killed by a signal reports 128 + the signal, as shells do
+    let code = status.code().or_else(|| status.signal().map(|s| 128 + s));
+    let outcome = classify(code, &err_output);
+    let ran = matches!(outcome, EnterOutcome::Ran(_));
+    relay.finish(ran);
+    if let Some(dir) = copy_from.as_ref().filter(|_| ran) {
+        workdir::after_run(name, dir);
+    }
+    Ok(outcome)
+}
+
+// `args` as run in the box, to be wired up by the caller as with any child process
+pub fn run_command(name: &str, args: &[String]) -> Command {
+    let mut cmd = run_cmd(name, &args[0]);
+    cmd.arg(&*alias(name, &args[0])).args(&args[1..]);
+    cmd
+}
+
+// like enter, but a box that failed transiently gets another chance before giving up on it
+pub fn enter_retrying(name: &str, args: &[String]) -> io::Result<EnterOutcome> {
+    retry_transient(
+        || enter(name, args),
+        |outcome| match outcome {
+            EnterOutcome::BoxFailed(reason) => Some(reason),
+            _ => None,
+        },
+    )
+}
+
+pub fn probe_retrying(name: &str, command: &str) -> io::Result<ProbeOutcome> {
+    retry_transient(
+        || probe(name, command),
+        |outcome| match outcome {
+            ProbeOutcome::BoxFailed(reason) => Some(reason),
+            _ => None,
+        },
+    )
+}
+
+fn retry_transient<T>(
+    attempt: impl Fn() -> io::Result<T>,
+    failure: impl Fn(&T) -> Option<&String>,
+) -> io::Result<T> {
+    let mut tries = 0;
+    loop {
+        let outcome = attempt()?;
+        match failure(&outcome) {
+            Some(reason) if tries < ENTER_RETRIES && is_transient(reason) => {
+                tries += 1;
+                thread::sleep(RETRY_BACKOFF * tries);
+            }
+            _ => return Ok(outcome),
+        }
+    }
+}
+
+fn is_transient(reason: &str) -> bool {
+    let reason = reason.to_lowercase();
+    TRANSIENT_ERRORS.iter().any(|pat| reason.contains(pat))
+}
+
+fn infra_error(err_output: &str) -> Option<&str> {
+    err_output
+        .lines()
+        .map(|l| l.trim())
+        .filter(|l| !MISSING_COMMAND.iter().any(|pat| l.contains(pat)))
+        .find(|l| INFRA_ERRORS.iter().any(|pat| l.contains(pat)))
+}
+
+fn classify(code: Option<i32>, err_output: &str) -> EnterOutcome {
+    match (code, infra_error(err_output)) {
+        (Some(127 | 125), Some(line)) => EnterOutcome::BoxFailed(line.to_string()),
+        (Some(127), None) => EnterOutcome::NotFound,
+        (code, _) => EnterOutcome::Ran(code.unwrap_or(1)),
+    }
+}
+
+pub enum ProbeOutcome {
+    Found,
+    NotFound,
+    BoxFailed(String),
+    // the box is configured to be searched only by running the command
+    Unknown,
+}
+
+const PROBE_SCRIPT: &str = r#"command -v "$1""#;
+const LOGIN_PROBE_SCRIPT: &str = r#"PATH=$2:$PATH; command -v "$1""#;
+const TEST_SCRIPT: &str = r#"c=$1; shift; for d; do [ -x "$d/$c" ] && exit 0; done; exit 1"#;
+const DEFAULT_TEST_PATHS: [&str; 4] = ["/usr/local/bin", "/usr/bin", "/bin", "/usr/sbin"];
+
+// look a command up inside a box without running it, the way [probe.<box>] says:
+// method = "command-v" (the default), "which", "test" (with paths = [...]), "full-run"
+// or "custom" (with command = "a template")
+pub fn probe(name: &str, command: &str) -> io::Result<ProbeOutcome> {
+    may_enter(name)?;
+    let config = &backend().config;
+    let command = alias(name, command);
+    let command = command.as_ref();
+    let mut cmd = enter_cmd(name);
+    match config.get_str(&format!("probe.{}.method", name)) {
+        None | Some("command-v") => {
+            match search_path(name) {
+                Some(path) => cmd.args(["sh", "-c", LOGIN_PROBE_SCRIPT, "sh", command, &path]),
+                None => cmd.args(["sh", "-c", PROBE_SCRIPT, "sh", command]),
+            };
+        }
+        Some("which") => {
+            match search_path(name) {
+                Some(path) => cmd
+                    .args(["sh", "-c", r#"PATH=$2:$PATH exec which "$1""#, "sh"])
+                    .args([command, &path]),
+                None => cmd.args(["which", command]),
+            };
+        }
+        Some("test") => {
+            let paths = config
+                .get_list(&format!("probe.{}.paths", name))
+                .unwrap_or_else(|| DEFAULT_TEST_PATHS.map(String::from).to_vec());
+            cmd.args(["sh", "-c", TEST_SCRIPT, "sh", command])
+                .args(paths);
+        }
+        Some("custom") => {
+            let template = custom_probe(config, name)?;
+            cmd.args(["sh", "-c", &template, "sh", command])
+                .arg(search_path(name).unwrap_or_default());
+        }
+        Some("full-run") => return Ok(ProbeOutcome::Unknown),
+        Some(other) => {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Unknown probe method {} for box {}", other, name),
+            ))
+        }
+    }
+    let out = match tasks::output(cmd.stdin(Stdio::null()), backend().deadline) {
+        Err(e) if e.kind() == ErrorKind::TimedOut => {
+            return Ok(ProbeOutcome::BoxFailed(e.to_string()))
+        }
+        out => out?,
+    };
+    if out.status.success() {
+        return Ok(ProbeOutcome::Found);
+    }
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    Ok(match infra_error(&stderr) {
+        Some(line) => ProbeOutcome::BoxFailed(line.to_string()),
+        None => ProbeOutcome::NotFound,
+    })
+}
+
+// the shell snippet of probe.<box>.command, exiting 0 when the command is there;
+// {command} and {path} (the login PATH, maybe empty) become quoted positional
+// parameters, so no command name is ever spliced into the script itself
+fn custom_probe(config: &Config, name: &str) -> io::Result<String> {
+    let template = config
+        .get_str(&format!("probe.{}.command", name))
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                format!("The custom probe for box {} has no command", name),
+            )
+        })?;
+    Ok(template
+        .replace("{command}", "\"$1\"")
+        .replace("{path}", "\"$2\""))
+}
+
+// where `command` lives in the box, as command -v reports it; None when it is not there
+pub fn locate(name: &str, command: &str) -> io::Result<Option<String>> {
+    may_enter(name)?;
+    let command = alias(name, command);
+    let command = command.as_ref();
+    let mut cmd = enter_cmd(name);
+    match search_path(name) {
+        Some(path) => cmd.args(["sh", "-c", LOGIN_PROBE_SCRIPT, "sh", command, &path]),
+        None => cmd.args(["sh", "-c", PROBE_SCRIPT, "sh", command]),
+    };
+    let out = tasks::output(cmd.stdin(Stdio::null()), backend().deadline)?;
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    Ok(out
+        .status
+        .success()
+        .then(|| stdout.lines().next().unwrap_or_default().trim().to_string())
+        .filter(|path| !path.is_empty()))
+}
+
+// whether `path` is an executable file in the box; relative paths start from the current
+// directory, which distrobox-enter keeps
+pub fn has_path(name: &str, path: &str) -> io::Result<ProbeOutcome> {
+    may_enter(name)?;
+    let mut cmd = enter_cmd(name);
+    cmd.args(["sh", "-c", r#"test -f "$1" && test -x "$1""#, "sh", path])
+        .stdin(Stdio::null());
+    let out = match tasks::output(&mut cmd, backend().deadline) {
+        Err(e) if e.kind() == ErrorKind::TimedOut => {
+            return Ok(ProbeOutcome::BoxFailed(e.to_string()))
+        }
+        out => out?,
+    };
+    if out.status.success() {
+        return Ok(ProbeOutcome::Found);
+    }
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    Ok(match infra_error(&stderr) {
+        Some(line) => ProbeOutcome::BoxFailed(line.to_string()),
+        None => ProbeOutcome::NotFound,
+    })
+}
+
+// bring the files matching `pattern` in `dir` (or, as distrobox-enter falls back to, the
+// box's home) back into `dir` on the host; false when nothing matched
+pub fn copy_back(name: &str, dir: &Path, pattern: &str) -> io::Result<bool> {
+    may_enter(name)?;
+    let script = r#"cd "$1" 2>/dev/null || cd || exit 1
+set -- $2
+[ -e "$1" ] || exit 3
+exec tar cf - -- "$@""#;
+    let mut cmd = enter_cmd(name);
+    cmd.arg("sh")
+        .arg("-c")
+        .arg(script)
+        .arg("sh")
+        .arg(dir)
+        .arg(pattern)
+        .stdin(Stdio::null());
+    let packed = tasks::output(&mut cmd, backend().deadline)?;
+    if packed.status.code() == Some(3) {
+        return Ok(false);
+    }
+    if !packed.status.success() {
+        return Err(Error::other(format!("{:?}", packed.status)));
+    }
+    let mut unpacker = Command::new("tar")
+        .arg("xf")
+        .arg("-")
+        .arg("-C")
+        .arg(dir)
+        .stdin(Stdio::piped())
+        .spawn()?;
+    let mut stdin = unpacker.stdin.take().expect("stdin was piped");
+    let written = stdin.write_all(&packed.stdout);
+    drop(stdin);
+    let unpacked = unpacker.wait()?;
+    written?;
+    if !unpacked.success() {
+        return Err(Error::other(format!("{:?}", unpacked)));
+    }
+    Ok(true)
+}
+
+// run a helper script inside a box and collect its stdout
+pub fn capture(name: &str, script: &str) -> io::Result<String> {
+    may_enter(name)?;
+    let mut cmd = enter_cmd(name);
+    cmd.args(["sh", "-c", script]).stdin(Stdio::null());
+    let out = tasks::output(&mut cmd, backend().deadline)?;
+    if !out.status.success() {
+        return Err(Error::other(format!("{:?}", out.status)));
+    }
+    Ok(String::from_utf8_lossy(&out.stdout).into_owned())
+}
+
+// run a script inside a box on the terminal, for the handler's own admin commands
+pub fn exec(name: &str, script: &str) -> io::Result<i32> {
+    may_enter(name)?;
+    let status = enter_cmd(name).args(["sh", "-c", script]).status()?;
+    Ok(status.code().unwrap_or(1))
+}
+
+// the box user's own shell, on the terminal
+pub fn interactive(name: &str) -> io::Result<i32> {
+    may_enter(name)?;
+    let status = enter_cmd(name).status()?;
+    Ok(status.code().unwrap_or(1))
+}
+
+// enter once with a no-op so the container and its init are up for the next real command
+pub fn start(name: &str) -> io::Result<()> {
+    capture(name, "true").map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn outcome(code: Option<i32>, stderr: &str) -> String {
+        match classify(code, stderr) {
+            EnterOutcome::Ran(code) => format!("ran {}", code),
+            EnterOutcome::NotFound => "not found".to_string(),
+            EnterOutcome::BoxFailed(line) => format!("failed: {}", line),
+            EnterOutcome::Skipped(reason) => format!("skipped: {}", reason),
+        }
+    }
+
+    #[test]
+    fn exit_status_and_stderr_tell_a_miss_from_a_broken_box() {
+        let oci = "Error: unable to start container \"abc\": OCI runtime error";
+        for (code, stderr, expected) in [
+            (Some(127), "", "not found"),
+            (Some(127), "sh: frob: command not found", "not found"),
+            (
+                Some(127),
+                "Error: executable file `frob` not found in $PATH",
+                "not found",
+            ),
+            (Some(127), oci, &format!("failed: {}", oci)),
+            (Some(125), oci, &format!("failed: {}", oci)),
+            (
+                Some(125),
+                "  Error response from daemon: No such container: x  ",
+                "failed: Error response from daemon: No such container: x",
+            ),
+            // 125 without the manager saying why is the command's own status
+            (Some(125), "", "ran 125"),
+            (Some(125), "frob: bad input", "ran 125"),
+            // whatever else a command prints, its status stands
+            (Some(1), oci, "ran 1"),
+            (Some(0), "", "ran 0"),
+            (None, "", "ran 1"),
+        ] {
+            assert_eq!(outcome(code, stderr), expected, "{:?} {:?}", code, stderr);
+        }
+    }
+
+    #[test]
+    fn versions_from_distrobox_list() {
+        for (text, expected) in [
+            ("distrobox: 1.7.2.1\n", Some((1, 7, 2))),
+            ("distrobox: 1.8.0", Some((1, 8, 0))),
+            ("distrobox: 1.8", Some((1, 8, 0))),
+            ("distrobox: 2", Some((2, 0, 0))),
+            ("  distrobox: 1.5.0.2 (git)\n", Some((1, 5, 0))),
+            ("WARNING: something\ndistrobox: 1.7.0\n", Some((1, 7, 0))),
+            ("distrobox: 1.x.2", Some((1, 0, 2))),
+            ("distrobox: dev", None),
+            ("distrobox:", None),
+            ("1.7.2", None),
+            ("", None),
+        ] {
+            assert_eq!(parse_version(text), expected, "{:?}", text);
+        }
+    }
+}
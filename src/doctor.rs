@@ -0,0 +1,195 @@
+#[cfg(feature = "daemon")]
+use std::os::unix::net::UnixStream;
+use std::process::exit;
+
+use crate::cache::now;
+use crate::coldstart;
+use crate::color::{self, Style};
+use crate::config::{self, Config};
+use crate::cooldown;
+#[cfg(feature = "daemon")]
+use crate::daemon;
+use crate::dbxconf::{find_in_path, DistroboxConf};
+use crate::distrobox::{self, get_boxes};
+use crate::wsl;
+
+fn report(ok: bool, what: &str, detail: &str) {
+    let mark = if ok {
+        color::out("ok", Style::Good)
+    } else {
+        color::out("!!", Style::Bad)
+    };
+    println!("[{}] {}: {}", mark, what, detail);
+}
+
+// `doctor`: check what the handler depends on and show boxes it is avoiding
+pub fn doctor_command(args: &[String]) {
+    if !args.is_empty() {
+        eprintln!("Usage: distrobox-cnf-handler doctor");
+        exit(1);
+    }
+    let mut healthy = true;
+    match distrobox::version() {
+        Some((major, minor, patch)) => {
+            report(true, "distrobox", &format!("{}.{}.{}", major, minor, patch))
+        }
+        None => {
+            report(false, "distrobox", "cannot tell the version");
+            healthy = false;
+        }
+    }
+    match distrobox::configured_prefix() {
+        Some(prefix) if distrobox::has_distrobox(prefix) => {
+            report(true, "distrobox prefix", &prefix.display().to_string())
+        }
+        Some(prefix) => {
+            report(
+                false,
+                "distrobox prefix",
+                &format!("no distrobox-enter under {}", prefix.display()),
+            );
+            healthy = false;
+        }
+        None if find_in_path("distrobox-enter").is_some() => report(
+            true,
+            "distrobox prefix",
+            "none needed, distrobox is on PATH",
+        ),
+        None => match distrobox::detect_prefix() {
+            Some(prefix) => report(
+                true,
+                "distrobox prefix",
+                &format!(
+                    "found in {}, off PATH; 'config set distrobox_prefix {}' pins it",
+                    prefix.display(),
+                    prefix.display()
+                ),
+            ),
+            None => {
+                report(
+                    false,
+                    "distrobox prefix",
+                    "distrobox-enter is neither on PATH nor under a common prefix",
+                );
+                healthy = false;
+            }
+        },
+    }
+    if wsl::wslg() {
+        let detail = format!(
+            "WSLg, graphical programs need {} mounted in the box",
+            wsl::WSLG_DIR
+        );
+        report(true, "wsl", &detail);
+    } else if wsl::detected() {
+        report(
+            true,
+            "wsl",
+            "no WSLg, graphical programs need an X server of their own",
+        );
+    }
+    if config::read_only() {
+        report(
+            true,
+            "read only",
+            "no state is written, and only running boxes are searched",
+        );
+    }
+    match DistroboxConf::load().container_manager() {
+        Some(manager) => report(true, "container manager", &manager),
+        None => {
+            report(false, "container manager", "none installed");
+            healthy = false;
+        }
+    }
+    let config = match Config::load() {
+        Ok(config) => {
+            report(true, "config", "loads");
+            config
+        }
+        Err(e) => {
+            report(false, "config", &e.to_string());
+            healthy = false;
+            Config::default()
+        }
+    };
+    match get_boxes() {
+        Ok(boxes) => {
+            let running = boxes.iter().filter(|b| b.running).count();
+            report(
+                true,
+                "boxes",
+                &format!("{} ({} running)", boxes.len(), running),
+            );
+        }
+        Err(e) => {
+            report(false, "boxes", &format!("cannot list: {}", e));
+            healthy = false;
+        }
+    }
+    for (name, cleanup) in distrobox::broken() {
+        report(
+            false,
+            "broken box",
+            &format!(
+                "{} has lost its image, '{}' removes it",
+                color::out(&name, Style::BoxName),
+                cleanup
+            ),
+        );
+        healthy = false;
+    }
+    #[cfg(feature = "daemon")]
+    {
+        let daemon = daemon::socket_path().is_ok_and(|p| UnixStream::connect(p).is_ok());
+        report(
+            true,
+            "daemon",
+            if daemon { "running" } else { "not running" },
+        );
+    }
+    // slow, not broken, so it does not make the run unhealthy
+    let limit = coldstart::limit_millis(&config);
+    for slow in coldstart::slow(&config) {
+        report(
+            false,
+            "cold start",
+            &format!(
+                "{} takes {:.1}s to start (over {}s each time); cold_start.demote = true searches it last while stopped",
+                color::out(&slow.name, Style::BoxName),
+                slow.median() as f64 / 1000.0,
+                limit / 1000
+            ),
+        );
+    }
+    let failing = cooldown::load();
+    if failing.is_empty() {
+        report(true, "cooldown", "no failing boxes");
+    }
+    for f in &failing {
+        let name = color::out(&f.name, Style::BoxName);
+        if f.cooling() {
+            report(
+                false,
+                "cooldown",
+                &format!(
+                    "{} failed {} times, skipped for another {}s ('forget --box {}' releases it)",
+                    name,
+                    f.failures,
+                    f.until - now(),
+                    f.name
+                ),
+            );
+            healthy = false;
+        } else {
+            report(
+                true,
+                "cooldown",
+                &format!("{} failed {} times recently", name, f.failures),
+            );
+        }
+    }
+    if !healthy {
+        exit(2);
+    }
+}
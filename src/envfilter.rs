@@ -0,0 +1,106 @@
+use std::env;
+use std::process::Command;
+
+use crate::config::Config;
+
+// distrobox-enter hands the whole host environment to the box; these it needs itself, to
+// find the manager, the container and the user, so no list takes them away
+const ALWAYS: [&str; 10] = [
+    "PATH",
+    "HOME",
+    "USER",
+    "LOGNAME",
+    "SHELL",
+    "TERM",
+    "XDG_RUNTIME_DIR",
+    "DBX_*",
+    "DISTROBOX_*",
+    "CONTAINER_*",
+];
+
+// * stands for any run of characters, e.g. "AWS_*" or "*_TOKEN"
+fn matches(pattern: &str, name: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = name.strip_prefix(first) else {
+        return false;
+    };
+    let mut parts: Vec<&str> = parts.collect();
+    let Some(last) = parts.pop() else {
+        return rest.is_empty();
+    };
+    for part in parts {
+        match rest.find(part) {
+            Some(at) => rest = &rest[at + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+fn any(patterns: &[String], name: &str) -> bool {
+    patterns.iter().any(|p| matches(p, name))
+}
+
+// [environment] keeps host variables, credentials above all, out of the boxes:
+//   deny = ["AWS_*", "SSH_AUTH_SOCK"]
+//   allow = ["LANG", "LC_*"]
+// with allow, nothing else gets in; deny wins over it. [environment.<box>] lists replace
+// these for that box
+pub fn apply(config: &Config, name: &str, cmd: &mut Command) {
+    let list = |key| {
+        config
+            .get_list(&format!("environment.{}.{}", name, key))
+            .or_else(|| config.get_list(&format!("environment.{}", key)))
+    };
+    let deny = list("deny").unwrap_or_default();
+    let allow = list("allow");
+    if deny.is_empty() && allow.is_none() {
+        return;
+    }
+    // what the handler sets for the box itself is meant to get there
+    let set: Vec<String> = cmd
+        .get_envs()
+        .map(|(key, _)| key.to_string_lossy().into_owned())
+        .collect();
+    for (key, _) in env::vars_os() {
+        let key = key.to_string_lossy();
+        if set.iter().any(|s| *s == key) || ALWAYS.iter().any(|p| matches(p, &key)) {
+            continue;
+        }
+        let allowed = allow.as_ref().is_none_or(|allow| any(allow, &key));
+        if !allowed || any(&deny, &key) {
+            cmd.env_remove(&*key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn patterns() {
+        for (pattern, name, matched) in [
+            ("PATH", "PATH", true),
+            ("PATH", "PATHS", false),
+            ("PATH", "MANPATH", false),
+            ("AWS_*", "AWS_SECRET_ACCESS_KEY", true),
+            ("AWS_*", "AWS_", true),
+            ("AWS_*", "XAWS_KEY", false),
+            ("*_TOKEN", "GITHUB_TOKEN", true),
+            ("*_TOKEN", "GITHUB_TOKENS", false),
+            ("*TOKEN*", "MY_TOKEN_FILE", true),
+            ("A*B*C", "AxxBxxC", true),
+            ("A*B*C", "ABC", true),
+            ("A*B*C", "ACB", false),
+            ("A*B*C", "AB", false),
+            ("*", "ANYTHING", true),
+            ("*", "", true),
+            ("", "", true),
+            ("", "X", false),
+        ] {
+            assert_eq!(matches(pattern, name), matched, "{} {}", pattern, name);
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use std::fs;
+use std::io::{self, ErrorKind, Write};
+use std::os::unix::net::UnixDatagram;
+use std::path::PathBuf;
+use std::process::{self, exit};
+use std::sync::OnceLock;
+
+use crate::cache::now;
+use crate::json::Json;
+use crate::paths;
+
+// every datagram socket in here gets each event; nothing is sent while it is empty
+const EVENTS_DIR: &str = "events";
+
+fn events_dir() -> io::Result<PathBuf> {
+    Ok(paths::runtime_dir()?.join(EVENTS_DIR))
+}
+
+// looked for once per run, events come in bursts
+fn listeners() -> &'static [PathBuf] {
+    static LISTENERS: OnceLock<Vec<PathBuf>> = OnceLock::new();
+    LISTENERS.get_or_init(|| {
+        events_dir()
+            .and_then(fs::read_dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|e| e.path())
+            .collect()
+    })
+}
+
+// one JSON object per datagram, e.g.
+// {"event":"executed","pid":123,"time":1700000000,"command":"htop","box":"fedora"}
+pub fn emit(event: &str, fields: Vec<(&str, Json)>) {
+    let listeners = listeners();
+    if listeners.is_empty() {
+        return;
+    }
+    let Ok(socket) = UnixDatagram::unbound() else {
+        return;
+    };
+    if socket.set_nonblocking(true).is_err() {
+        return;
+    }
+    let mut all = vec![
+        ("event", Json::str(event)),
+        ("pid", Json::from(process::id() as u64)),
+        ("time", Json::from(now())),
+    ];
+    all.extend(fields);
+    let message = Json::obj(all).to_string();
+    for path in listeners {
+        // nobody bound to it any more: a listener that was killed leaves its socket behind
+        if let Err(e) = socket.send_to(message.as_bytes(), path) {
+            if e.kind() == ErrorKind::ConnectionRefused {
+                let _ = fs::remove_file(path);
+            }
+        }
+    }
+}
+
+// a socket of our own among the listeners, gone again when dropped
+pub struct Listener {
+    socket: UnixDatagram,
+    path: PathBuf,
+    buf: Vec<u8>,
+}
+
+impl Listener {
+    pub fn bind() -> io::Result<Listener> {
+        let dir = events_dir()?;
+        fs::create_dir_all(&dir)?;
+        let path = dir.join(format!("{}.sock", process::id()));
+        let _ = fs::remove_file(&path);
+        Ok(Listener {
+            socket: UnixDatagram::bind(&path)?,
+            path,
+            buf: vec![0; 64 * 1024],
+        })
+    }
+
+    // the next event as sent
+    pub fn recv(&mut self) -> io::Result<&str> {
+        let n = loop {
+            match self.socket.recv(&mut self.buf) {
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                n => break n?,
+            }
+        };
+        std::str::from_utf8(&self.buf[..n]).map_err(|e| io::Error::new(ErrorKind::InvalidData, e))
+    }
+}
+
+impl Drop for Listener {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+pub fn listen_or_exit() -> Listener {
+    Listener::bind().unwrap_or_else(|e| {
+        eprintln!("Cannot listen for events: {}", e);
+        exit(1);
+    })
+}
+
+// `events`: listen for the handler's events and print them as JSON lines, for status bars
+pub fn events_command(args: &[String]) {
+    if !args.is_empty() {
+        eprintln!("Usage: distrobox-cnf-handler events");
+        exit(1);
+    }
+    let mut listener = listen_or_exit();
+    let mut stdout = io::stdout();
+    loop {
+        let event = match listener.recv() {
+            Ok(event) => event,
+            Err(e) => {
+                eprintln!("Cannot read events: {}", e);
+                break;
+            }
+        };
+        // the reader went away, e.g. the bar restarted
+        if writeln!(stdout, "{}", event)
+            .and_then(|_| stdout.flush())
+            .is_err()
+        {
+            break;
+        }
+    }
+    drop(listener);
+    exit(1);
+}
@@ -0,0 +1,40 @@
+use std::io;
+use std::os::unix::process::ExitStatusExt;
+use std::process::{Command, Stdio};
+
+use crate::dbxconf::find_in_path;
+
+// the installed app `command` most likely means: its full ID, or an ID whose last part
+// matches ignoring case, as org.gimp.GIMP for gimp
+pub fn find(command: &str) -> Option<String> {
+    find_in_path("flatpak")?;
+    let output = Command::new("flatpak")
+        .args(["list", "--app", "--columns=application"])
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    let apps = String::from_utf8_lossy(&output.stdout);
+    let apps: Vec<&str> = apps.lines().map(str::trim).collect();
+    apps.iter()
+        .find(|app| **app == command)
+        .or_else(|| {
+            apps.iter().find(|app| {
+                app.rsplit('.')
+                    .next()
+                    .is_some_and(|last| last.eq_ignore_ascii_case(command))
+            })
+        })
+        .map(|app| app.to_string())
+}
+
+pub fn run(app: &str, args: &[String]) -> io::Result<i32> {
+    let status = Command::new("flatpak")
+        .arg("run")
+        .arg(app)
+        .args(args)
+        .status()?;
+    Ok(status
+        .code()
+        .or_else(|| status.signal().map(|s| 128 + s))
+        .unwrap_or(1))
+}
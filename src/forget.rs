@@ -0,0 +1,66 @@
+use std::process::exit;
+
+use crate::arch;
+use crate::cache::Cache;
+use crate::coldstart;
+use crate::color::{self, Style};
+use crate::cooldown;
+use crate::index;
+use crate::loginpath;
+use crate::pathmap;
+use crate::pins;
+
+// `forget <command>` or `forget --box <name>`: undo what was learned or remembered;
+// forgetting a box also takes it off cooldown
+pub fn forget_command(args: &[String]) {
+    let (target, is_box) = match args {
+        [flag, name] if flag == "--box" => (name, true),
+        [command] if !command.starts_with('-') => (command, false),
+        _ => {
+            eprintln!("Usage: distrobox-cnf-handler forget <command> | --box <name>");
+            exit(1);
+        }
+    };
+    let gone = |c: &str, b: &str| if is_box { b == target } else { c == target };
+    let mut cache = match Cache::load() {
+        Ok(cache) => cache,
+        Err(e) => {
+            eprintln!("Cannot load cache: {:?}", e);
+            exit(2);
+        }
+    };
+    let removed = if is_box {
+        let _ = index::remove(target);
+        let _ = loginpath::remove(target);
+        let _ = pathmap::forget_home(target);
+        let _ = arch::remove(target);
+        let _ = cooldown::recovered(target);
+        let _ = coldstart::forget(target);
+        cache.invalidate_box(target)
+    } else {
+        cache.invalidate_command(target)
+    };
+    if let Err(e) = cache.save() {
+        eprintln!("Cannot write cache: {:?}", e);
+        exit(2);
+    }
+    let unpinned = match pins::forget(gone) {
+        Ok(files) => files,
+        Err(e) => {
+            eprintln!("Cannot remove pins: {:?}", e);
+            exit(2);
+        }
+    };
+    let what = if is_box {
+        color::out(target, Style::BoxName)
+    } else {
+        color::out(target, Style::Command)
+    };
+    println!("Forgot {}: {} cache entries", what, removed);
+    for file in &unpinned {
+        println!("  unpinned in {}", file.display());
+    }
+    if pins::env_pinned(gone) {
+        eprintln!("Note: DISTROBOX_CNF_PIN still pins it in this environment");
+    }
+}
@@ -0,0 +1,37 @@
+use crate::config::Config;
+
+// programs that draw on the terminal and wait for keys, unless fullscreen.commands
+// overrides them
+const DEFAULT_COMMANDS: [&str; 14] = [
+    "vi", "vim", "nvim", "nano", "emacs", "htop", "btop", "top", "less", "man", "mc", "ranger",
+    "tmux", "screen",
+];
+
+// what to do with one of them when stdin is not a terminal, as in a script
+#[derive(PartialEq)]
+pub enum Policy {
+    // explain and fail, rather than hang on a screen nobody sees
+    Fail,
+    // leave it out and let the script go on, exiting 127 as if it were missing
+    Skip,
+    // run it under a pty of its own
+    Tty,
+    // run it as it is
+    Run,
+}
+
+pub fn policy(config: &Config) -> Policy {
+    match config.get_str("fullscreen.policy") {
+        Some("skip") => Policy::Skip,
+        Some("tty") => Policy::Tty,
+        Some("run") => Policy::Run,
+        _ => Policy::Fail,
+    }
+}
+
+pub fn wanted(config: &Config, command: &str) -> bool {
+    match config.get_list("fullscreen.commands") {
+        Some(commands) => commands.iter().any(|c| c == command),
+        None => DEFAULT_COMMANDS.contains(&command),
+    }
+}
@@ -0,0 +1,182 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::exit;
+use std::time::{Duration, SystemTime};
+
+use crate::cache::Cache;
+use crate::config::{self, Config};
+use crate::paths;
+
+const STAMP_FILE: &str = "gc-stamp";
+// collected at most this often without being asked
+const AUTO_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+const DEFAULT_MAX_AGE_DAYS: u64 = 30;
+const DEFAULT_MAX_MB: u64 = 64;
+// what piles up over time, under the cache directory
+const CACHE_DIRS: [&str; 8] = [
+    "traces",
+    "transcripts",
+    "index",
+    "login-path",
+    "os-release",
+    "homes",
+    "arch",
+    "crashes",
+];
+// and under the runtime directory, which a logout would clear anyway
+const RUNTIME_DIRS: [&str; 3] = ["shims", "sessions", "affinity"];
+
+#[derive(Default)]
+struct Pruned {
+    files: usize,
+    bytes: u64,
+    entries: usize,
+}
+
+// one file, or one directory such as a shim dir, removed as a whole
+struct Item {
+    path: PathBuf,
+    modified: SystemTime,
+    bytes: u64,
+}
+
+fn size(path: &Path) -> u64 {
+    let Ok(meta) = fs::symlink_metadata(path) else {
+        return 0;
+    };
+    if !meta.is_dir() {
+        return meta.len();
+    }
+    fs::read_dir(path)
+        .map(|entries| entries.flatten().map(|e| size(&e.path())).sum())
+        .unwrap_or(0)
+}
+
+fn items() -> Vec<Item> {
+    let cache = CACHE_DIRS
+        .iter()
+        .filter_map(|d| Some(paths::cache_dir().ok()?.join(d)));
+    let runtime = RUNTIME_DIRS
+        .iter()
+        .filter_map(|d| Some(paths::runtime_dir().ok()?.join(d)));
+    let mut items = vec![];
+    for dir in cache.chain(runtime) {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Ok(modified) = entry.metadata().and_then(|m| m.modified()) else {
+                continue;
+            };
+            items.push(Item {
+                bytes: size(&path),
+                path,
+                modified,
+            });
+        }
+    }
+    items
+}
+
+fn remove(item: &Item) -> io::Result<()> {
+    match item.path.is_dir() {
+        true => fs::remove_dir_all(&item.path),
+        false => fs::remove_file(&item.path),
+    }
+}
+
+// drop what is older than [gc] max_age_days, then the oldest of the rest until it all
+// fits in max_mb; resolutions older than the age limit go from the cache as well
+fn collect(config: &Config) -> Pruned {
+    let days = config
+        .get_int("gc.max_age_days")
+        .and_then(|n| u64::try_from(n).ok())
+        .unwrap_or(DEFAULT_MAX_AGE_DAYS);
+    let max_bytes = config
+        .get_int("gc.max_mb")
+        .and_then(|n| u64::try_from(n).ok())
+        .unwrap_or(DEFAULT_MAX_MB)
+        * 1024
+        * 1024;
+    let max_age = Duration::from_secs(days * 24 * 60 * 60);
+    let now = SystemTime::now();
+    let mut items = items();
+    items.sort_by_key(|item| item.modified);
+    let mut total: u64 = items.iter().map(|item| item.bytes).sum();
+    let mut pruned = Pruned::default();
+    for item in &items {
+        let old = now
+            .duration_since(item.modified)
+            .is_ok_and(|age| age > max_age);
+        if !old && total <= max_bytes {
+            continue;
+        }
+        if remove(item).is_ok() {
+            total -= item.bytes;
+            pruned.files += 1;
+            pruned.bytes += item.bytes;
+        }
+    }
+    if let Ok(mut cache) = Cache::load() {
+        pruned.entries = cache.prune(max_age.as_secs());
+        if pruned.entries > 0 {
+            let _ = cache.save();
+        }
+    }
+    pruned
+}
+
+// called by the handler once it is done; cheap unless a day has passed since the last time
+pub fn auto(config: &Config) {
+    if config.get_bool("gc.auto") == Some(false) || config::read_only() {
+        return;
+    }
+    let Ok(stamp) = paths::cache_dir().map(|d| d.join(STAMP_FILE)) else {
+        return;
+    };
+    let due = fs::metadata(&stamp)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.elapsed().ok())
+        .is_none_or(|age| age > AUTO_INTERVAL);
+    if !due {
+        return;
+    }
+    // stamped first, so concurrent handlers do not all collect at once
+    if stamp
+        .parent()
+        .is_none_or(|dir| fs::create_dir_all(dir).is_ok())
+        && fs::write(&stamp, "").is_ok()
+    {
+        collect(config);
+    }
+}
+
+// `gc`: prune now, whatever the stamp says
+pub fn gc_command(args: &[String]) {
+    if !args.is_empty() {
+        eprintln!("Usage: distrobox-cnf-handler gc");
+        exit(1);
+    }
+    // pruning is writing, which read_only rules out however it is asked for
+    if let Err(e) = config::writable() {
+        eprintln!("Cannot prune: {}", e);
+        exit(1);
+    }
+    let config = Config::load().unwrap_or_else(|e| {
+        eprintln!("Ignoring config: {}", e);
+        Config::default()
+    });
+    let pruned = collect(&config);
+    if let Ok(stamp) = paths::cache_dir().map(|d| d.join(STAMP_FILE)) {
+        let _ = fs::write(stamp, "");
+    }
+    println!(
+        "Removed {} files ({} KiB) and {} cache entries",
+        pruned.files,
+        pruned.bytes.div_ceil(1024),
+        pruned.entries
+    );
+}
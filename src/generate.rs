@@ -0,0 +1,358 @@
+use std::process::exit;
+
+const NAME: &str = "distrobox-cnf-handler";
+
+// the handler's own flags: name, value placeholder, description
+const FLAGS: [(&str, Option<&str>, &str); 10] = [
+    (
+        "--host",
+        None,
+        "run the command on the host when called from inside a container",
+    ),
+    (
+        "--quiet",
+        None,
+        "print nothing but the command's own output",
+    ),
+    (
+        "--timings",
+        None,
+        "report how long each step of the search took",
+    ),
+    (
+        "--shell-protocol",
+        Some("bash|zsh|fish"),
+        "how to tell the calling shell about the result",
+    ),
+    (
+        "--trace",
+        Some("json"),
+        "write a trace of the search to stderr",
+    ),
+    (
+        "--trace-file",
+        Some("path"),
+        "write a trace of the search to a file",
+    ),
+    (
+        "--box",
+        Some("name"),
+        "run in this box instead of searching (run only)",
+    ),
+    (
+        "--copy-back",
+        Some("glob"),
+        "bring matching files back from a box that does not share the current directory",
+    ),
+    (
+        "--output",
+        Some("text|json"),
+        "how to report a command that was found nowhere",
+    ),
+    ("--", None, "end of handler flags, the command follows"),
+];
+
+// subcommands: name, arguments, description
+const SUBCOMMANDS: [(&str, &str, &str); 23] = [
+    (
+        "run",
+        "[--box <name>] [flags...] -- <command> [args...]",
+        "find and run a command, exiting with its status",
+    ),
+    (
+        "cache",
+        "[show | clear | invalidate <box|command>]",
+        "show or change the cache of where commands live",
+    ),
+    (
+        "resolve",
+        "[--batch] [--output text|json] [command...]",
+        "print which box provides each command",
+    ),
+    (
+        "daemon",
+        "[--metrics-port <port>]",
+        "keep resolving in the background for faster lookups",
+    ),
+    (
+        "prewarm",
+        "[--count <n>]",
+        "resolve the most used commands ahead of time",
+    ),
+    ("setup", "", "install the handler into the shell's rc files"),
+    (
+        "uninstall",
+        "[--keep-config]",
+        "remove the shell hooks, caches and state, and the config unless kept",
+    ),
+    ("upgrade-all", "", "upgrade the packages in every box"),
+    (
+        "forget",
+        "<command> | --box <name>",
+        "drop what is known about a command or a box",
+    ),
+    ("doctor", "", "check what the handler depends on"),
+    (
+        "calibrate",
+        "[--apply]",
+        "propose a box order and pins from the commands resolved so far",
+    ),
+    (
+        "config",
+        "get <key> | set <key> <value>",
+        "read or change one setting, keeping the rest of the file as it is",
+    ),
+    (
+        "gc",
+        "",
+        "prune old traces, transcripts, indexes and cache entries",
+    ),
+    (
+        "why",
+        "<command>",
+        "explain how the last lookup of a command went",
+    ),
+    (
+        "env",
+        "--box <name> --commands <command,...>",
+        "print exports that send these commands to one box, e.g. for make",
+    ),
+    (
+        "shell",
+        "[box | command]",
+        "open a shell in a box, the one a command lives in, or the last one used",
+    ),
+    (
+        "stats",
+        "[--prometheus]",
+        "show lookup latency percentiles per phase and box",
+    ),
+    (
+        "top",
+        "",
+        "watch the daemon's lookups live, with the process that asked",
+    ),
+    (
+        "events",
+        "",
+        "print the handler's events as JSON lines, for status bars",
+    ),
+    (
+        "status",
+        "[--format text|waybar|polybar] [--follow]",
+        "show what the handler last did, as a status bar module",
+    ),
+    (
+        "version",
+        "[--json]",
+        "print the version and build features",
+    ),
+    (
+        "pipeline",
+        "[--] '<command> | <command> ...'",
+        "run a pipeline whose commands live in different boxes",
+    ),
+    (
+        "generate",
+        "man | bash | zsh | fish",
+        "print a man page or shell completions",
+    ),
+];
+
+// `generate man|bash|zsh|fish`: output for packagers to ship alongside the binary
+pub fn generate_command(args: &[String]) {
+    let text = match args.first().map(|s| s.as_str()) {
+        Some("man") if args.len() == 1 => man(),
+        Some("bash") if args.len() == 1 => bash(),
+        Some("zsh") if args.len() == 1 => zsh(),
+        Some("fish") if args.len() == 1 => fish(),
+        _ => {
+            eprintln!("Usage: {} generate man | bash | zsh | fish", NAME);
+            exit(1);
+        }
+    };
+    print!("{}", text);
+}
+
+fn flag_synopsis((flag, value, _): &(&str, Option<&str>, &str)) -> String {
+    match value {
+        Some(value) => format!("{} <{}>", flag, value),
+        None => flag.to_string(),
+    }
+}
+
+// roff only treats these specially at the start of a line or after a backslash
+fn roff(text: &str) -> String {
+    let text = text.replace('\\', "\\e").replace('-', "\\-");
+    match text.starts_with(['.', '\'']) {
+        true => format!("\\&{}", text),
+        false => text,
+    }
+}
+
+fn man() -> String {
+    let mut out = format!(
+        ".TH {} 1 \"\" \"{} {}\"\n",
+        roff(&NAME.to_uppercase()),
+        roff(NAME),
+        env!("CARGO_PKG_VERSION")
+    );
+    out += &format!(
+        ".SH NAME\n{} \\- find missing commands in distrobox containers\n",
+        roff(NAME)
+    );
+    out += ".SH SYNOPSIS\n";
+    out += &format!(
+        ".B {}\n[flags...] [\\-\\-] <command> [args...]\n",
+        roff(NAME)
+    );
+    for (name, args, _) in SUBCOMMANDS {
+        out += &format!(".br\n.B {} {}\n{}\n", roff(NAME), roff(name), roff(args));
+    }
+    out += ".SH DESCRIPTION\n";
+    out += "Called by the shell when a command is not found, it looks for the command in \
+            each distrobox container and runs it in the first one that has it.\n";
+    out += ".PP\nA first word that names one of the COMMANDS below, or starts with \\-, is \
+            read as that command or flag. A command of the same name, such as one called \
+            gc, status or \\-i, goes after \\-\\-, which is how \
+            the shell hooks pass every command.\n";
+    out += ".SH OPTIONS\n";
+    for flag in &FLAGS {
+        out += &format!(".TP\n.B {}\n{}\n", roff(&flag_synopsis(flag)), roff(flag.2));
+    }
+    out += ".SH COMMANDS\n";
+    for (name, args, description) in SUBCOMMANDS {
+        out += &format!(
+            ".TP\n.B {} {}\n{}\n",
+            roff(name),
+            roff(args),
+            roff(description)
+        );
+    }
+    out += ".SH EXIT STATUS\n";
+    out += "The command's own status when it was found, 127 when it was found nowhere or \
+            fullscreen.policy = \"skip\" left it out.\n";
+    out += ".SH FILES\n";
+    out += ".TP\n.I ~/.config/distrobox\\-cnf/config.toml\nthe handler's settings\n";
+    out += ".TP\n.I ~/.config/distrobox\\-cnf/locale/\ntranslations of the handler's messages\n";
+    out += ".SH SEE ALSO\n.BR distrobox (1)\n";
+    out
+}
+
+fn names() -> String {
+    SUBCOMMANDS.map(|(name, _, _)| name).join(" ")
+}
+
+fn flag_names() -> String {
+    FLAGS.map(|(flag, _, _)| flag).join(" ")
+}
+
+fn bash() -> String {
+    format!(
+        r#"# bash completion for {name}
+_{fn_name}() {{
+    local cur prev
+    cur="${{COMP_WORDS[COMP_CWORD]}}"
+    prev="${{COMP_WORDS[COMP_CWORD-1]}}"
+    case "$prev" in
+        --shell-protocol) COMPREPLY=($(compgen -W "bash zsh fish" -- "$cur")); return ;;
+        --trace) COMPREPLY=($(compgen -W "json" -- "$cur")); return ;;
+        --output) COMPREPLY=($(compgen -W "text json" -- "$cur")); return ;;
+        --format) COMPREPLY=($(compgen -W "text waybar polybar" -- "$cur")); return ;;
+        --trace-file) COMPREPLY=($(compgen -f -- "$cur")); return ;;
+        generate) COMPREPLY=($(compgen -W "man bash zsh fish" -- "$cur")); return ;;
+        cache) COMPREPLY=($(compgen -W "show clear invalidate" -- "$cur")); return ;;
+    esac
+    if [ "$COMP_CWORD" -eq 1 ]; then
+        COMPREPLY=($(compgen -W "{names} {flags}" -- "$cur"))
+    elif [[ "$cur" == -* ]]; then
+        COMPREPLY=($(compgen -W "{flags}" -- "$cur"))
+    else
+        COMPREPLY=($(compgen -c -- "$cur"))
+    fi
+}}
+complete -F _{fn_name} {name}
+"#,
+        name = NAME,
+        fn_name = NAME.replace('-', "_"),
+        names = names(),
+        flags = flag_names(),
+    )
+}
+
+// zsh and fish descriptions are single-quoted
+fn quoted(text: &str) -> String {
+    text.replace('\'', "'\\''")
+}
+
+fn zsh() -> String {
+    let mut out = format!("#compdef {}\n\n_{}() {{\n", NAME, NAME.replace('-', "_"));
+    out += "    local -a subcommands\n    subcommands=(\n";
+    for (name, _, description) in SUBCOMMANDS {
+        out += &format!("        '{}:{}'\n", name, quoted(description));
+    }
+    out += "    )\n    _arguments -s \\\n";
+    for (flag, value, description) in FLAGS.iter().filter(|(flag, _, _)| *flag != "--") {
+        let choices = match value {
+            Some("path") => ":path:_files".to_string(),
+            Some(value) if value.contains('|') => {
+                format!(":{}:({})", value, value.replace('|', " "))
+            }
+            Some(value) => format!(":{}:", value),
+            None => String::new(),
+        };
+        out += &format!(
+            "        '{}[{}]{}' \\\n",
+            flag,
+            quoted(description),
+            choices
+        );
+    }
+    out += "        '1: :->first' \\\n        '*:: :->rest'\n";
+    out += "    case $state in\n";
+    out += "        first) _describe 'command' subcommands; _command_names ;;\n";
+    out += "        rest) _normal ;;\n";
+    out += "    esac\n}\n\n";
+    out += &format!("_{} \"$@\"\n", NAME.replace('-', "_"));
+    out
+}
+
+fn fish() -> String {
+    let mut out = format!("# fish completion for {}\ncomplete -c {} -f\n", NAME, NAME);
+    for (name, _, description) in SUBCOMMANDS {
+        out += &format!(
+            "complete -c {} -n __fish_use_subcommand -a {} -d '{}'\n",
+            NAME,
+            name,
+            quoted(description)
+        );
+    }
+    for (flag, value, description) in FLAGS.iter().filter(|(flag, _, _)| *flag != "--") {
+        let long = flag.trim_start_matches('-');
+        let argument = match value {
+            Some("path") => " -r -F".to_string(),
+            Some(value) => format!(" -x -a '{}'", value.replace('|', " ")),
+            None => String::new(),
+        };
+        out += &format!(
+            "complete -c {} -l {}{} -d '{}'\n",
+            NAME,
+            long,
+            argument,
+            quoted(description)
+        );
+    }
+    out += &format!(
+        "complete -c {} -n '__fish_seen_subcommand_from generate' -a 'man bash zsh fish'\n",
+        NAME
+    );
+    out += &format!(
+        "complete -c {} -n '__fish_seen_subcommand_from cache' -a 'show clear invalidate'\n",
+        NAME
+    );
+    out += &format!(
+        "complete -c {} -n __fish_use_subcommand -a '(__fish_complete_command)'\n",
+        NAME
+    );
+    out
+}
@@ -0,0 +1,40 @@
+use std::io;
+
+use crate::config::Config;
+use crate::distrobox;
+
+// commands that are no use without a GPU, unless gpu.commands overrides them
+const DEFAULT_COMMANDS: [&str; 6] = [
+    "nvidia-smi",
+    "nvtop",
+    "blender",
+    "glxinfo",
+    "vulkaninfo",
+    "clinfo",
+];
+const DEVICES: &str =
+    "{{range .HostConfig.Devices}}{{.PathOnHost}} {{end}}{{range .Mounts}}{{.Source}} {{end}}";
+
+pub fn wanted(config: &Config, command: &str) -> bool {
+    match config.get_list("gpu.commands") {
+        Some(commands) => commands.iter().any(|c| c == command),
+        None => DEFAULT_COMMANDS.contains(&command),
+    }
+}
+
+// every distrobox shares /dev, so only explicit passthrough sets a box apart: the driver
+// mounts `distrobox create --nvidia` adds, or devices passed with --device
+fn passthrough(paths: &str) -> bool {
+    paths
+        .split_whitespace()
+        .any(|p| p.contains("nvidia") || p.starts_with("/dev/dri/") || p.starts_with("/dev/kfd"))
+}
+
+// the names among `names` whose container has GPU passthrough
+pub fn capable<'a>(names: impl IntoIterator<Item = &'a str>) -> io::Result<Vec<String>> {
+    Ok(distrobox::inspect(names, DEVICES)?
+        .into_iter()
+        .filter(|(_, paths)| passthrough(paths))
+        .map(|(name, _)| name)
+        .collect())
+}
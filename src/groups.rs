@@ -0,0 +1,41 @@
+use std::env;
+use std::path::PathBuf;
+
+use crate::config::Config;
+use crate::paths;
+
+// directory trees that keep to a group of boxes, e.g.
+//   [directories]
+//   "~/work/**" = ["corp-ubuntu", "corp-tools"]
+const TABLE: &str = "directories";
+
+// "~/work/**", "~/work/" and "~/work" all name the tree under ~/work
+fn root(pattern: &str) -> Option<PathBuf> {
+    let pattern = pattern.trim_end_matches("**").trim_end_matches('/');
+    match pattern.strip_prefix('~') {
+        Some(rest) => Some(paths::home().ok()?.join(rest.trim_start_matches('/'))),
+        None => Some(PathBuf::from(pattern)),
+    }
+}
+
+// the boxes of the deepest tree the working directory is in, None outside all of them
+pub fn current(config: &Config) -> Option<Vec<String>> {
+    let cwd = env::current_dir().ok()?;
+    let (_, pattern) = config
+        .keys_under(TABLE)
+        .into_iter()
+        .filter_map(|pattern| Some((root(pattern)?, pattern)))
+        .filter(|(root, _)| cwd.starts_with(root))
+        .max_by_key(|(root, _)| root.components().count())?;
+    config.get_list(&format!("{}.{}", TABLE, pattern))
+}
+
+// whether box `name` may be used from here
+pub fn allows(config: &Config, name: &str) -> bool {
+    excluding(config, name).is_none()
+}
+
+// the group that keeps box `name` out of the working directory, for saying so
+pub fn excluding(config: &Config, name: &str) -> Option<Vec<String>> {
+    current(config).filter(|group| !group.iter().any(|b| b == name))
+}
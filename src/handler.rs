@@ -0,0 +1,1203 @@
+use std::env;
+use std::io::{self, ErrorKind, IsTerminal};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Instant;
+
+use crate::affinity;
+use crate::arch;
+use crate::builtins::{self, Explanation};
+use crate::cache::{Cache, Resolution};
+use crate::caller;
+use crate::cli::Options;
+use crate::coldstart;
+use crate::color::{self, Style};
+use crate::config::{self, Config};
+use crate::cooldown;
+#[cfg(feature = "daemon")]
+use crate::daemon;
+use crate::display;
+use crate::distrobox::{self, get_boxes, DistroboxInstance, EnterOutcome, ProbeOutcome};
+use crate::events;
+use crate::flatpak;
+use crate::fullscreen;
+use crate::gc;
+use crate::gpu;
+use crate::groups;
+use crate::host;
+use crate::i18n::tr;
+use crate::index;
+use crate::json::Json;
+use crate::msg::{self, say};
+#[cfg(feature = "tui")]
+use crate::pick;
+#[cfg(feature = "tui")]
+use crate::pins;
+use crate::pkgmgr;
+use crate::progress::Progress;
+use crate::protocol::{self, Protocol};
+use crate::report::{self, Report};
+use crate::resolve::{self, Resolved};
+use crate::setup;
+use crate::shebang::{self, Shebang};
+use crate::shim;
+use crate::storm;
+use crate::strategy::{Step, Strategy};
+use crate::sudo;
+use crate::suggest;
+#[cfg(feature = "tui")]
+use crate::tasks;
+use crate::title::Title;
+use crate::trace::Trace;
+use crate::workdir;
+use crate::wsl;
+
+// cache-only mode for scripts and build systems: never list, probe or search
+const FAST_VAR: &str = "DISTROBOX_CNF_FAST";
+
+fn fast_mode() -> bool {
+    matches!(env::var(FAST_VAR).as_deref(), Ok("1" | "true" | "yes"))
+}
+
+// the command-not-found path: find a box providing args[0] and run it there; returns the exit code
+pub fn handle(opts: Options, args: Vec<String>) -> i32 {
+    // the very first run on the host offers to set things up before getting on with it
+    if !opts.quiet
+        && !opts.run
+        && !host::in_container()
+        && !config::read_only()
+        && config::config_path().is_ok_and(|p| !p.exists())
+    {
+        setup::offer();
+    }
+    report::set_json(opts.json);
+    if let Some(pattern) = &opts.copy_back {
+        workdir::set_copy_back(pattern.clone());
+    }
+    let config = Config::load();
+    msg::set_quiet(opts.quiet || matches!(&config, Ok(c) if c.get_bool("quiet") == Some(true)));
+    let config = config.unwrap_or_else(|e| {
+        // a broken config must not take the handler down with it
+        say!("{}", tr!("config-ignored", error = e));
+        Config::default()
+    });
+    let mut trace = Trace::new(&args[0]);
+    events::emit("resolution-started", vec![("command", Json::str(&args[0]))]);
+    let mut exit = search(&opts, &config, &args, &mut trace);
+    // the distro's own handler, e.g. PackageKit's, gets the last word on a miss; not for
+    // `run`, nor after an alias hint or a deliberate decline
+    let declined = trace.decision() == "alias" || trace.decision().starts_with("declined");
+    if let (false, Err(3), false) = (opts.run, exit, declined) {
+        if let Some(code) = chain(&config, "fallback", &args, &mut trace) {
+            exit = Ok(code);
+        }
+    }
+    match (trace.ran_in(), &exit) {
+        (Some(name), Ok(code)) => events::emit(
+            "finished",
+            vec![
+                ("command", Json::str(&args[0])),
+                ("box", Json::str(name)),
+                ("exit", Json::Num(*code as f64)),
+            ],
+        ),
+        _ => events::emit(
+            "failed",
+            vec![
+                ("command", Json::str(&args[0])),
+                ("reason", Json::str(trace.decision())),
+            ],
+        ),
+    }
+    if opts.timings {
+        eprintln!("{}", trace.timings());
+    }
+    if let Some(target) = &opts.trace {
+        if let Err(e) = trace.write(target) {
+            say!("{}", tr!("cannot-write-trace", error = format!("{:?}", e)));
+        }
+    }
+    if let (true, Some(name)) = (affinity::enabled(&config), trace.ran_in()) {
+        let _ = affinity::remember(name);
+    }
+    // the shims and lines for the shell are state as much as the cache is
+    if let (Some(protocol), Some(name), false) =
+        (opts.shell_protocol, trace.ran_in(), config::read_only())
+    {
+        hash(&config, protocol, &args[0], name);
+    }
+    let _ = trace.save();
+    gc::auto(&config);
+    match (opts.shell_protocol, exit) {
+        // the script goes on, but it can tell the command never ran; the handler said why,
+        // so the shell's own "command not found" would only mislead
+        (_, Err(SKIPPED)) => SKIPPED,
+        // `run` is for scripts: the command's status, 127 when no box has it as with a
+        // shell, and 125 when the handler itself failed, as with env(1)
+        (_, Ok(code)) if opts.run => code,
+        (_, Err(3)) if opts.run => 127,
+        (_, Err(_)) if opts.run => 125,
+        (Some(protocol), exit) => protocol.exit(&args[0], exit.ok()),
+        // a command that ran is the handler succeeding, whatever the command returned
+        (None, Ok(_)) => 0,
+        (None, Err(code)) => code,
+    }
+}
+
+// Ok with the command's exit code once it ran, Err with the handler's own otherwise
+type Exit = Result<i32, i32>;
+
+// a full-screen command fullscreen.policy = "skip" left out: not run, as with a missing one
+const SKIPPED: i32 = 127;
+
+// session_functions = true has the hook define a function for the rest of the session
+// that goes straight to the box. Otherwise hash = "hint" says how to make the shell skip
+// the lookup next time, and "hook" has the hook do it, saying it only under a hook too
+// old to take the line in itself
+fn hash(config: &Config, protocol: Protocol, command: &str, name: &str) {
+    if config.get_bool("session_functions") == Some(true) {
+        let line = env::current_exe()
+            .ok()
+            .and_then(|exe| protocol.function_line(command, name, &exe));
+        if line.is_some_and(|line| protocol::replay(&line).unwrap_or(false)) {
+            return;
+        }
+    }
+    let hook = match config.get_str("hash") {
+        Some("hint") => false,
+        Some("hook") => true,
+        _ => return,
+    };
+    // a command run by its path is not looked up, so there is nothing to hash
+    if !protocol.hashes() || !shim::plain_name(command) {
+        return;
+    }
+    let line = match shim::hashed(name, command) {
+        Ok(shim) => protocol.hash_line(command, &shim),
+        Err(e) => {
+            say!("{}", tr!("cannot-write-shim", command = command, error = e));
+            return;
+        }
+    };
+    let Some(line) = line else {
+        return;
+    };
+    if hook && protocol::replay(&line).unwrap_or(false) {
+        return;
+    }
+    say!(
+        "{}",
+        tr!(
+            "hash-hint",
+            command = color::err(command, Style::Command),
+            box = color::err(name, Style::BoxName),
+            line = line
+        )
+    );
+}
+
+// another command-not-found handler, `fallback` after the boxes or `pre_handler` before
+// them, e.g. "/usr/libexec/pk-command-not-found" or a list with arguments of its own; it
+// gets the original command line. None when there is none or it cannot run
+fn chain(config: &Config, key: &'static str, args: &[String], trace: &mut Trace) -> Option<i32> {
+    let command = match config.get_list(key) {
+        Some(list) => list,
+        None => vec![config.get_str(key)?.to_string()],
+    };
+    let (program, own_args) = command.split_first()?;
+    let started = Instant::now();
+    match Command::new(program).args(own_args).args(args).status() {
+        Ok(status) => {
+            let code = status.code().unwrap_or(1);
+            trace.step("host", key, started, &format!("exit {}", code));
+            Some(code)
+        }
+        Err(e) => {
+            say!(
+                "{}",
+                tr!("cannot-chain", key = key, program = program, error = e)
+            );
+            trace.step("host", key, started, "failed");
+            None
+        }
+    }
+}
+
+fn search(opts: &Options, config: &Config, args: &[String], trace: &mut Trace) -> Exit {
+    // inside a box there are no boxes to search; go to the host instead
+    if host::in_container() {
+        if host::passthrough_enabled() && !opts.host {
+            let started = Instant::now();
+            return match host::forward_to_host(args) {
+                Ok(code) => {
+                    trace.step("host", "forward", started, &format!("exit {}", code));
+                    trace.decide("forwarded to host handler");
+                    // the host handler only says whether the command ran, not how it went
+                    if code == 0 {
+                        Ok(0)
+                    } else {
+                        Err(code)
+                    }
+                }
+                Err(e) => {
+                    say!("{}", tr!("cannot-forward", error = format!("{:?}", e)));
+                    trace.decide("forward failed");
+                    Err(1)
+                }
+            };
+        }
+        return run_on_host(args, trace);
+    } else if opts.host {
+        say!("{}", tr!("host-only"));
+        return Err(1);
+    }
+    let command = &args[0];
+    // `run` is asked for by name; the hook is not, so unattended it stays a plain miss
+    if let (false, Some(why)) = (opts.run, caller::declined(config)) {
+        if opts.shell_protocol.is_none() {
+            say!("{}", tr!("declined", command = command));
+        }
+        trace.decide(&format!("declined, {}", why));
+        return Err(3);
+    }
+    // a handler of the user's own goes first; exiting 127, as a shell does for a missing
+    // command, hands it on to the boxes, anything else means it dealt with it
+    if !opts.run {
+        match chain(config, "pre_handler", args, trace) {
+            Some(127) | None => {}
+            Some(code) => {
+                trace.decide("handled by the pre handler");
+                return Ok(code);
+            }
+        }
+    }
+    // a full-screen program started from a script would wait on a screen nobody sees
+    if fullscreen::wanted(config, command) && !io::stdin().is_terminal() {
+        match fullscreen::policy(config) {
+            fullscreen::Policy::Fail => {
+                say!(
+                    "{}",
+                    tr!(
+                        "fullscreen-refused",
+                        command = color::err(command, Style::Command)
+                    )
+                );
+                trace.decide("needs a terminal");
+                return Err(1);
+            }
+            fullscreen::Policy::Skip => {
+                say!(
+                    "{}",
+                    tr!(
+                        "fullscreen-skipped",
+                        command = color::err(command, Style::Command)
+                    )
+                );
+                trace.decide("skipped, needs a terminal");
+                return Err(SKIPPED);
+            }
+            fullscreen::Policy::Tty => distrobox::force_pty(),
+            fullscreen::Policy::Run => {}
+        }
+    }
+    if let Some(name) = &opts.box_name {
+        return run_in_box(name, args, trace);
+    }
+    match builtins::explain(command) {
+        Some(Explanation::Builtin(shell)) => {
+            say!(
+                "{}",
+                tr!(
+                    "shell-builtin",
+                    command = color::err(command, Style::Command),
+                    shell = shell
+                )
+            );
+            trace.decide(&format!("{} builtin", shell));
+            return Err(3);
+        }
+        Some(Explanation::Alias(expansion)) => {
+            say!(
+                "{}",
+                tr!(
+                    "shell-alias",
+                    command = color::err(command, Style::Command),
+                    expansion = expansion
+                )
+            );
+            trace.decide("alias");
+            return Err(3);
+        }
+        None => {}
+    }
+    if let Some(at) = sudo::target(args) {
+        return privileged(config, args, at, trace);
+    }
+    // a path names one file, so there is nothing to look up by name or cache
+    if command.contains('/') {
+        return by_path(config, args, trace);
+    }
+    // a broken cache must never break the handler, start fresh instead
+    let started = Instant::now();
+    let mut cache = Cache::load().unwrap_or_else(|_| Cache::empty());
+    let strategy = Strategy::load(config);
+    let cached = cache
+        .lookup(command)
+        .map(|e| e.resolution.clone())
+        .filter(|_| strategy.has(Step::Cache));
+    let cache_result = match &cached {
+        Some(Resolution::Found(name)) => format!("hit {}", name),
+        Some(Resolution::NotFound) => "hit not found".to_string(),
+        None => "miss".to_string(),
+    };
+    trace.step("cache", "lookup", started, &cache_result);
+    let mut title = Title::new(config.get_bool("terminal_title") == Some(true));
+    // a build tool or cron job can have rules of its own, found by walking up the parents
+    let started = Instant::now();
+    let caller = caller::rule(config);
+    if let Some(rule) = &caller {
+        trace.step("host", "caller", started, &rule.process);
+    }
+    // a caller's box or a remembered choice wins over the cache; a stale one falls through
+    // to the search
+    let pinned = resolve::pinned(config, command, caller.as_ref(), trace);
+    if let Some((name, why)) = pinned {
+        let started = Instant::now();
+        title.set(command, &name);
+        if let Ok(EnterOutcome::Ran(code)) = distrobox::enter_retrying(&name, args) {
+            trace.step(&name, "run", started, "ran");
+            trace.decide(&format!("ran in {} ({})", name, why));
+            return Ok(code);
+        }
+        trace.step(&name, "run", started, "not found");
+    }
+    if fast_mode() {
+        let why = tr!("fast-var-set", var = FAST_VAR);
+        return fast_path(config, command, args, cached, &mut title, trace, &why);
+    }
+    // the same command again right away is a loop somewhere, not a person; answer it
+    // from the cache instead of listing and probing every time
+    if storm::repeated(config, command) {
+        return fast_path(
+            config,
+            command,
+            args,
+            cached,
+            &mut title,
+            trace,
+            &tr!("repeated-too-quickly"),
+        );
+    }
+    if cached == Some(Resolution::NotFound) {
+        // skip listing boxes on this fast path, suggestions come from whatever is indexed
+        trace.decide("not found (cached)");
+        return not_found(command, &indexed_boxes(), false, config, trace);
+    }
+    let wants_gpu = gpu::wanted(config, command);
+    // without a graphical session there is nothing to check, the program fails on its own terms
+    let sockets = if display::wanted(config, command) {
+        display::sockets()
+    } else {
+        vec![]
+    };
+    // the box this terminal last ran something in goes first; it only gets the chance
+    // in a full search, so the shortcuts below step aside when it is another box
+    let affine = affinity::preferred(config, command);
+    // the common case: the cache names a box that can be entered by name alone, so skip
+    // listing everything and go straight there; a miss falls through to the full search
+    if let Some(Resolution::Found(name)) = &cached {
+        if affine.as_ref().is_none_or(|a| a == name)
+            && !wants_gpu
+            && sockets.is_empty()
+            && distrobox::direct(name)
+            && !cooldown::cooling(name)
+            && !arch::barred(config, command, name)
+            && groups::allows(config, name)
+        {
+            let started = Instant::now();
+            title.set(command, name);
+            if let Ok(EnterOutcome::Ran(code)) = distrobox::enter_retrying(name, args) {
+                trace.step(name, "run", started, "ran");
+                trace.decide(&format!("ran in {} (cached)", name));
+                cache.record(command, Resolution::Found(name.clone()));
+                let _ = cache.save();
+                return Ok(code);
+            }
+            trace.step(name, "run", started, "not found");
+        }
+    }
+    // a running daemon has the answer in memory; it only resolves, running stays with us
+    let started = Instant::now();
+    match query_daemon(command).filter(|_| affine.is_none()) {
+        // an emulated box the arch policy keeps this command out of is no answer, look
+        // for a native one
+        Some(Resolved::Found(name)) if arch::barred(config, command, &name) => {
+            trace.step(
+                "daemon",
+                "query",
+                started,
+                &format!("found {}, emulated", name),
+            );
+        }
+        // the daemon searched from its own directory, not ours
+        Some(Resolved::Found(name)) if !groups::allows(config, &name) => {
+            trace.step(
+                "daemon",
+                "query",
+                started,
+                &format!("found {}, outside this directory's group", name),
+            );
+        }
+        // a box that cannot reach the display is no answer, search properly
+        Some(Resolved::Found(name))
+            if !sockets.is_empty()
+                && display::capable([name.as_str()], &sockets).is_ok_and(|c| c.is_empty()) =>
+        {
+            trace.step(
+                "daemon",
+                "query",
+                started,
+                &format!("found {}, no display", name),
+            );
+        }
+        Some(Resolved::Found(name)) => {
+            trace.step("daemon", "query", started, &format!("found {}", name));
+            if wants_gpu && gpu::capable([name.as_str()]).is_ok_and(|c| c.is_empty()) {
+                warn_no_gpu(command, &name);
+            }
+            let started = Instant::now();
+            title.set(command, &name);
+            if let Ok(EnterOutcome::Ran(code)) = distrobox::enter_retrying(&name, args) {
+                trace.step(&name, "run", started, "ran");
+                trace.decide(&format!("ran in {}", name));
+                cache.record(command, Resolution::Found(name));
+                let _ = cache.save();
+                return Ok(code);
+            }
+            // the daemon was wrong or the box broke, search properly
+            trace.step(&name, "run", started, "not found");
+        }
+        Some(Resolved::NotFound) => {
+            trace.step("daemon", "query", started, "not found");
+            // the daemon answers for the boxes only; the rest of a miss is as after a search
+            if strategy.has(Step::Flatpak) {
+                if let Some(exit) = try_flatpak(command, args, trace) {
+                    return exit;
+                }
+            }
+            trace.decide("not found (daemon)");
+            return not_found(command, &indexed_boxes(), false, config, trace);
+        }
+        Some(Resolved::Incomplete) => trace.step("daemon", "query", started, "incomplete"),
+        None => {}
+    }
+    // only a few handlers list and probe at once; the runs themselves are not limited
+    let started = Instant::now();
+    let slot = match storm::slot(config) {
+        Ok(Some(slot)) => Some(slot),
+        Ok(None) => {
+            say!("{}", tr!("too-many-searches", command = command));
+            trace.step("host", "slot", started, "busy");
+            trace.decide("too many searches");
+            return Err(1);
+        }
+        // without a lock directory there is no limiting, but no reason to fail either
+        Err(_) => None,
+    };
+    trace.step("host", "slot", started, "taken");
+    let started = Instant::now();
+    let mut boxes: Vec<DistroboxInstance> = match get_boxes() {
+        Ok(box_list) => resolve::order_boxes(box_list, cached.as_ref(), config),
+        // still busy after waiting; what is already known beats failing outright
+        Err(e) if e.kind() == ErrorKind::ResourceBusy => {
+            trace.step("host", "list", started, "storage locked");
+            drop(slot);
+            let why = tr!("storage-locked");
+            return fast_path(config, command, args, cached, &mut title, trace, &why);
+        }
+        Err(e) => {
+            say!("{}", tr!("cannot-list", error = format!("{:?}", e)));
+            trace.step("host", "list", started, "failed");
+            trace.decide("cannot list boxes");
+            return Err(2);
+        }
+    };
+    trace.step("host", "list", started, &format!("{} boxes", boxes.len()));
+    let resolve::Route {
+        gpu_boxes,
+        headless,
+    } = resolve::route(
+        config,
+        command,
+        &mut boxes,
+        affine.as_deref(),
+        wants_gpu,
+        &sockets,
+        trace,
+    );
+    #[cfg(feature = "tui")]
+    if config.get_bool("pick") == Some(true)
+        && caller.as_ref().and_then(|r| r.pick) != Some(false)
+        && pick::interactive()
+    {
+        choose(command, &mut boxes, headless.as_deref(), trace);
+    }
+    drop(slot);
+    let order: Vec<&str> = boxes.iter().map(|b| b.name.as_str()).collect();
+    trace.step("host", "order", Instant::now(), &order.join(", "));
+    let cooling: Vec<String> = cooldown::load()
+        .into_iter()
+        .filter(|f| f.cooling())
+        .map(|f| f.name)
+        .collect();
+    let progress = Progress::new();
+    let mut failed: usize = 0;
+    let mut flatpak_tried = false;
+    for box_inst in &boxes {
+        if !flatpak_tried && strategy.flatpak_before(box_inst.running) {
+            flatpak_tried = true;
+            progress.pause();
+            if let Some(exit) = try_flatpak(command, args, trace) {
+                return exit;
+            }
+        }
+        // a box that keeps failing is not retried on every typo
+        if cooling.contains(&box_inst.name) {
+            trace.step(&box_inst.name, "skip", Instant::now(), "cooling down");
+            failed += 1;
+            continue;
+        }
+        progress.searching(&box_inst.name);
+        if headless
+            .as_ref()
+            .is_some_and(|h| h.contains(&box_inst.name))
+        {
+            let started = Instant::now();
+            match distrobox::probe_retrying(&box_inst.name, command) {
+                Ok(ProbeOutcome::Found) => {
+                    trace.step(&box_inst.name, "probe", started, "found, no display");
+                    trace.decide("no display in box");
+                    drop(progress);
+                    return no_display(command, &box_inst.name, &sockets);
+                }
+                Ok(ProbeOutcome::NotFound) => {
+                    trace.step(&box_inst.name, "probe", started, "not found");
+                    continue;
+                }
+                Ok(ProbeOutcome::BoxFailed(_) | ProbeOutcome::Unknown) | Err(_) => {
+                    trace.step(&box_inst.name, "probe", started, "failed");
+                    continue;
+                }
+            }
+        }
+        // a cold start is the slow part; when someone is watching, do it in a probe
+        // while the status line is up, and only run once the command is known to exist
+        if progress.enabled() && !box_inst.running {
+            let started = Instant::now();
+            match distrobox::probe_retrying(&box_inst.name, command) {
+                Ok(ProbeOutcome::Found) => {
+                    let _ = coldstart::record(&box_inst.name, started.elapsed());
+                    trace.step(&box_inst.name, "probe", started, "found");
+                }
+                Ok(ProbeOutcome::NotFound) => {
+                    let _ = coldstart::record(&box_inst.name, started.elapsed());
+                    trace.step(&box_inst.name, "probe", started, "not found");
+                    continue;
+                }
+                // let the run below find out or report it
+                Ok(ProbeOutcome::BoxFailed(_) | ProbeOutcome::Unknown) | Err(_) => {}
+            }
+        }
+        progress.pause();
+        if gpu_boxes
+            .as_ref()
+            .is_some_and(|c| !c.contains(&box_inst.name))
+        {
+            warn_no_gpu(command, &box_inst.name);
+        }
+        let started = Instant::now();
+        title.set(command, &box_inst.name);
+        match distrobox::enter_retrying(&box_inst.name, args) {
+            Ok(EnterOutcome::Ran(code)) => {
+                trace.step(&box_inst.name, "run", started, "ran");
+                trace.decide(&format!("ran in {}", box_inst.name));
+                let _ = cooldown::recovered(&box_inst.name);
+                cache.record(command, Resolution::Found(box_inst.name.clone()));
+                let _ = cache.save();
+                return Ok(code);
+            }
+            Ok(EnterOutcome::NotFound) => {
+                trace.step(&box_inst.name, "run", started, "not found");
+            }
+            Ok(EnterOutcome::BoxFailed(reason)) => {
+                trace.step(
+                    &box_inst.name,
+                    "run",
+                    started,
+                    &format!("box failed: {}", reason),
+                );
+                say!(
+                    "{}",
+                    tr!(
+                        "box-failed",
+                        box = color::err(&box_inst.name, Style::BoxName),
+                        priority = box_inst.priority,
+                        reason = color::err(&reason, Style::Bad)
+                    )
+                );
+                if cooldown::failed(config, &box_inst.name) {
+                    say!(
+                        "{}",
+                        tr!(
+                            "box-cooldown",
+                            box = color::err(&box_inst.name, Style::BoxName)
+                        )
+                    );
+                }
+                failed += 1;
+            }
+            Ok(EnterOutcome::Skipped(reason)) => {
+                trace.step(
+                    &box_inst.name,
+                    "run",
+                    started,
+                    &format!("skipped: {}", reason),
+                );
+                skipped(command, &box_inst.name, &reason);
+                failed += 1;
+            }
+            Err(e) => {
+                say!("{}", tr!("cannot-enter", error = e));
+                trace.decide("cannot run distrobox-enter");
+                return Err(1);
+            }
+        }
+    }
+    drop(progress);
+    if !flatpak_tried && strategy.has(Step::Flatpak) {
+        if let Some(exit) = try_flatpak(command, args, trace) {
+            return exit;
+        }
+    }
+    if failed > 0 {
+        // some boxes were never searched, so "not found" is not a fact worth caching
+        say!(
+            "{}",
+            tr!(
+                "not-found-some-failed",
+                command = color::err(command, Style::Command),
+                failed = failed
+            )
+        );
+        trace.decide("not found, some boxes failed");
+        return Err(4);
+    }
+    // missing from a directory's group is not missing from every box
+    if groups::current(config).is_none() {
+        cache.record(command, Resolution::NotFound);
+        let _ = cache.save();
+    }
+    trace.decide("not found");
+    not_found(command, &boxes, true, config, trace)
+}
+
+// `sudo cmd` with sudo missing on the host. Host root cannot be had from a box, so by
+// default this is only explained; sudo = "box" runs it with sudo inside the box that has
+// the command, which is root in that box only
+fn privileged(config: &Config, args: &[String], at: usize, trace: &mut Trace) -> Exit {
+    let command = &args[at];
+    let sudo = color::err(&args[0], Style::Command);
+    if config.get_str("sudo") != Some("box") {
+        say!(
+            "{}",
+            tr!(
+                "sudo-explain",
+                sudo = sudo,
+                command = color::err(command, Style::Command)
+            )
+        );
+        trace.decide("privileged, explained");
+        return Err(3);
+    }
+    let started = Instant::now();
+    let boxes = match get_boxes() {
+        Ok(boxes) => resolve::order_boxes(boxes, None, config),
+        Err(e) => {
+            say!("{}", tr!("cannot-list", error = format!("{:?}", e)));
+            trace.decide("cannot list boxes");
+            return Err(2);
+        }
+    };
+    let mut cache = Cache::load().unwrap_or_else(|_| Cache::empty());
+    let resolved = resolve::resolve(command, &boxes, &mut cache, config);
+    let _ = cache.save();
+    let Resolved::Found(name) = resolved else {
+        trace.step("host", "resolve", started, "not found");
+        trace.decide("privileged, not found");
+        return not_found(command, &boxes, true, config, trace);
+    };
+    trace.step("host", "resolve", started, &format!("found {}", name));
+    let started = Instant::now();
+    match distrobox::enter_retrying(&name, args) {
+        Ok(EnterOutcome::Ran(code)) => {
+            trace.step(&name, "run", started, "ran");
+            trace.decide(&format!("ran privileged in {}", name));
+            Ok(code)
+        }
+        Ok(EnterOutcome::NotFound) => {
+            trace.step(&name, "run", started, "not found");
+            say!("{}", tr!("sudo-missing-in-box", sudo = sudo, box = name));
+            Err(3)
+        }
+        Ok(EnterOutcome::Skipped(reason)) => {
+            trace.step(&name, "run", started, "skipped");
+            skipped(command, &name, &reason);
+            Err(4)
+        }
+        Ok(EnterOutcome::BoxFailed(reason)) => {
+            trace.step(&name, "run", started, "box failed");
+            say!(
+                "{}",
+                tr!(
+                    "box-failed-named",
+                    box = color::err(&name, Style::BoxName),
+                    reason = color::err(&reason, Style::Bad)
+                )
+            );
+            Err(4)
+        }
+        Err(e) => {
+            say!("{}", tr!("cannot-enter", error = e));
+            Err(1)
+        }
+    }
+}
+
+// run `args[0]`, a path, in the first box where it is an executable file
+fn by_path(config: &Config, args: &[String], trace: &mut Trace) -> Exit {
+    let path = &args[0];
+    // a script that failed on the host for want of its interpreter goes where that is
+    if let Some(shebang) = shebang::read(Path::new(path)).filter(|s| s.missing_on_host()) {
+        return by_interpreter(config, &shebang, args, trace);
+    }
+    let started = Instant::now();
+    let boxes = match get_boxes() {
+        Ok(boxes) => resolve::order_boxes(boxes, None, config),
+        Err(e) => {
+            say!("{}", tr!("cannot-list", error = format!("{:?}", e)));
+            trace.decide("cannot list boxes");
+            return Err(2);
+        }
+    };
+    trace.step("host", "list", started, &format!("{} boxes", boxes.len()));
+    for dbx in &boxes {
+        if cooldown::cooling(&dbx.name) {
+            trace.step(&dbx.name, "skip", Instant::now(), "cooling down");
+            continue;
+        }
+        let started = Instant::now();
+        match distrobox::has_path(&dbx.name, path) {
+            Ok(ProbeOutcome::Found) => trace.step(&dbx.name, "probe", started, "found"),
+            Ok(_) | Err(_) => {
+                trace.step(&dbx.name, "probe", started, "not found");
+                continue;
+            }
+        }
+        let started = Instant::now();
+        match distrobox::enter_retrying(&dbx.name, args) {
+            Ok(EnterOutcome::Ran(code)) => {
+                trace.step(&dbx.name, "run", started, "ran");
+                trace.decide(&format!("ran {} in {}", path, dbx.name));
+                return Ok(code);
+            }
+            Ok(EnterOutcome::Skipped(reason)) => {
+                trace.step(&dbx.name, "run", started, "skipped");
+                skipped(path, &dbx.name, &reason);
+            }
+            Ok(_) => trace.step(&dbx.name, "run", started, "not found"),
+            Err(e) => {
+                say!("{}", tr!("cannot-enter", error = e));
+                trace.decide("cannot run distrobox-enter");
+                return Err(1);
+            }
+        }
+    }
+    say!(
+        "{}",
+        tr!("path-not-found", path = color::err(path, Style::Command))
+    );
+    trace.decide("path not found");
+    Err(3)
+}
+
+fn by_interpreter(config: &Config, shebang: &Shebang, args: &[String], trace: &mut Trace) -> Exit {
+    let interpreter = shebang.env_program().unwrap_or(&shebang.interpreter);
+    let started = Instant::now();
+    let boxes = match get_boxes() {
+        Ok(boxes) => resolve::order_boxes(boxes, None, config),
+        Err(e) => {
+            say!("{}", tr!("cannot-list", error = format!("{:?}", e)));
+            trace.decide("cannot list boxes");
+            return Err(2);
+        }
+    };
+    // an interpreter named by env is looked up like any command, a full path must exist
+    let owner = if shebang.env_program().is_some() {
+        let mut cache = Cache::load().unwrap_or_else(|_| Cache::empty());
+        let resolved = resolve::resolve(interpreter, &boxes, &mut cache, config);
+        let _ = cache.save();
+        match resolved {
+            Resolved::Found(name) => Some(name),
+            _ => None,
+        }
+    } else {
+        boxes
+            .iter()
+            .filter(|b| !cooldown::cooling(&b.name))
+            .find(|b| {
+                matches!(
+                    distrobox::has_path(&b.name, interpreter),
+                    Ok(ProbeOutcome::Found)
+                )
+            })
+            .map(|b| b.name.clone())
+    };
+    let Some(name) = owner else {
+        trace.step("host", "interpreter", started, "not found");
+        trace.decide("interpreter not found");
+        say!(
+            "{}",
+            tr!(
+                "interpreter-not-found",
+                interpreter = color::err(interpreter, Style::Command),
+                script = &args[0]
+            )
+        );
+        return Err(3);
+    };
+    trace.step(
+        "host",
+        "interpreter",
+        started,
+        &format!("{} in {}", interpreter, name),
+    );
+    let started = Instant::now();
+    match distrobox::enter_retrying(&name, &shebang.command(args)) {
+        Ok(EnterOutcome::Ran(code)) => {
+            trace.step(&name, "run", started, "ran");
+            trace.decide(&format!("ran {} with {} in {}", args[0], interpreter, name));
+            Ok(code)
+        }
+        Ok(EnterOutcome::Skipped(reason)) => {
+            trace.step(&name, "run", started, "skipped");
+            trace.decide("arguments not visible in the box");
+            skipped(&args[0], &name, &reason);
+            Err(4)
+        }
+        Ok(_) => {
+            trace.step(&name, "run", started, "not found");
+            trace.decide("interpreter not found");
+            Err(3)
+        }
+        Err(e) => {
+            say!("{}", tr!("cannot-enter", error = e));
+            trace.decide("cannot run distrobox-enter");
+            Err(1)
+        }
+    }
+}
+
+// Some with the app's exit code when a flatpak app stands for `command`
+fn try_flatpak(command: &str, args: &[String], trace: &mut Trace) -> Option<Exit> {
+    let started = Instant::now();
+    let Some(app) = flatpak::find(command) else {
+        trace.step("host", "flatpak", started, "no app");
+        return None;
+    };
+    match flatpak::run(&app, &args[1..]) {
+        Ok(code) => {
+            trace.step("host", "flatpak", started, &format!("ran {}", app));
+            trace.decide(&format!("ran flatpak {}", app));
+            Some(Ok(code))
+        }
+        Err(e) => {
+            say!("{}", tr!("cannot-run-flatpak", error = format!("{:?}", e)));
+            trace.step("host", "flatpak", started, "failed");
+            None
+        }
+    }
+}
+
+#[cfg(feature = "daemon")]
+fn query_daemon(command: &str) -> Option<Resolved> {
+    daemon::query(command)
+}
+
+#[cfg(not(feature = "daemon"))]
+fn query_daemon(_command: &str) -> Option<Resolved> {
+    None
+}
+
+// with several boxes providing `command`, ask which one to use and move it to the front
+#[cfg(feature = "tui")]
+fn choose(
+    command: &str,
+    boxes: &mut [DistroboxInstance],
+    headless: Option<&[String]>,
+    trace: &mut Trace,
+) {
+    let started = Instant::now();
+    let candidates: Vec<&str> = boxes
+        .iter()
+        .filter(|b| !headless.is_some_and(|h| h.contains(&b.name)))
+        .map(|b| b.name.as_str())
+        .collect();
+    let found = tasks::parallel(&candidates, |name| distrobox::probe_retrying(name, command));
+    let providing: Vec<String> = candidates
+        .iter()
+        .zip(found)
+        .filter(|(_, probe)| matches!(probe, Some(Ok(ProbeOutcome::Found))))
+        .map(|(name, _)| name.to_string())
+        .collect();
+    trace.step(
+        "host",
+        "probe all",
+        started,
+        &format!("{} providing", providing.len()),
+    );
+    if providing.len() < 2 {
+        return;
+    }
+    let (name, scope) = match pick::pick(command, &providing) {
+        Ok(choice) => choice,
+        // no answer, carry on in the usual order
+        Err(_) => return,
+    };
+    if let Some(at) = boxes.iter().position(|b| b.name == name) {
+        boxes[..=at].rotate_right(1);
+    }
+    if let Some(scope) = scope {
+        match pins::remember(command, &name, scope) {
+            Ok(path) => say!(
+                "{}",
+                tr!(
+                    "remembered",
+                    box = color::err(&name, Style::BoxName),
+                    scope = scope.describe(),
+                    path = path.display()
+                )
+            ),
+            Err(e) => say!("{}", tr!("cannot-remember", error = format!("{:?}", e))),
+        }
+    }
+}
+
+// run in whichever box the cache or an index already names, or give up right away
+fn fast_path(
+    config: &Config,
+    command: &str,
+    args: &[String],
+    cached: Option<Resolution>,
+    title: &mut Title,
+    trace: &mut Trace,
+    why: &str,
+) -> Exit {
+    let target = match cached {
+        Some(Resolution::Found(name)) => Some(name),
+        Some(Resolution::NotFound) => None,
+        None => index::boxes_providing(command)
+            .into_iter()
+            .find(|name| groups::allows(config, name)),
+    };
+    if let Some(name) = target.filter(|name| groups::allows(config, name)) {
+        let started = Instant::now();
+        title.set(command, &name);
+        if let Ok(EnterOutcome::Ran(code)) = distrobox::enter(&name, args) {
+            trace.step(&name, "run", started, "ran");
+            trace.decide(&format!("ran in {} (fast)", name));
+            return Ok(code);
+        }
+        trace.step(&name, "run", started, "not found");
+    }
+    trace.decide("not found (fast)");
+    say!(
+        "{}",
+        tr!(
+            "not-in-cache",
+            command = color::err(command, Style::Command),
+            reason = why
+        )
+    );
+    Err(3)
+}
+
+// `run --box`: no search and nothing learned, the caller already knows where it goes
+fn run_in_box(name: &str, args: &[String], trace: &mut Trace) -> Exit {
+    let started = Instant::now();
+    match distrobox::enter_retrying(name, args) {
+        Ok(EnterOutcome::Ran(code)) => {
+            trace.step(name, "run", started, "ran");
+            trace.decide(&format!("ran in {} (--box)", name));
+            Ok(code)
+        }
+        Ok(EnterOutcome::NotFound) => {
+            trace.step(name, "run", started, "not found");
+            say!(
+                "{}",
+                tr!(
+                    "not-in-box",
+                    command = color::err(&args[0], Style::Command),
+                    box = color::err(name, Style::BoxName)
+                )
+            );
+            Err(3)
+        }
+        Ok(EnterOutcome::Skipped(reason)) => {
+            trace.step(name, "run", started, "skipped");
+            skipped(&args[0], name, &reason);
+            Err(4)
+        }
+        Ok(EnterOutcome::BoxFailed(reason)) => {
+            trace.step(name, "run", started, &format!("box failed: {}", reason));
+            say!(
+                "{}",
+                tr!(
+                    "box-failed-named",
+                    box = color::err(name, Style::BoxName),
+                    reason = color::err(&reason, Style::Bad)
+                )
+            );
+            Err(4)
+        }
+        Err(e) => {
+            say!("{}", tr!("cannot-enter", error = e));
+            Err(1)
+        }
+    }
+}
+
+fn no_display(command: &str, box_name: &str, sockets: &[PathBuf]) -> Exit {
+    let sockets: Vec<String> = sockets.iter().map(|s| s.display().to_string()).collect();
+    say!(
+        "{}",
+        tr!(
+            "no-display",
+            command = color::err(command, Style::Command),
+            box = color::err(box_name, Style::BoxName),
+            sockets = sockets.join(&format!(" {} ", tr!("or")))
+        )
+    );
+    if wsl::wslg() {
+        say!("{}", tr!("no-display-wsl", dir = wsl::WSLG_DIR));
+    }
+    Err(1)
+}
+
+fn skipped(command: &str, box_name: &str, reason: &str) {
+    say!(
+        "{}",
+        tr!(
+            "box-skipped",
+            command = color::err(command, Style::Command),
+            box = color::err(box_name, Style::BoxName),
+            reason = color::err(reason, Style::Bad)
+        )
+    );
+}
+
+fn warn_no_gpu(command: &str, box_name: &str) {
+    say!(
+        "{}",
+        tr!(
+            "no-gpu",
+            box = color::err(box_name, Style::BoxName),
+            command = color::err(command, Style::Command)
+        )
+    );
+}
+
+// python3 or python3.12 for python, gcc-14 for gcc
+const DEFAULT_VERSIONED: [&str; 2] = ["#", "-#"];
+
+// the boxes whatever is indexed names, for a miss answered without listing them
+fn indexed_boxes() -> Vec<DistroboxInstance> {
+    index::indexed_boxes()
+        .into_iter()
+        .map(|name| DistroboxInstance {
+            name,
+            id: String::new(),
+            manager: None,
+            connection: None,
+            priority: 0,
+            running: false,
+            image: String::new(),
+        })
+        .collect()
+}
+
+// report a miss; `listed` says `boxes` came from listing them, so none at all means
+// there are none, rather than no index
+fn not_found(
+    command: &str,
+    boxes: &[DistroboxInstance],
+    listed: bool,
+    config: &Config,
+    trace: &Trace,
+) -> Exit {
+    let mut report = Report {
+        command: command.to_string(),
+        searched: trace.searched(),
+        suggestions: vec![],
+        alternative: None,
+        builtin: None,
+        package_search: None,
+        no_boxes: listed && boxes.is_empty() && trace.searched().is_empty(),
+    };
+    if (!msg::quiet() || report::json()) && Strategy::load(config).has(Step::Suggest) {
+        let known = suggest::commands(boxes);
+        report.suggestions = suggest::suggest(command, &known);
+        // versioned_suffixes = [] turns these off
+        let rules = config
+            .get_list("versioned_suffixes")
+            .unwrap_or_else(|| DEFAULT_VERSIONED.map(String::from).to_vec());
+        report.alternative = suggest::versioned(command, &known, &rules);
+        report.builtin = builtins::foreign_builtin(command);
+        // only from what is already known; a miss must not start a box just for a hint.
+        // Package searches go to the repositories, so there is no point offline
+        if !config::offline(config) {
+            report.package_search = boxes.iter().find_map(|b| {
+                pkgmgr::for_box(b, false).map(|pm| (b.name.clone(), pm.search_hint(command)))
+            });
+        }
+    }
+    report.show();
+    Err(3)
+}
+
+fn run_on_host(args: &[String], trace: &mut Trace) -> Exit {
+    let started = Instant::now();
+    match host::run_on_host(args) {
+        Ok(Some(code)) => {
+            trace.step("host", "run", started, "ran");
+            trace.decide("ran on host");
+            Ok(code)
+        }
+        Ok(None) => {
+            trace.step("host", "run", started, "not found");
+            trace.decide("not found on host");
+            say!(
+                "{}",
+                tr!(
+                    "not-on-host",
+                    command = color::err(&args[0], Style::Command)
+                )
+            );
+            Err(3)
+        }
+        Err(e) => {
+            say!("{}", tr!("cannot-run-on-host", error = format!("{:?}", e)));
+            trace.decide("cannot run on host");
+            Err(1)
+        }
+    }
+}
@@ -0,0 +1,52 @@
+use std::env;
+use std::io;
+use std::process::Command;
+
+// opt-in: forward handler invocations made inside a box to the handler on the host
+pub const PASSTHROUGH_VAR: &str = "DISTROBOX_CNF_PASSTHROUGH";
+const HOST_EXEC: &str = "distrobox-host-exec";
+const HOST_SPAWN: &str = "host-spawn";
+const HANDLER: &str = "distrobox-cnf-handler";
+
+pub fn in_container() -> bool {
+    matches!(env::var("CONTAINER_ID"), Ok(id) if !id.trim().is_empty())
+}
+
+pub fn passthrough_enabled() -> bool {
+    matches!(
+        env::var(PASSTHROUGH_VAR).as_deref(),
+        Ok("1" | "true" | "yes")
+    )
+}
+
+// run the host's copy of the handler with the same arguments, returning its exit code
+pub fn forward_to_host(args: &[String]) -> io::Result<i32> {
+    let status = Command::new(host_executor())
+        .arg(HANDLER)
+        // the command may look like a flag; the host's handler must not take it for one
+        .arg("--")
+        .args(args)
+        .status()?;
+    Ok(status.code().unwrap_or(1))
+}
+
+// distrobox-host-exec may try to download host-spawn on first use, so prefer a local host-spawn
+fn host_executor() -> &'static str {
+    let found = env::var("PATH")
+        .map(|path| env::split_paths(&path).any(|dir| dir.join(HOST_SPAWN).is_file()))
+        .unwrap_or(false);
+    if found {
+        HOST_SPAWN
+    } else {
+        HOST_EXEC
+    }
+}
+
+// run the command itself on the host; None means the host does not have it either
+pub fn run_on_host(args: &[String]) -> io::Result<Option<i32>> {
+    let status = Command::new(host_executor()).args(args).status()?;
+    match status.code() {
+        Some(127) => Ok(None),
+        code => Ok(Some(code.unwrap_or(1))),
+    }
+}
@@ -0,0 +1,123 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use crate::paths;
+
+// the built-in messages, in the same `key = text { $arg }` form as translation files
+const ENGLISH: &str = include_str!("../locale/en.ftl");
+const SYSTEM_LOCALE_DIR: &str = "/usr/share/distrobox-cnf/locale";
+
+type Catalog = HashMap<String, String>;
+
+// Fluent's simple message syntax: comments, `key = value`, and indented lines continuing
+// the previous value; attributes, terms and selectors are not supported
+fn parse(text: &str) -> Catalog {
+    let mut catalog = Catalog::new();
+    let mut last: Option<String> = None;
+    for line in text.lines() {
+        if line.trim_start().starts_with('#') || line.trim().is_empty() {
+            last = None;
+            continue;
+        }
+        if line.starts_with(' ') {
+            if let Some(value) = last.as_ref().and_then(|k| catalog.get_mut(k)) {
+                if !value.is_empty() {
+                    value.push('\n');
+                }
+                value.push_str(line.trim());
+            }
+            continue;
+        }
+        if let Some((key, value)) = line.split_once('=') {
+            let key = key.trim().to_string();
+            catalog.insert(key.clone(), value.trim().to_string());
+            last = Some(key);
+        }
+    }
+    catalog
+}
+
+// e.g. "pt_BR.UTF-8" gives ["pt_BR", "pt"]; C and POSIX mean untranslated
+fn languages() -> Vec<String> {
+    let locale = ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .find_map(|var| env::var(var).ok().filter(|v| !v.is_empty()))
+        .unwrap_or_default();
+    let locale = locale.split(['.', '@']).next().unwrap_or_default();
+    if locale.is_empty() || locale == "C" || locale == "POSIX" {
+        return vec![];
+    }
+    let mut languages = vec![locale.to_string()];
+    if let Some((language, _)) = locale.split_once('_') {
+        languages.push(language.to_string());
+    }
+    languages
+}
+
+// a translation in the user's config dir beats one shipped by the distribution
+fn translation(language: &str) -> Option<Catalog> {
+    let file = format!("{}.ftl", language);
+    let dirs = paths::config_dir()
+        .map(|d| d.join("locale"))
+        .into_iter()
+        .chain([PathBuf::from(SYSTEM_LOCALE_DIR)]);
+    dirs.into_iter()
+        .find_map(|dir| fs::read_to_string(dir.join(&file)).ok())
+        .map(|text| parse(&text))
+}
+
+struct Catalogs {
+    translated: Vec<Catalog>,
+    english: Catalog,
+}
+
+fn catalogs() -> &'static Catalogs {
+    static CATALOGS: OnceLock<Catalogs> = OnceLock::new();
+    CATALOGS.get_or_init(|| Catalogs {
+        translated: languages().iter().filter_map(|l| translation(l)).collect(),
+        english: parse(ENGLISH),
+    })
+}
+
+// message `key` with its `{ $name }` placeholders filled in; a message missing from a
+// translation falls back to English, and an unknown key shows as itself
+pub fn translate(key: &str, args: &[(&str, String)]) -> String {
+    let catalogs = catalogs();
+    let template = catalogs
+        .translated
+        .iter()
+        .chain([&catalogs.english])
+        .find_map(|c| c.get(key))
+        .map(String::as_str)
+        .unwrap_or(key);
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        let Some(close) = rest[open..].find('}') else {
+            break;
+        };
+        out.push_str(&rest[..open]);
+        let placeholder = rest[open + 1..open + close].trim();
+        match placeholder
+            .strip_prefix('$')
+            .and_then(|name| args.iter().find(|(n, _)| *n == name))
+        {
+            Some((_, value)) => out.push_str(value),
+            None => out.push_str(&rest[open..open + close + 1]),
+        }
+        rest = &rest[open + close + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+// tr!("not-found", command = name) looks up "not-found" with $command set to name
+macro_rules! tr {
+    ($key:expr $(, $name:ident = $value:expr)* $(,)?) => {
+        $crate::i18n::translate($key, &[$((stringify!($name), $value.to_string())),*])
+    };
+}
+pub(crate) use tr;
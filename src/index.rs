@@ -0,0 +1,127 @@
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::PathBuf;
+
+use crate::cache::now;
+use crate::config;
+use crate::distrobox;
+use crate::paths;
+
+const INDEX_DIR: &str = "index";
+const INDEX_TTL_SECS: u64 = 24 * 60 * 60;
+// every file on the box's PATH; not everything listed is executable, which is fine for
+// suggestions. Then the alternatives groups of Debian and Fedora boxes whose generic name
+// has no link on PATH, as "name<TAB>program"
+const LIST_SCRIPT: &str = r#"IFS=:; for d in $PATH; do [ -d "$d" ] && ls -1 "$d"; done 2>/dev/null
+unset IFS
+{ update-alternatives --get-selections || alternatives --list; } 2>/dev/null |
+while read -r n _ p; do
+    command -v "$n" >/dev/null 2>&1 || { [ -x "$p" ] && printf '%s\t%s\n' "$n" "$p"; }
+done"#;
+
+// the commands a box provides, as of `timestamp`
+pub struct BoxIndex {
+    pub commands: Vec<String>,
+    pub timestamp: u64,
+}
+
+impl BoxIndex {
+    pub fn is_fresh(&self) -> bool {
+        now().saturating_sub(self.timestamp) < INDEX_TTL_SECS
+    }
+}
+
+fn index_path(name: &str) -> io::Result<PathBuf> {
+    Ok(paths::cache_dir()?.join(INDEX_DIR).join(name))
+}
+
+pub fn load(name: &str) -> Option<BoxIndex> {
+    let contents = fs::read_to_string(index_path(name).ok()?).ok()?;
+    let mut lines = contents.lines();
+    let timestamp = lines.next()?.parse().ok()?;
+    Some(BoxIndex {
+        commands: lines
+            .map(|l| l.split_once('\t').map_or(l, |(name, _)| name).to_string())
+            .collect(),
+        timestamp,
+    })
+}
+
+pub fn refresh(name: &str) -> io::Result<BoxIndex> {
+    let listing = distrobox::capture(name, LIST_SCRIPT)?;
+    let mut lines: Vec<&str> = listing
+        .lines()
+        .map(|l| l.trim())
+        .filter(|l| !l.is_empty())
+        .collect();
+    lines.sort();
+    lines.dedup();
+    let index = BoxIndex {
+        commands: lines
+            .iter()
+            .map(|l| l.split_once('\t').map_or(*l, |(name, _)| name).to_string())
+            .collect(),
+        timestamp: now(),
+    };
+    config::writable()?;
+    let path = index_path(name)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    // alternatives keep their program, for `alternative`
+    let mut out = format!("{}\n", index.timestamp);
+    for line in &lines {
+        out.push_str(line);
+        out.push('\n');
+    }
+    fs::write(path, out)?;
+    Ok(index)
+}
+
+// the program box `name`'s alternatives group `command` points to, when the group has no
+// link on PATH and the box was indexed
+pub fn alternative(name: &str, command: &str) -> Option<String> {
+    let contents = fs::read_to_string(index_path(name).ok()?).ok()?;
+    contents.lines().skip(1).find_map(|l| {
+        let (group, program) = l.split_once('\t')?;
+        (group == command).then(|| program.to_string())
+    })
+}
+
+pub fn remove(name: &str) -> io::Result<()> {
+    match fs::remove_file(index_path(name)?) {
+        Err(e) if e.kind() != ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+pub fn clear() -> io::Result<()> {
+    match fs::remove_dir_all(paths::cache_dir()?.join(INDEX_DIR)) {
+        Err(e) if e.kind() != ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+// boxes that have an index on disk, whether or not they still exist
+pub fn indexed_boxes() -> Vec<String> {
+    let Ok(dir) = paths::cache_dir().map(|d| d.join(INDEX_DIR)) else {
+        return vec![];
+    };
+    let Ok(entries) = fs::read_dir(dir) else {
+        return vec![];
+    };
+    entries
+        .flatten()
+        .map(|e| e.file_name().to_string_lossy().into_owned())
+        .collect()
+}
+
+// indexed boxes listing `command`, by name; stale indexes count too, this is a best guess
+pub fn boxes_providing(command: &str) -> Vec<String> {
+    let mut names: Vec<String> = indexed_boxes()
+        .into_iter()
+        .filter(|name| load(name).is_some_and(|idx| idx.commands.iter().any(|c| c == command)))
+        .collect();
+    names.sort();
+    names
+}
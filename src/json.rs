@@ -0,0 +1,341 @@
+use std::fmt::{Display, Formatter};
+use std::iter::Peekable;
+use std::str::Chars;
+
+// just enough JSON to emit machine-readable output and read the container manager's,
+// without pulling in serde
+pub enum Json {
+    Null,
+    Bool(bool),
+    Num(f64),
+    Str(String),
+    Arr(Vec<Json>),
+    Obj(Vec<(String, Json)>),
+}
+
+impl Json {
+    pub fn str(s: impl Into<String>) -> Json {
+        Json::Str(s.into())
+    }
+
+    pub fn obj(fields: Vec<(&str, Json)>) -> Json {
+        Json::Obj(
+            fields
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v))
+                .collect(),
+        )
+    }
+}
+
+impl Json {
+    pub fn parse(text: &str) -> Option<Json> {
+        let mut parser = Parser {
+            chars: text.chars().peekable(),
+            depth: 0,
+        };
+        let value = parser.value()?;
+        parser.skip_ws();
+        parser.chars.peek().is_none().then_some(value)
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Obj(fields) => fields.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::Str(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Json::Num(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Json]> {
+        match self {
+            Json::Arr(items) => Some(items),
+            _ => None,
+        }
+    }
+}
+
+// nothing the handler reads nests anywhere near this; deeper input is rejected rather
+// than allowed to exhaust the stack
+const MAX_DEPTH: usize = 64;
+
+struct Parser<'a> {
+    chars: Peekable<Chars<'a>>,
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn skip_ws(&mut self) {
+        while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+    }
+
+    fn eat(&mut self, expected: char) -> Option<()> {
+        self.skip_ws();
+        self.chars.next_if_eq(&expected).map(|_| ())
+    }
+
+    fn value(&mut self) -> Option<Json> {
+        self.skip_ws();
+        match *self.chars.peek()? {
+            '{' | '[' if self.depth >= MAX_DEPTH => None,
+            '{' => self.nested(Parser::object),
+            '[' => self.nested(Parser::array),
+            '"' => self.string().map(Json::Str),
+            't' => self.word("true", Json::Bool(true)),
+            'f' => self.word("false", Json::Bool(false)),
+            'n' => self.word("null", Json::Null),
+            _ => self.number(),
+        }
+    }
+
+    fn nested(&mut self, parse: fn(&mut Self) -> Option<Json>) -> Option<Json> {
+        self.depth += 1;
+        let value = parse(self);
+        self.depth -= 1;
+        value
+    }
+
+    fn word(&mut self, word: &str, value: Json) -> Option<Json> {
+        for expected in word.chars() {
+            self.chars.next_if_eq(&expected)?;
+        }
+        Some(value)
+    }
+
+    fn number(&mut self) -> Option<Json> {
+        let mut text = String::new();
+        while let Some(c) = self
+            .chars
+            .next_if(|c| c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E'))
+        {
+            text.push(c);
+        }
+        text.parse().ok().map(Json::Num)
+    }
+
+    fn string(&mut self) -> Option<String> {
+        self.eat('"')?;
+        let mut s = String::new();
+        loop {
+            match self.chars.next()? {
+                '"' => return Some(s),
+                '\\' => match self.chars.next()? {
+                    'n' => s.push('\n'),
+                    'r' => s.push('\r'),
+                    't' => s.push('\t'),
+                    'b' => s.push('\u{8}'),
+                    'f' => s.push('\u{c}'),
+                    'u' => {
+                        let hex: String = (0..4).filter_map(|_| self.chars.next()).collect();
+                        // surrogate pairs are rare in container metadata, they become U+FFFD
+                        let c = u32::from_str_radix(&hex, 16).ok()?;
+                        s.push(char::from_u32(c).unwrap_or('\u{fffd}'));
+                    }
+                    c => s.push(c),
+                },
+                c => s.push(c),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Option<Json> {
+        self.eat('[')?;
+        let mut items = vec![];
+        if self.eat(']').is_some() {
+            return Some(Json::Arr(items));
+        }
+        loop {
+            items.push(self.value()?);
+            if self.eat(']').is_some() {
+                return Some(Json::Arr(items));
+            }
+            self.eat(',')?;
+        }
+    }
+
+    fn object(&mut self) -> Option<Json> {
+        self.eat('{')?;
+        let mut fields = vec![];
+        if self.eat('}').is_some() {
+            return Some(Json::Obj(fields));
+        }
+        loop {
+            self.skip_ws();
+            let key = self.string()?;
+            self.eat(':')?;
+            fields.push((key, self.value()?));
+            if self.eat('}').is_some() {
+                return Some(Json::Obj(fields));
+            }
+            self.eat(',')?;
+        }
+    }
+}
+
+impl From<u64> for Json {
+    fn from(n: u64) -> Json {
+        Json::Num(n as f64)
+    }
+}
+
+impl From<usize> for Json {
+    fn from(n: usize) -> Json {
+        Json::Num(n as f64)
+    }
+}
+
+impl From<bool> for Json {
+    fn from(b: bool) -> Json {
+        Json::Bool(b)
+    }
+}
+
+impl<T: Into<Json>> From<Option<T>> for Json {
+    fn from(v: Option<T>) -> Json {
+        v.map(Into::into).unwrap_or(Json::Null)
+    }
+}
+
+fn write_str(f: &mut Formatter<'_>, s: &str) -> std::fmt::Result {
+    f.write_str("\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    f.write_str("\"")
+}
+
+impl Display for Json {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Json::Null => f.write_str("null"),
+            Json::Bool(b) => write!(f, "{}", b),
+            Json::Num(n) if n.is_finite() => write!(f, "{}", n),
+            Json::Num(_) => f.write_str("null"),
+            Json::Str(s) => write_str(f, s),
+            Json::Arr(items) => {
+                f.write_str("[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(",")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                f.write_str("]")
+            }
+            Json::Obj(fields) => {
+                f.write_str("{")?;
+                for (i, (key, value)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(",")?;
+                    }
+                    write_str(f, key)?;
+                    write!(f, ":{}", value)?;
+                }
+                f.write_str("}")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testgen::{Gen, CASES};
+
+    const SPECIAL: &str = "{}[]\":,\\/-+.eEtrufalsnu0123456789 ";
+
+    fn value(gen: &mut Gen, depth: usize) -> Json {
+        match gen.below(if depth == 0 { 4 } else { 6 }) {
+            0 => Json::Null,
+            1 => Json::Bool(gen.chance(50)),
+            2 => match gen.chance(50) {
+                true => Json::Num(gen.next() as i64 as f64),
+                // any bit pattern but NaN and the infinities, which are written as null
+                false => match f64::from_bits(gen.next()) {
+                    n if n.is_finite() => Json::Num(n),
+                    _ => Json::Null,
+                },
+            },
+            3 => Json::Str(gen.text(SPECIAL, 10)),
+            4 => Json::Arr((0..gen.below(4)).map(|_| value(gen, depth - 1)).collect()),
+            _ => Json::Obj(
+                (0..gen.below(4))
+                    .map(|_| (gen.text(SPECIAL, 6), value(gen, depth - 1)))
+                    .collect(),
+            ),
+        }
+    }
+
+    #[test]
+    fn written_json_reads_back() {
+        for seed in 0..CASES {
+            let mut gen = Gen::new(seed);
+            let text = value(&mut gen, 4).to_string();
+            let parsed = Json::parse(&text).unwrap_or_else(|| panic!("seed {}: {}", seed, text));
+            assert_eq!(parsed.to_string(), text, "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn arbitrary_text_parses_or_is_rejected() {
+        for seed in 0..CASES * 5 {
+            let mut gen = Gen::new(seed);
+            let text = match gen.chance(50) {
+                true => gen.text(SPECIAL, 40),
+                // JSON with a piece of it cut out or repeated
+                false => {
+                    let text: Vec<char> = value(&mut gen, 3).to_string().chars().collect();
+                    let (a, b) = (gen.below(text.len()), gen.below(text.len()));
+                    let (a, b) = (a.min(b), a.max(b));
+                    let mut mangled: String = text[..a].iter().collect();
+                    if gen.chance(50) {
+                        mangled.extend(&text[a..b]);
+                        mangled.extend(&text[a..b]);
+                    }
+                    mangled.extend(&text[b..]);
+                    mangled
+                }
+            };
+            // whatever is accepted is something it can write and read again the same
+            if let Some(parsed) = Json::parse(&text) {
+                let written = parsed.to_string();
+                let again = Json::parse(&written)
+                    .unwrap_or_else(|| panic!("seed {}: {:?} from {:?}", seed, written, text));
+                assert_eq!(again.to_string(), written, "seed {}: {:?}", seed, text);
+            }
+        }
+    }
+
+    #[test]
+    fn deep_nesting_is_rejected() {
+        for depth in [MAX_DEPTH + 1, 10_000, 1_000_000] {
+            for (open, close) in [("[", "]"), ("{\"a\":", "}")] {
+                let text = open.repeat(depth) + &close.repeat(depth);
+                assert!(Json::parse(&text).is_none(), "depth {}", depth);
+            }
+        }
+        let text = "[".repeat(MAX_DEPTH) + &"]".repeat(MAX_DEPTH);
+        assert!(Json::parse(&text).is_some());
+    }
+}
@@ -0,0 +1,302 @@
+// the boxes as distrobox-list shows them, and the parser for its plain table; std only,
+// so the fuzz targets build it on its own
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter};
+use std::io::{self, Error, ErrorKind};
+
+#[derive(Clone)]
+pub struct DistroboxInstance {
+    // unique among the listed boxes; a name taken twice across managers gets "@manager"
+    pub name: String,
+    // the container ID, empty when unknown; boxes are entered by it when known
+    pub id: String,
+    // the container manager, None for the one distrobox is configured with
+    pub manager: Option<String>,
+    // the podman system connection, None for the default one
+    pub connection: Option<String>,
+    pub priority: usize,
+    pub running: bool,
+    // the image reference it was created from, empty when unknown
+    pub image: String,
+}
+impl TryFrom<(usize, &String)> for DistroboxInstance {
+    type Error = Error;
+
+    fn try_from(value: (usize, &String)) -> Result<DistroboxInstance, Error> {
+        let mut split_stat = value.1.split("|");
+        let dbx = DistroboxInstance {
+            id: split_stat
+                .next()
+                .ok_or_else(|| Error::new(ErrorKind::NotFound, "ID was not found"))?
+                .trim()
+                .to_string(),
+            manager: None,
+            connection: None,
+            name: split_stat
+                .next()
+                .ok_or_else(|| Error::new(ErrorKind::NotFound, "Name was not found"))?
+                .trim()
+                .to_string(),
+            priority: value.0,
+            running: split_stat
+                .next()
+                .ok_or_else(|| Error::new(ErrorKind::NotFound, "State was not found"))?
+                .contains("Up"),
+            image: split_stat.next().unwrap_or_default().trim().to_string(),
+        };
+        if dbx.name.is_empty() {
+            return Err(Error::new(ErrorKind::InvalidData, "Name is empty"));
+        }
+        Ok(dbx)
+    }
+}
+
+impl Display for DistroboxInstance {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Box {} [{}]", self.name, self.priority)
+    }
+}
+impl Eq for DistroboxInstance {}
+impl PartialEq<Self> for DistroboxInstance {
+    fn eq(&self, other: &Self) -> bool {
+        self.priority.eq(&other.priority)
+            && self.name.eq(&other.name)
+            && self.running.eq(&other.running)
+    }
+}
+impl PartialOrd<Self> for DistroboxInstance {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for DistroboxInstance {
+    fn cmp(&self, other: &Self) -> Ordering {
+        if self.running == other.running {
+            self.priority.cmp(&other.priority)
+        } else if self.running {
+            Ordering::Less
+        } else {
+            Ordering::Greater
+        }
+    }
+
+    fn max(self, other: Self) -> Self
+    where
+        Self: Sized,
+    {
+        if self.priority > other.priority {
+            self
+        } else {
+            other
+        }
+    }
+
+    fn min(self, other: Self) -> Self
+    where
+        Self: Sized,
+    {
+        if self.priority < other.priority {
+            self
+        } else {
+            other
+        }
+    }
+
+    fn clamp(self, min: Self, max: Self) -> Self
+    where
+        Self: Sized,
+    {
+        if self.priority < min.priority {
+            min
+        } else if self.priority > max.priority {
+            max
+        } else {
+            self
+        }
+    }
+}
+
+// for releases of unknown format: colour codes are stripped and the columns are found by
+// their headers, with the long-standing ID | NAME | STATUS layout as the last resort
+pub fn parse_list_conservative(lines: &[String]) -> io::Result<Vec<DistroboxInstance>> {
+    let lines: Vec<String> = lines.iter().map(|l| strip_ansi(l)).collect();
+    let columns_of =
+        |line: &str| -> Vec<String> { line.split('|').map(|c| c.trim().to_uppercase()).collect() };
+    // anything printed ahead of the table, such as a warning, is not the header
+    let start = lines
+        .iter()
+        .position(|l| columns_of(l).iter().any(|c| c == "NAME"))
+        .unwrap_or(0);
+    let Some(header) = lines.get(start) else {
+        return Ok(vec![]);
+    };
+    let columns = columns_of(header);
+    let name_col = columns.iter().position(|c| c == "NAME").unwrap_or(1);
+    let status_col = columns.iter().position(|c| c == "STATUS").unwrap_or(2);
+    let id_col = columns.iter().position(|c| c == "ID").unwrap_or(0);
+    let image_col = columns.iter().position(|c| c == "IMAGE").unwrap_or(3);
+    let mut boxes = vec![];
+    for (priority, line) in lines.iter().enumerate().skip(start + 1) {
+        let fields: Vec<&str> = line.split('|').map(|f| f.trim()).collect();
+        let (Some(name), Some(status)) = (fields.get(name_col), fields.get(status_col)) else {
+            continue;
+        };
+        // blank lines, rules and a repeated header are not boxes
+        if name.is_empty() || name.chars().all(|c| c == '-') || columns_of(line) == columns {
+            continue;
+        }
+        let status = status.to_lowercase();
+        boxes.push(DistroboxInstance {
+            name: name.to_string(),
+            id: fields.get(id_col).unwrap_or(&"").to_string(),
+            manager: None,
+            connection: None,
+            priority,
+            running: status.starts_with("up") || status.starts_with("running"),
+            image: fields.get(image_col).unwrap_or(&"").to_string(),
+        });
+    }
+    Ok(boxes)
+}
+
+fn strip_ansi(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // CSI sequences end with a letter
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testgen::{Gen, CASES};
+
+    const SPECIAL: &str = "|-  NAMESTATUSIDIMAGEUpup";
+    const NAME_CHARS: &str = "abcxyz019-_.@";
+    const STATUSES: [(&str, bool); 5] = [
+        ("Up 2 hours", true),
+        ("running", true),
+        ("Exited (0) 3 days ago", false),
+        ("Created", false),
+        ("exited", false),
+    ];
+    // what distrobox and the manager may print ahead of the table
+    const NOISE: [&str; 4] = [
+        "WARNING: the container engine is podman 3",
+        "",
+        "Error: cannot connect to the docker daemon, is it running?",
+        "------------------------------",
+    ];
+
+    fn colored(gen: &mut Gen, text: &str) -> String {
+        match gen.chance(30) {
+            true => format!("\x1b[1;{}m{}\x1b[0m", 30 + gen.below(8), text),
+            false => text.to_string(),
+        }
+    }
+
+    #[test]
+    fn arbitrary_lists_give_only_named_boxes() {
+        for seed in 0..CASES {
+            let mut gen = Gen::new(seed);
+            let lines: Vec<String> = (0..gen.below(8)).map(|_| gen.text(SPECIAL, 30)).collect();
+            let boxes = parse_list_conservative(&lines).expect("the parser never fails");
+            for dbx in boxes {
+                assert!(
+                    !dbx.name.is_empty()
+                        && !dbx.name.contains('|')
+                        && !dbx.name.chars().all(|c| c == '-'),
+                    "seed {}: {:?} from {:?}",
+                    seed,
+                    dbx.name,
+                    lines
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn tables_read_back_in_any_column_order() {
+        for seed in 0..CASES {
+            let mut gen = Gen::new(seed);
+            let boxes: Vec<(String, String, (&str, bool), String)> = (0..gen.below(5))
+                .map(|_| {
+                    (
+                        gen.word("0123456789abcdef", 11),
+                        gen.word(NAME_CHARS, 12),
+                        *gen.pick(&STATUSES),
+                        format!("registry.example/{}:latest", gen.word(NAME_CHARS, 6)),
+                    )
+                })
+                .collect();
+            let mut columns = [0, 1, 2, 3];
+            for i in (1..columns.len()).rev() {
+                columns.swap(i, gen.below(i + 1));
+            }
+            let headers = ["ID", "NAME", "STATUS", "IMAGE"];
+            let row = |fields: [&str; 4]| {
+                columns
+                    .iter()
+                    .map(|c| fields[*c])
+                    .collect::<Vec<_>>()
+                    .join(" | ")
+            };
+            let mut lines: Vec<String> = (0..gen.below(3))
+                .map(|_| gen.pick(&NOISE).to_string())
+                .collect();
+            lines.push(colored(&mut gen, &row(headers)));
+            for (id, name, (status, _), image) in &boxes {
+                lines.push(colored(&mut gen, &row([id, name, status, image])));
+            }
+            let parsed = parse_list_conservative(&lines).expect("the parser never fails");
+            let got: Vec<_> = parsed
+                .iter()
+                .map(|b| (b.id.clone(), b.name.clone(), b.running, b.image.clone()))
+                .collect();
+            let want: Vec<_> = boxes
+                .iter()
+                .map(|(id, name, (_, running), image)| {
+                    (id.clone(), name.clone(), *running, image.clone())
+                })
+                .collect();
+            assert_eq!(got, want, "seed {}: {:?}", seed, lines);
+        }
+    }
+
+    #[test]
+    fn rows_convert_or_are_rejected() {
+        for seed in 0..CASES {
+            let mut gen = Gen::new(seed);
+            let line = gen.text(SPECIAL, 30);
+            if let Ok(dbx) = DistroboxInstance::try_from((0, &line)) {
+                assert!(!dbx.name.is_empty(), "seed {}: {:?}", seed, line);
+                assert!(!dbx.name.contains('|'), "seed {}: {:?}", seed, line);
+            }
+            let (status, _) = *gen.pick(&STATUSES);
+            let (id, name) = (gen.word("0123456789abcdef", 11), gen.word(NAME_CHARS, 12));
+            let line = format!("{} | {} | {} | img:1", id, name, status);
+            let dbx = DistroboxInstance::try_from((3, &line))
+                .unwrap_or_else(|e| panic!("seed {}: {:?}: {}", seed, line, e));
+            // the plain table parser goes by "Up" alone
+            let up = status.contains("Up");
+            assert_eq!(
+                (dbx.id, dbx.name, dbx.running, dbx.image, dbx.priority),
+                (id, name, up, "img:1".to_string(), 3),
+                "seed {}: {:?}",
+                seed,
+                line
+            );
+        }
+    }
+}
@@ -0,0 +1,52 @@
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::PathBuf;
+
+use crate::config;
+use crate::distrobox;
+use crate::paths;
+
+const LOGIN_PATH_DIR: &str = "login-path";
+// profiles may print things, so the PATH comes after a marker of its own
+const MARKER: &str = "@@distrobox-cnf-path@@";
+const LOGIN_PATH_SCRIPT: &str = r#"case "${SHELL##*/}" in
+fish) "$SHELL" -l -c 'printf "\n%s%s\n" $argv[1] (string join : $PATH)' "$1" ;;
+*) "${SHELL:-/bin/sh}" -l -c 'printf "\n%s%s\n" "$0" "$PATH"' "$1" ;;
+esac"#;
+
+fn cache_path(name: &str) -> io::Result<PathBuf> {
+    Ok(paths::cache_dir()?.join(LOGIN_PATH_DIR).join(name))
+}
+
+// the PATH a login shell of the box user sees, including ~/.local/bin and the like;
+// worked out once per box and kept until the cache is cleared
+pub fn login_path(name: &str) -> Option<String> {
+    let path = cache_path(name).ok()?;
+    if let Ok(cached) = fs::read_to_string(&path) {
+        return Some(cached.trim_end().to_string()).filter(|p| !p.is_empty());
+    }
+    let script = format!("set -- {}; {}", MARKER, LOGIN_PATH_SCRIPT);
+    let output = distrobox::capture(name, &script).ok()?;
+    let login = output.lines().find_map(|l| l.strip_prefix(MARKER))?.trim();
+    if config::writable().is_ok() {
+        if let Some(dir) = path.parent() {
+            let _ = fs::create_dir_all(dir);
+        }
+        let _ = fs::write(&path, login);
+    }
+    Some(login.to_string()).filter(|p| !p.is_empty())
+}
+
+pub fn remove(name: &str) -> io::Result<()> {
+    match fs::remove_file(cache_path(name)?) {
+        Err(e) if e.kind() != ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+pub fn clear() -> io::Result<()> {
+    match fs::remove_dir_all(paths::cache_dir()?.join(LOGIN_PATH_DIR)) {
+        Err(e) if e.kind() != ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
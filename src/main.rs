@@ -1,171 +1,248 @@
-use std::cmp::Ordering;
+mod affinity;
+mod arch;
+mod builtins;
+mod cache;
+mod calibrate;
+mod caller;
+mod cli;
+mod coldstart;
+mod color;
+mod config;
+mod cooldown;
+mod crash;
+#[cfg(feature = "daemon")]
+mod daemon;
+mod dbxconf;
+mod display;
+mod distrobox;
+mod doctor;
+mod envfilter;
+mod events;
+mod flatpak;
+mod forget;
+mod fullscreen;
+mod gc;
+mod generate;
+mod gpu;
+mod groups;
+mod handler;
+mod host;
+mod i18n;
+mod index;
+mod json;
+mod listing;
+mod loginpath;
+#[cfg(feature = "daemon")]
+mod metrics;
+mod msg;
+mod osrelease;
+mod pathmap;
+mod paths;
+#[cfg(feature = "tui")]
+mod pick;
+mod pins;
+mod pipeline;
+mod pkgmgr;
+mod prewarm;
+mod progress;
+mod protocol;
+mod quadlet;
+mod relay;
+mod report;
+mod resolve;
+mod setup;
+mod shebang;
+mod shell;
+mod shim;
+mod stats;
+mod status;
+mod storm;
+mod strategy;
+mod sudo;
+mod suggest;
+mod tasks;
+#[cfg(test)]
+mod testgen;
+mod title;
+#[cfg(feature = "daemon")]
+mod top;
+mod trace;
+mod transcript;
+mod upgrade;
+mod version;
+mod why;
+mod workdir;
+mod wsl;
+
 use std::env;
-use std::fmt::{Display, Formatter};
-use std::io::{self, Error, ErrorKind};
-use std::process::{exit, Command, Stdio};
+use std::panic;
+use std::process::exit;
+
+use cache::{Cache, Resolution};
+use color::Style;
+use distrobox::get_boxes;
 
 fn main() {
+    crash::install_hook();
+    // whatever went wrong, the shell sees a command that was not found
+    if panic::catch_unwind(run).is_err() {
+        exit(crash::CRASH_EXIT);
+    }
+}
+
+// a first word naming a subcommand is that subcommand, so a command called gc or status
+// has to come after `--` to be looked up; the shell hooks always pass it so
+fn run() {
     let args: Vec<String> = env::args().skip(1).collect();
-    // sanity check; make sure we are not in a container
-    match env::var("CONTAINER_ID") {
-        Ok(id) => {
-            if !id.trim().is_empty() {
-                eprintln!("Cannot run inside a container! {}", id);
-                exit(1);
-            }
+    match args.first().map(|s| s.as_str()) {
+        Some("cache") => cache_command(&args[1..]),
+        Some("resolve") => resolve::resolve_command(&args[1..]),
+        #[cfg(feature = "daemon")]
+        Some("daemon") => daemon::daemon_command(&args[1..]),
+        #[cfg(not(feature = "daemon"))]
+        Some("daemon") => {
+            eprintln!("This build has no daemon, it needs the \"daemon\" feature");
+            exit(1);
         }
-        Err(_) => {}
-    }
-    let mut boxes: Vec<DistroboxInstance> = match get_boxes() {
-        Ok(box_list) => box_list,
-        Err(e) => {
-            eprintln!("Cannot get boxes: {:?}", e);
-            exit(2);
+        Some("prewarm") => prewarm::prewarm_command(&args[1..]),
+        Some("setup") => setup::setup_command(&args[1..]),
+        Some("uninstall") => setup::uninstall_command(&args[1..]),
+        Some("upgrade-all") => upgrade::upgrade_all_command(&args[1..]),
+        Some("forget") => forget::forget_command(&args[1..]),
+        Some("doctor") => doctor::doctor_command(&args[1..]),
+        Some("why") => why::why_command(&args[1..]),
+        Some("pipeline") => pipeline::pipeline_command(&args[1..]),
+        Some("calibrate") => calibrate::calibrate_command(&args[1..]),
+        Some("config") => config::config_command(&args[1..]),
+        Some("gc") => gc::gc_command(&args[1..]),
+        Some("env") => shim::env_command(&args[1..]),
+        Some("shell") => shell::shell_command(&args[1..]),
+        Some("stats") => stats::stats_command(&args[1..]),
+        Some("events") => events::events_command(&args[1..]),
+        Some("status") => status::status_command(&args[1..]),
+        #[cfg(feature = "daemon")]
+        Some("top") => top::top_command(&args[1..]),
+        #[cfg(not(feature = "daemon"))]
+        Some("top") => {
+            eprintln!("This build has no daemon to watch, it needs the \"daemon\" feature");
+            exit(1);
         }
-    };
-    boxes.sort();
-    for box_inst in boxes {
-        match Command::new("distrobox-enter")
-            .arg(&box_inst.name)
-            .arg("--")
-            .args(args.clone())
-            .stderr(Stdio::null()) // disable error output
-            .spawn()
-        {
-            Ok(mut child) => {
-                match child.wait() {
-                    Ok(status) => {
-                        if status.code() != Some(127) {
-                            exit(0);
-                        }
-                        // else, try next box
-                    }
-                    Err(_) => {
-                        // does not exist in this box, try the next one
-                    }
-                }
+        Some("generate") => generate::generate_command(&args[1..]),
+        Some("--version" | "-V") if args.len() == 1 => version::version_command(&[]),
+        Some("version") => version::version_command(&args[1..]),
+        Some("run") => match cli::parse(args[1..].to_vec()) {
+            Ok((mut opts, command)) if !command.is_empty() => {
+                opts.run = true;
+                exit(handler::handle(opts, command))
             }
+            Ok(_) => usage(),
             Err(e) => {
-                eprintln!("Cannot run distrobox-enter: {:?}", e);
-                exit(1);
+                eprintln!("{}", e);
+                usage();
             }
-        }
+        },
+        _ => match cli::parse(args) {
+            Ok((opts, _)) if opts.box_name.is_some() => {
+                eprintln!("--box only works with run");
+                usage();
+            }
+            Ok((opts, command)) if !command.is_empty() => exit(handler::handle(opts, command)),
+            Ok(_) => usage(),
+            Err(e) => {
+                eprintln!("{}", e);
+                usage();
+            }
+        },
     }
-    eprintln!("Cannot find {} in any boxes!", args[0]);
-    exit(3);
-}
-
-struct DistroboxInstance {
-    name: String,
-    priority: usize,
-    running: bool,
 }
-impl TryFrom<(usize, &String)> for DistroboxInstance {
-    type Error = Error;
 
-    fn try_from(value: (usize, &String)) -> Result<DistroboxInstance, Error> {
-        let mut split_stat = value.1.split("|");
-        Ok(DistroboxInstance {
-            name: split_stat
-                .nth(1)
-                .ok_or_else(|| Error::new(ErrorKind::NotFound, "Name was not found"))?
-                .trim()
-                .to_string(),
-            priority: value.0,
-            running: split_stat
-                .next()
-                .ok_or_else(|| Error::new(ErrorKind::NotFound, "State was not found"))?
-                .contains("Up"),
-        })
-    }
+fn usage() -> ! {
+    eprintln!("Usage: distrobox-cnf-handler [--host] [--quiet] [--timings] [--shell-protocol bash|zsh|fish] [--trace json | --trace-file <path>] [--copy-back <glob>] [--output text|json] [--] <command> [args...]");
+    eprintln!("       distrobox-cnf-handler run [--box <name>] [flags...] -- <command> [args...]");
+    eprintln!("       distrobox-cnf-handler cache [show | clear | invalidate <box|command>]");
+    eprintln!("       distrobox-cnf-handler resolve [--batch] [--output text|json] [command...]");
+    eprintln!("       distrobox-cnf-handler daemon [--metrics-port <port>]");
+    eprintln!("       distrobox-cnf-handler prewarm [--count <n>]");
+    eprintln!("       distrobox-cnf-handler setup");
+    eprintln!("       distrobox-cnf-handler uninstall [--keep-config]");
+    eprintln!("       distrobox-cnf-handler upgrade-all");
+    eprintln!("       distrobox-cnf-handler forget <command> | --box <name>");
+    eprintln!("       distrobox-cnf-handler doctor");
+    eprintln!("       distrobox-cnf-handler gc");
+    eprintln!("       distrobox-cnf-handler calibrate [--apply]");
+    eprintln!("       distrobox-cnf-handler config get <key> | set <key> <value>");
+    eprintln!("       distrobox-cnf-handler why <command>");
+    eprintln!("       distrobox-cnf-handler pipeline [--] '<command> | <command> ...'");
+    eprintln!("       distrobox-cnf-handler env --box <name> --commands <command,...>");
+    eprintln!("       distrobox-cnf-handler shell [box | command]");
+    eprintln!("       distrobox-cnf-handler stats [--prometheus]");
+    eprintln!("       distrobox-cnf-handler top");
+    eprintln!("       distrobox-cnf-handler events");
+    eprintln!("       distrobox-cnf-handler status [--format text|waybar|polybar] [--follow]");
+    eprintln!("       distrobox-cnf-handler version [--json]");
+    eprintln!("       distrobox-cnf-handler generate man | bash | zsh | fish");
+    eprintln!("A command named like one of these or like a flag, e.g. gc or -i, goes after --");
+    exit(1);
 }
 
-impl Display for DistroboxInstance {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Box {} [{}]", self.name, self.priority)
-    }
-}
-impl Eq for DistroboxInstance {}
-impl PartialEq<Self> for DistroboxInstance {
-    fn eq(&self, other: &Self) -> bool {
-        self.priority.eq(&other.priority)
-            && self.name.eq(&other.name)
-            && self.running.eq(&other.running)
-    }
-}
-impl PartialOrd<Self> for DistroboxInstance {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
-    }
-}
-impl Ord for DistroboxInstance {
-    fn cmp(&self, other: &Self) -> Ordering {
-        if self.running == other.running {
-            self.priority.cmp(&other.priority)
-        } else {
-            if self.running {
-                Ordering::Less
-            } else {
-                Ordering::Greater
+fn cache_command(args: &[String]) {
+    let mut cache = match Cache::load() {
+        Ok(cache) => cache,
+        Err(e) => {
+            eprintln!("Cannot load cache: {:?}", e);
+            exit(2);
+        }
+    };
+    match (args.first().map(|s| s.as_str()), args.get(1)) {
+        (Some("show") | None, _) => {
+            if cache.entries.is_empty() {
+                println!("Cache is empty");
+            }
+            let now = cache::now();
+            for entry in &cache.entries {
+                let age = now.saturating_sub(entry.timestamp);
+                let target = match &entry.resolution {
+                    Resolution::Found(name) => color::out(name, Style::BoxName),
+                    Resolution::NotFound => color::out("not found", Style::Bad),
+                };
+                let note = if entry.is_fresh() {
+                    format!("({}s ago)", age)
+                } else {
+                    color::out(&format!("({}s ago, stale)", age), Style::Dim)
+                };
+                println!("{} -> {} {}", entry.command, target, note);
             }
+            return;
         }
-    }
-
-    fn max(self, other: Self) -> Self
-    where
-        Self: Sized,
-    {
-        if self.priority > other.priority {
-            self
-        } else {
-            other
+        (Some("clear"), _) => {
+            cache.clear();
+            let _ = index::clear();
+            let _ = loginpath::clear();
+            let _ = pathmap::clear_homes();
+            let _ = arch::clear();
+            println!("{}", color::out("Cache cleared", Style::Good));
         }
-    }
-
-    fn min(self, other: Self) -> Self
-    where
-        Self: Sized,
-    {
-        if self.priority < other.priority {
-            self
-        } else {
-            other
-        }
-    }
-
-    fn clamp(self, min: Self, max: Self) -> Self
-    where
-        Self: Sized,
-    {
-        if self.priority < min.priority {
-            min
-        } else if self.priority > max.priority {
-            max
-        } else {
-            self
+        (Some("invalidate"), Some(target)) => {
+            // a box name wins over a command of the same name, since it is the broader fix
+            let is_box = cache.knows_box(target)
+                || get_boxes()
+                    .map(|boxes| boxes.iter().any(|b| &b.name == target))
+                    .unwrap_or(false);
+            let removed = if is_box {
+                let _ = index::remove(target);
+                let _ = loginpath::remove(target);
+                let _ = pathmap::forget_home(target);
+                let _ = arch::remove(target);
+                cache.invalidate_box(target)
+            } else {
+                cache.invalidate_command(target)
+            };
+            println!("Removed {} entries for {}", removed, target);
         }
+        _ => usage(),
     }
-}
-
-fn get_boxes() -> io::Result<Vec<DistroboxInstance>> {
-    let out = Command::new("/usr/bin/distrobox-list")
-        .arg("--no-color")
-        .output()?;
-    if !out.status.success() {
-        return Err(Error::new(ErrorKind::Other, format!("{:?}", out.status)));
-    }
-    let result: String = match String::from_utf8(out.stdout) {
-        Ok(s) => s,
-        Err(_) => {
-            return Err(Error::new(ErrorKind::InvalidData, "Bad UTF-8"));
-        }
-    };
-    // parse command output
-    let lines: Vec<String> = result.lines().map(|x| x.to_string()).collect();
-    let mut boxes: Vec<DistroboxInstance> = vec![];
-    for line in lines.iter().enumerate().skip(1) {
-        let dbx: DistroboxInstance = DistroboxInstance::try_from(line)?;
-        boxes.push(dbx);
+    if let Err(e) = cache.save() {
+        eprintln!("Cannot write cache: {:?}", e);
+        exit(2);
     }
-    Ok(boxes)
 }
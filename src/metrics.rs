@@ -0,0 +1,112 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::time::Duration;
+
+// counters the daemon keeps about its own work, rendered in the Prometheus text format
+#[derive(Default)]
+pub struct Metrics {
+    resolutions: BTreeMap<&'static str, u64>,
+    per_box: BTreeMap<String, u64>,
+    cache_hits: u64,
+    cache_misses: u64,
+    coalesced: u64,
+    latency_sum: Duration,
+    latency_count: u64,
+}
+
+impl Metrics {
+    pub fn record(&mut self, result: &'static str, found_in: Option<&str>, latency: Duration) {
+        *self.resolutions.entry(result).or_default() += 1;
+        if let Some(name) = found_in {
+            *self.per_box.entry(name.to_string()).or_default() += 1;
+        }
+        self.latency_sum += latency;
+        self.latency_count += 1;
+    }
+
+    pub fn cache_lookup(&mut self, hit: bool) {
+        if hit {
+            self.cache_hits += 1;
+        } else {
+            self.cache_misses += 1;
+        }
+    }
+
+    // a request answered by waiting for an identical one already being resolved
+    pub fn coalesced(&mut self) {
+        self.coalesced += 1;
+    }
+
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "# HELP distrobox_cnf_resolutions_total Resolutions served, by result."
+        );
+        let _ = writeln!(out, "# TYPE distrobox_cnf_resolutions_total counter");
+        for (result, count) in &self.resolutions {
+            let _ = writeln!(
+                out,
+                "distrobox_cnf_resolutions_total{{result=\"{}\"}} {}",
+                result, count
+            );
+        }
+        let _ = writeln!(
+            out,
+            "# HELP distrobox_cnf_box_resolutions_total Commands resolved to each box."
+        );
+        let _ = writeln!(out, "# TYPE distrobox_cnf_box_resolutions_total counter");
+        for (name, count) in &self.per_box {
+            let _ = writeln!(
+                out,
+                "distrobox_cnf_box_resolutions_total{{box=\"{}\"}} {}",
+                escape_label(name),
+                count
+            );
+        }
+        let _ = writeln!(
+            out,
+            "# HELP distrobox_cnf_cache_lookups_total Resolution cache lookups."
+        );
+        let _ = writeln!(out, "# TYPE distrobox_cnf_cache_lookups_total counter");
+        let _ = writeln!(
+            out,
+            "distrobox_cnf_cache_lookups_total{{result=\"hit\"}} {}",
+            self.cache_hits
+        );
+        let _ = writeln!(
+            out,
+            "distrobox_cnf_cache_lookups_total{{result=\"miss\"}} {}",
+            self.cache_misses
+        );
+        let _ = writeln!(
+            out,
+            "# HELP distrobox_cnf_coalesced_total Requests that shared an identical request's resolution."
+        );
+        let _ = writeln!(out, "# TYPE distrobox_cnf_coalesced_total counter");
+        let _ = writeln!(out, "distrobox_cnf_coalesced_total {}", self.coalesced);
+        let _ = writeln!(
+            out,
+            "# HELP distrobox_cnf_resolution_seconds Time spent resolving."
+        );
+        let _ = writeln!(out, "# TYPE distrobox_cnf_resolution_seconds summary");
+        let _ = writeln!(
+            out,
+            "distrobox_cnf_resolution_seconds_sum {}",
+            self.latency_sum.as_secs_f64()
+        );
+        let _ = writeln!(
+            out,
+            "distrobox_cnf_resolution_seconds_count {}",
+            self.latency_count
+        );
+        out
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
@@ -0,0 +1,21 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+pub fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+}
+
+pub fn quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+// eprintln! for the handler's own diagnostics; silenced entirely by --quiet
+macro_rules! say {
+    ($($arg:tt)*) => {
+        if !$crate::msg::quiet() {
+            eprintln!($($arg)*);
+        }
+    };
+}
+pub(crate) use say;
@@ -0,0 +1,211 @@
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use crate::config;
+use crate::distrobox;
+use crate::paths;
+
+const OS_RELEASE_DIR: &str = "os-release";
+
+// the identifying bits of an os-release(5) file
+pub struct OsRelease {
+    pub id: String,
+    pub id_like: Vec<String>,
+}
+
+impl OsRelease {
+    pub fn parse(text: &str) -> OsRelease {
+        let mut release = OsRelease {
+            id: String::new(),
+            id_like: vec![],
+        };
+        for line in text.lines() {
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let value = value.trim().trim_matches('"').trim_matches('\'');
+            match key.trim() {
+                "ID" => release.id = value.to_lowercase(),
+                "ID_LIKE" => {
+                    release.id_like = value.split_whitespace().map(|v| v.to_lowercase()).collect()
+                }
+                _ => {}
+            }
+        }
+        release
+    }
+
+    fn family(&self) -> impl Iterator<Item = &String> {
+        std::iter::once(&self.id).chain(self.id_like.iter())
+    }
+
+    // e.g. ubuntu and debian, or centos and fedora, share a family
+    pub fn same_family(&self, other: &OsRelease) -> bool {
+        self.family()
+            .any(|a| !a.is_empty() && other.family().any(|b| a == b))
+    }
+}
+
+pub fn host() -> Option<OsRelease> {
+    fs::read_to_string("/etc/os-release")
+        .or_else(|_| fs::read_to_string("/usr/lib/os-release"))
+        .ok()
+        .map(|text| OsRelease::parse(&text))
+}
+
+fn cache_path(name: &str) -> io::Result<PathBuf> {
+    Ok(paths::cache_dir()?.join(OS_RELEASE_DIR).join(name))
+}
+
+// a box's os-release, cached forever since a container does not change distro; only
+// running boxes are asked, a stopped one stays unknown until it is seen running
+pub fn for_box(name: &str, running: bool) -> Option<OsRelease> {
+    let path = cache_path(name).ok()?;
+    if let Ok(text) = fs::read_to_string(&path) {
+        return Some(OsRelease::parse(&text));
+    }
+    if !running {
+        return None;
+    }
+    let text = distrobox::capture(name, "cat /etc/os-release || cat /usr/lib/os-release").ok()?;
+    if config::writable().is_ok() {
+        if let Some(dir) = path.parent() {
+            let _ = fs::create_dir_all(dir);
+        }
+        let _ = fs::write(&path, &text);
+    }
+    Some(OsRelease::parse(&text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the identifying lines of the images distrobox lists as supported, as they ship them
+    const RELEASES: [(&str, &str); 22] = [
+        (
+            "fedora",
+            "NAME=\"Fedora Linux\"\nID=fedora\nVERSION_ID=40\n",
+        ),
+        ("ubuntu", "NAME=\"Ubuntu\"\nID=ubuntu\nID_LIKE=debian\n"),
+        (
+            "debian",
+            "PRETTY_NAME=\"Debian GNU/Linux 12 (bookworm)\"\nID=debian\n",
+        ),
+        ("arch", "NAME=\"Arch Linux\"\nID=arch\nBUILD_ID=rolling\n"),
+        (
+            "manjaro",
+            "NAME=\"Manjaro Linux\"\nID=manjaro\nID_LIKE=arch\n",
+        ),
+        ("steamos", "NAME=\"SteamOS\"\nID=steamos\nID_LIKE=arch\n"),
+        (
+            "centos",
+            "NAME=\"CentOS Stream\"\nID=\"centos\"\nID_LIKE=\"rhel fedora\"\n",
+        ),
+        (
+            "rocky",
+            "NAME=\"Rocky Linux\"\nID=\"rocky\"\nID_LIKE=\"rhel centos fedora\"\n",
+        ),
+        (
+            "almalinux",
+            "NAME=\"AlmaLinux\"\nID=\"almalinux\"\nID_LIKE=\"rhel centos fedora\"\n",
+        ),
+        (
+            "ubi",
+            "NAME=\"Red Hat Enterprise Linux\"\nID=\"rhel\"\nID_LIKE=\"fedora\"\n",
+        ),
+        (
+            "oracle",
+            "NAME=\"Oracle Linux Server\"\nID=\"ol\"\nID_LIKE=\"fedora\"\n",
+        ),
+        (
+            "amazon",
+            "NAME=\"Amazon Linux\"\nID=\"amzn\"\nID_LIKE=\"fedora\"\n",
+        ),
+        (
+            "tumbleweed",
+            "NAME=\"openSUSE Tumbleweed\"\nID=\"opensuse-tumbleweed\"\nID_LIKE=\"opensuse suse\"\n",
+        ),
+        (
+            "leap",
+            "NAME=\"openSUSE Leap\"\nID=\"opensuse-leap\"\nID_LIKE=\"suse opensuse\"\n",
+        ),
+        ("sles", "NAME=\"SLES\"\nID=\"sles\"\nID_LIKE=\"suse\"\n"),
+        (
+            "alpine",
+            "NAME=\"Alpine Linux\"\nID=alpine\nVERSION_ID=3.20.0\n",
+        ),
+        ("wolfi", "ID=wolfi\nNAME=\"Wolfi\"\n"),
+        ("chimera", "NAME=\"Chimera\"\nID=\"chimera\"\n"),
+        ("void", "NAME=\"Void\"\nID=\"void\"\n"),
+        (
+            "kali",
+            "PRETTY_NAME=\"Kali GNU/Linux Rolling\"\nID=kali\nID_LIKE=debian\n",
+        ),
+        (
+            "mint",
+            "NAME=\"Linux Mint\"\nID=linuxmint\nID_LIKE=\"ubuntu debian\"\n",
+        ),
+        ("gentoo", "NAME=Gentoo\nID=gentoo\n"),
+    ];
+
+    fn release(image: &str) -> OsRelease {
+        let (_, text) = RELEASES
+            .iter()
+            .find(|(i, _)| *i == image)
+            .expect("known image");
+        OsRelease::parse(text)
+    }
+
+    #[test]
+    fn ids_of_the_common_images() {
+        let cases: [(&str, &str, &[&str]); 8] = [
+            ("fedora", "fedora", &[]),
+            ("ubuntu", "ubuntu", &["debian"]),
+            ("rocky", "rocky", &["rhel", "centos", "fedora"]),
+            ("tumbleweed", "opensuse-tumbleweed", &["opensuse", "suse"]),
+            ("alpine", "alpine", &[]),
+            ("wolfi", "wolfi", &[]),
+            ("void", "void", &[]),
+            ("mint", "linuxmint", &["ubuntu", "debian"]),
+        ];
+        for (image, id, id_like) in cases {
+            let release = release(image);
+            assert_eq!(release.id, id, "{}", image);
+            assert_eq!(release.id_like, id_like, "{}", image);
+        }
+    }
+
+    #[test]
+    fn quoting_case_and_stray_lines() {
+        let text =
+            "# a comment\n\nID = 'Fedora'\nID_LIKE=\"RHEL  centos\"\nnonsense\nVARIANT=x=y\n";
+        let release = OsRelease::parse(text);
+        assert_eq!(release.id, "fedora");
+        assert_eq!(release.id_like, ["rhel", "centos"]);
+        let empty = OsRelease::parse("");
+        assert!(empty.id.is_empty() && empty.id_like.is_empty());
+    }
+
+    #[test]
+    fn families() {
+        let cases = [
+            ("ubuntu", "debian", true),
+            ("mint", "kali", true),
+            ("rocky", "fedora", true),
+            ("amazon", "ubi", true),
+            ("manjaro", "steamos", true),
+            ("tumbleweed", "sles", true),
+            ("fedora", "debian", false),
+            ("arch", "alpine", false),
+            ("gentoo", "void", false),
+        ];
+        for (a, b, same) in cases {
+            assert_eq!(release(a).same_family(&release(b)), same, "{} and {}", a, b);
+            assert_eq!(release(b).same_family(&release(a)), same, "{} and {}", b, a);
+        }
+        let unknown = OsRelease::parse("");
+        assert!(!unknown.same_family(&OsRelease::parse("")));
+    }
+}
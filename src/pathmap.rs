@@ -0,0 +1,217 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Error, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::config;
+use crate::distrobox;
+use crate::json::Json;
+use crate::paths;
+use crate::wsl;
+
+const MOUNTS: &str = "{{range .Mounts}}{{.Source}}:{{.Destination}} {{end}}";
+const ENV: &str = "{{json .Config.Env}}";
+const HOME_DIR: &str = "homes";
+// each box has its own copy of these, so a path there means the box's file, not the host's
+const SYSTEM_DIRS: [&str; 11] = [
+    "/usr", "/etc", "/bin", "/sbin", "/lib", "/lib64", "/opt", "/proc", "/sys", "/dev", "/run",
+];
+
+// a box's volume mounts, host path to box path
+#[derive(Clone)]
+pub struct Mounts {
+    mounts: Vec<(PathBuf, PathBuf)>,
+}
+
+// one inspect per box and process; a run asks about the same box several times
+static MOUNTS_SEEN: Mutex<BTreeMap<String, Mounts>> = Mutex::new(BTreeMap::new());
+
+impl Mounts {
+    pub fn of(name: &str) -> io::Result<Mounts> {
+        if let Some(mounts) = MOUNTS_SEEN.lock().ok().and_then(|m| m.get(name).cloned()) {
+            return Ok(mounts);
+        }
+        let found = distrobox::inspect([name], MOUNTS)?;
+        let mounts = Mounts {
+            mounts: found
+                .iter()
+                .flat_map(|(_, line)| line.split_whitespace())
+                .filter_map(|mount| mount.split_once(':'))
+                .map(|(source, dest)| (PathBuf::from(source), PathBuf::from(dest)))
+                .collect(),
+        };
+        if let Ok(mut seen) = MOUNTS_SEEN.lock() {
+            seen.insert(name.to_string(), mounts.clone());
+        }
+        Ok(mounts)
+    }
+
+    // where the box sees the host's `path`, preferring the mount closest to it
+    pub fn translate(&self, path: &Path) -> Option<PathBuf> {
+        self.mounts
+            .iter()
+            .filter(|(source, _)| path.starts_with(source))
+            .max_by_key(|(source, _)| source.components().count())
+            .and_then(|(source, dest)| Some(dest.join(path.strip_prefix(source).ok()?)))
+    }
+}
+
+fn home_cache(name: &str) -> io::Result<PathBuf> {
+    Ok(paths::cache_dir()?.join(HOME_DIR).join(name))
+}
+
+// the box's home when it was made with `distrobox create --home`, None when it shares
+// the host's; such a box does not see the host's home where the host does. Worked out
+// once per box and kept until the cache is cleared, since a box's home never changes
+pub fn custom_home(name: &str) -> Option<PathBuf> {
+    let cache = home_cache(name).ok()?;
+    if let Ok(cached) = fs::read_to_string(&cache) {
+        return Some(PathBuf::from(cached.trim_end())).filter(|h| !h.as_os_str().is_empty());
+    }
+    let found = distrobox::inspect([name], ENV).ok()?;
+    let env = Json::parse(&found.first()?.1)?;
+    let home = env
+        .as_array()?
+        .iter()
+        .filter_map(|var| var.as_str()?.strip_prefix("HOME="))
+        .map(PathBuf::from)
+        .next_back()
+        .filter(|home| paths::home().is_ok_and(|host| &host != home));
+    if let Some(dir) = cache.parent().filter(|_| config::writable().is_ok()) {
+        let _ = fs::create_dir_all(dir);
+        let text = home.as_ref().map(|h| h.to_string_lossy().into_owned());
+        let _ = fs::write(&cache, text.unwrap_or_default());
+    }
+    home
+}
+
+pub fn forget_home(name: &str) -> io::Result<()> {
+    match fs::remove_file(home_cache(name)?) {
+        Err(e) if e.kind() != ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+pub fn clear_homes() -> io::Result<()> {
+    match fs::remove_dir_all(paths::cache_dir()?.join(HOME_DIR)) {
+        Err(e) if e.kind() != ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+// a host file the user most likely means, and which might not be where they think in the
+// box; paths in the host's home only count with `home_shared` false
+fn host_path(arg: &str, home_shared: bool) -> Option<&Path> {
+    let path = Path::new(arg);
+    let home = paths::home().ok().filter(|_| home_shared);
+    (path.is_absolute()
+        && !SYSTEM_DIRS.iter().any(|dir| path.starts_with(dir))
+        && !home.is_some_and(|home| path.starts_with(home))
+        && path.exists())
+    .then_some(path)
+}
+
+fn rewrite(mounts: &Mounts, path: &Path) -> io::Result<String> {
+    let translated = mounts.translate(path).ok_or_else(|| {
+        Error::new(
+            ErrorKind::NotFound,
+            format!("{} is not visible in the box", path.display()),
+        )
+    })?;
+    Ok(translated.to_string_lossy().into_owned())
+}
+
+// `args` with host paths rewritten to where the box sees them, e.g. /mnt/data as
+// /run/host/mnt/data where distrobox mounts the host's root; paths in the home directory
+// only for a box with a home of its own. An error names a path the box cannot see
+pub fn translate_args(name: &str, args: &[String]) -> io::Result<Vec<String>> {
+    // on WSL a pasted Windows path is a host path too, under its drive's mount
+    let windows = |arg: &String| {
+        wsl::unix_path(arg)
+            .filter(|p| wsl::detected() && p.exists())
+            .map_or_else(|| arg.clone(), |p| p.to_string_lossy().into_owned())
+    };
+    let args: Vec<String> = args[..1]
+        .iter()
+        .cloned()
+        .chain(args[1..].iter().map(windows))
+        .collect();
+    let args = &args[..];
+    let wanted = |home_shared: bool| {
+        args.iter().skip(1).any(|arg| {
+            host_path(arg, home_shared).is_some()
+                || arg
+                    .split_once('=')
+                    .is_some_and(|(_, value)| host_path(value, home_shared).is_some())
+        })
+    };
+    // the home only costs an inspect when some argument is a path at all
+    if !wanted(false) {
+        return Ok(args.to_vec());
+    }
+    let home_shared = custom_home(name).is_none();
+    if !wanted(home_shared) {
+        return Ok(args.to_vec());
+    }
+    let mounts = Mounts::of(name)?;
+    let mut translated = vec![args[0].clone()];
+    for arg in &args[1..] {
+        translated.push(match host_path(arg, home_shared) {
+            Some(path) => rewrite(&mounts, path)?,
+            // --output=/mnt/x
+            None => match arg.split_once('=') {
+                Some((flag, value)) if host_path(value, home_shared).is_some() => {
+                    format!("{}={}", flag, rewrite(&mounts, Path::new(value))?)
+                }
+                _ => arg.clone(),
+            },
+        });
+    }
+    Ok(translated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mounts(pairs: &[(&str, &str)]) -> Mounts {
+        Mounts {
+            mounts: pairs
+                .iter()
+                .map(|(s, d)| (PathBuf::from(s), PathBuf::from(d)))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn host_paths_as_the_box_sees_them() {
+        let box_mounts = mounts(&[
+            ("/", "/run/host"),
+            ("/mnt/data", "/data"),
+            ("/home/user", "/home/user"),
+            ("/tmp", "/tmp"),
+        ]);
+        for (path, expected) in [
+            ("/mnt/data/x.csv", Some("/data/x.csv")),
+            ("/mnt/data", Some("/data")),
+            ("/mnt/database/y", Some("/run/host/mnt/database/y")),
+            ("/srv/www/index.html", Some("/run/host/srv/www/index.html")),
+            ("/home/user/notes.txt", Some("/home/user/notes.txt")),
+            ("/tmp/a", Some("/tmp/a")),
+        ] {
+            let got = box_mounts.translate(Path::new(path));
+            assert_eq!(got.as_deref(), expected.map(Path::new), "{}", path);
+        }
+        // without the host's root mounted, only what is mounted is visible
+        let rootless = mounts(&[("/mnt/data", "/data")]);
+        assert_eq!(
+            rootless.translate(Path::new("/mnt/data/x")).as_deref(),
+            Some(Path::new("/data/x"))
+        );
+        assert_eq!(rootless.translate(Path::new("/srv/x")), None);
+        let e = rewrite(&rootless, Path::new("/srv/x")).expect_err("not mounted");
+        assert_eq!(e.kind(), ErrorKind::NotFound);
+        assert!(mounts(&[]).translate(Path::new("/mnt/data")).is_none());
+    }
+}
@@ -0,0 +1,40 @@
+use std::env;
+use std::io::{self, Error, ErrorKind};
+use std::path::PathBuf;
+
+const APP_DIR: &str = "distrobox-cnf";
+
+pub fn home() -> io::Result<PathBuf> {
+    env::var("HOME")
+        .map(PathBuf::from)
+        .map_err(|_| Error::new(ErrorKind::NotFound, "HOME is not set"))
+}
+
+// resolve an XDG base directory, falling back to a path under $HOME
+fn xdg_dir(var: &str, fallback: &str) -> io::Result<PathBuf> {
+    match env::var(var) {
+        Ok(dir) if !dir.trim().is_empty() => Ok(PathBuf::from(dir)),
+        _ => Ok(home()?.join(fallback)),
+    }
+}
+
+// the base directory other programs' configs live in, e.g. fish's
+pub fn config_home() -> io::Result<PathBuf> {
+    xdg_dir("XDG_CONFIG_HOME", ".config")
+}
+
+pub fn cache_dir() -> io::Result<PathBuf> {
+    Ok(xdg_dir("XDG_CACHE_HOME", ".cache")?.join(APP_DIR))
+}
+
+pub fn config_dir() -> io::Result<PathBuf> {
+    Ok(config_home()?.join(APP_DIR))
+}
+
+// sockets and other per-session files; $XDG_RUNTIME_DIR is per-user and cleared on logout
+pub fn runtime_dir() -> io::Result<PathBuf> {
+    match env::var("XDG_RUNTIME_DIR") {
+        Ok(dir) if !dir.trim().is_empty() => Ok(PathBuf::from(dir).join(APP_DIR)),
+        _ => cache_dir(),
+    }
+}
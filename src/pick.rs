@@ -0,0 +1,53 @@
+use std::io::{self, BufRead, IsTerminal, Write};
+
+use crate::color::{self, Style};
+use crate::pins::Scope;
+
+pub fn interactive() -> bool {
+    io::stdin().is_terminal() && io::stderr().is_terminal()
+}
+
+fn ask(prompt: &str) -> io::Result<String> {
+    eprint!("{}", prompt);
+    io::stderr().flush()?;
+    let mut line = String::new();
+    if io::stdin().lock().read_line(&mut line)? == 0 {
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "No answer"));
+    }
+    Ok(line.trim().to_string())
+}
+
+// let the user choose among the boxes providing `command`; Enter takes the first.
+// Returns the box and, if it should be remembered, for how long
+pub fn pick(command: &str, names: &[String]) -> io::Result<(String, Option<Scope>)> {
+    eprintln!(
+        "{} is in several boxes:",
+        color::err(command, Style::Command)
+    );
+    for (n, name) in names.iter().enumerate() {
+        eprintln!("  {}) {}", n + 1, color::err(name, Style::BoxName));
+    }
+    let name = loop {
+        let answer = ask(&format!("Run it in [1-{}] (Enter for 1): ", names.len()))?;
+        if answer.is_empty() {
+            break names[0].clone();
+        }
+        match answer.parse::<usize>() {
+            Ok(n) if (1..=names.len()).contains(&n) => break names[n - 1].clone(),
+            _ => {}
+        }
+    };
+    let scope = loop {
+        match ask("Remember this? [N]o, [s]ession, [d]irectory, [g]lobal: ")?
+            .to_lowercase()
+            .as_str()
+        {
+            "" | "n" | "no" => break None,
+            "s" | "session" => break Some(Scope::Session),
+            "d" | "directory" => break Some(Scope::Directory),
+            "g" | "global" => break Some(Scope::Global),
+            _ => {}
+        }
+    };
+    Ok((name, scope))
+}
@@ -0,0 +1,177 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::config;
+use crate::i18n::tr;
+use crate::paths;
+
+// DISTROBOX_CNF_PIN="cargo=rust,dnf=fedora" pins by hand for one environment
+const PIN_VAR: &str = "DISTROBOX_CNF_PIN";
+const PROJECT_FILE: &str = ".distrobox-cnf";
+const GLOBAL_FILE: &str = "pins";
+const SESSION_DIR: &str = "sessions";
+
+// how long a remembered choice of box holds
+#[derive(Clone, Copy)]
+pub enum Scope {
+    Session,
+    Directory,
+    Global,
+}
+
+impl Scope {
+    pub fn describe(self) -> String {
+        tr!(match self {
+            Scope::Session => "scope-session",
+            Scope::Directory => "scope-directory",
+            Scope::Global => "scope-global",
+        })
+    }
+}
+
+// the interactive shell the handler runs under, as "pid-starttime" so a recycled pid
+// does not inherit someone else's session
+fn session_key() -> Option<String> {
+    let fields_after_comm = |stat: String| -> Option<Vec<String>> {
+        Some(
+            stat.rsplit_once(')')?
+                .1
+                .split_whitespace()
+                .map(String::from)
+                .collect(),
+        )
+    };
+    let own = fields_after_comm(fs::read_to_string("/proc/self/stat").ok()?)?;
+    let ppid = own.get(1)?;
+    let parent = fields_after_comm(fs::read_to_string(format!("/proc/{}/stat", ppid)).ok()?)?;
+    // starttime is field 22, the 20th after the parenthesised command name
+    Some(format!("{}-{}", ppid, parent.get(19)?))
+}
+
+fn session_file() -> Option<PathBuf> {
+    Some(
+        paths::runtime_dir()
+            .ok()?
+            .join(SESSION_DIR)
+            .join(session_key()?),
+    )
+}
+
+// the nearest project file at or above the working directory
+fn project_file() -> Option<PathBuf> {
+    let cwd = env::current_dir().ok()?;
+    cwd.ancestors()
+        .map(|dir| dir.join(PROJECT_FILE))
+        .find(|f| f.is_file())
+}
+
+fn global_file() -> io::Result<PathBuf> {
+    Ok(paths::config_dir()?.join(GLOBAL_FILE))
+}
+
+// pin files are "command<TAB>box" lines
+fn read(path: &Path) -> Vec<(String, String)> {
+    fs::read_to_string(path)
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|l| l.split_once('\t'))
+        .map(|(c, b)| (c.to_string(), b.to_string()))
+        .collect()
+}
+
+fn write(path: &Path, pins: &[(String, String)]) -> io::Result<()> {
+    config::writable()?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let text: String = pins
+        .iter()
+        .map(|(c, b)| format!("{}\t{}\n", c, b))
+        .collect();
+    fs::write(path, text)
+}
+
+// the box `command` is pinned to, most specific layer first
+pub fn lookup(command: &str) -> Option<(String, Scope)> {
+    if let Ok(pins) = env::var(PIN_VAR) {
+        let pinned = pins
+            .split(',')
+            .filter_map(|p| p.split_once('='))
+            .find(|(c, _)| c.trim() == command);
+        if let Some((_, name)) = pinned {
+            return Some((name.trim().to_string(), Scope::Session));
+        }
+    }
+    let layers = [
+        (session_file(), Scope::Session),
+        (project_file(), Scope::Directory),
+        (global_file().ok(), Scope::Global),
+    ];
+    layers.into_iter().find_map(|(file, scope)| {
+        let pins = read(&file?);
+        let (_, name) = pins.into_iter().find(|(c, _)| c == command)?;
+        Some((name, scope))
+    })
+}
+
+// pin `command` to box `name`; a directory pin goes into the working directory itself
+pub fn remember(command: &str, name: &str, scope: Scope) -> io::Result<PathBuf> {
+    let path = match scope {
+        Scope::Session => session_file()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "Cannot tell the session"))?,
+        Scope::Directory => env::current_dir()?.join(PROJECT_FILE),
+        Scope::Global => global_file()?,
+    };
+    let mut pins = read(&path);
+    pins.retain(|(c, _)| c != command);
+    pins.push((command.to_string(), name.to_string()));
+    write(&path, &pins)?;
+    Ok(path)
+}
+
+// every pin file a choice could have gone into: all sessions, the project file in
+// effect here, and the global one
+fn all_files() -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = paths::runtime_dir()
+        .ok()
+        .and_then(|dir| fs::read_dir(dir.join(SESSION_DIR)).ok())
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .collect();
+    files.extend(project_file());
+    files.extend(global_file().ok());
+    files
+}
+
+// drop the pins `gone` matches as (command, box); returns the files that changed
+pub fn forget(gone: impl Fn(&str, &str) -> bool) -> io::Result<Vec<PathBuf>> {
+    let mut changed = vec![];
+    for path in all_files() {
+        let mut pins = read(&path);
+        let before = pins.len();
+        pins.retain(|(c, b)| !gone(c, b));
+        if pins.len() == before {
+            continue;
+        }
+        if pins.is_empty() {
+            fs::remove_file(&path)?;
+        } else {
+            write(&path, &pins)?;
+        }
+        changed.push(path);
+    }
+    Ok(changed)
+}
+
+// a hand-written pin in the environment cannot be taken back from here
+pub fn env_pinned(gone: impl Fn(&str, &str) -> bool) -> bool {
+    env::var(PIN_VAR).is_ok_and(|pins| {
+        pins.split(',')
+            .filter_map(|p| p.split_once('='))
+            .any(|(c, b)| gone(c.trim(), b.trim()))
+    })
+}
@@ -0,0 +1,163 @@
+use std::os::unix::process::ExitStatusExt;
+use std::process::{exit, Child, Command, Stdio};
+
+use crate::cache::Cache;
+use crate::config::Config;
+use crate::dbxconf::find_in_path;
+use crate::distrobox::{self, get_boxes};
+use crate::resolve::{self, Resolved};
+
+// split `cmd1 args | cmd2 args` into commands of words, with sh's quoting; anything that
+// needs a real shell (redirections, lists, substitutions) is refused rather than guessed at
+fn parse(line: &str) -> Result<Vec<Vec<String>>, String> {
+    let mut commands = vec![];
+    let mut words: Vec<String> = vec![];
+    let mut word: Option<String> = None;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => word.push(c),
+                        None => return Err("Unterminated ' quote".to_string()),
+                    }
+                }
+            }
+            '"' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\' | '$' | '`')) => word.push(c),
+                            Some(c) => {
+                                word.push('\\');
+                                word.push(c);
+                            }
+                            None => return Err("Unterminated \" quote".to_string()),
+                        },
+                        Some('$' | '`') => {
+                            return Err("Substitutions need a shell, run one in a box".to_string())
+                        }
+                        Some(c) => word.push(c),
+                        None => return Err("Unterminated \" quote".to_string()),
+                    }
+                }
+            }
+            '\\' => {
+                if let Some(c) = chars.next() {
+                    word.get_or_insert_with(String::new).push(c);
+                }
+            }
+            '|' => {
+                words.extend(word.take());
+                if words.is_empty() {
+                    return Err("Empty command in the pipeline".to_string());
+                }
+                commands.push(std::mem::take(&mut words));
+            }
+            c if c.is_whitespace() => words.extend(word.take()),
+            ';' | '&' | '<' | '>' | '(' | ')' | '$' | '`' => {
+                return Err(format!(
+                    "{} needs a shell, only plain pipelines work here",
+                    c
+                ))
+            }
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    words.extend(word.take());
+    if words.is_empty() {
+        return Err("Empty command in the pipeline".to_string());
+    }
+    commands.push(words);
+    Ok(commands)
+}
+
+// `pipeline [--] 'cmd1 | cmd2 ...'`: each command runs on the host if it is there, else in
+// the box that has it, with the pipes between them crossing containers
+pub fn pipeline_command(args: &[String]) {
+    let args = match args.first() {
+        Some(first) if first == "--" => &args[1..],
+        _ => args,
+    };
+    if args.is_empty() {
+        eprintln!("Usage: distrobox-cnf-handler pipeline [--] '<command> | <command> ...'");
+        exit(1);
+    }
+    let commands = match parse(&args.join(" ")) {
+        Ok(commands) => commands,
+        Err(e) => {
+            eprintln!("{}", e);
+            exit(1);
+        }
+    };
+    let config = Config::load().unwrap_or_default();
+    let boxes = match get_boxes() {
+        Ok(boxes) => resolve::order_boxes(boxes, None, &config),
+        Err(e) => {
+            eprintln!("Cannot get boxes: {:?}", e);
+            exit(2);
+        }
+    };
+    let mut cache = Cache::load().unwrap_or_else(|_| Cache::empty());
+    let mut planned: Vec<Command> = vec![];
+    for words in &commands {
+        if find_in_path(&words[0]).is_some() || words[0].contains('/') {
+            let mut cmd = Command::new(&words[0]);
+            cmd.args(&words[1..]);
+            planned.push(cmd);
+            continue;
+        }
+        match resolve::resolve(&words[0], &boxes, &mut cache, &config) {
+            Resolved::Found(name) => planned.push(distrobox::run_command(&name, words)),
+            Resolved::NotFound | Resolved::Incomplete => {
+                let _ = cache.save();
+                eprintln!("Cannot find {} on the host or in any box", words[0]);
+                exit(3);
+            }
+        }
+    }
+    let _ = cache.save();
+    let mut children: Vec<Child> = vec![];
+    let mut upstream: Option<Stdio> = None;
+    let last = planned.len() - 1;
+    for (n, mut cmd) in planned.into_iter().enumerate() {
+        if let Some(stdin) = upstream.take() {
+            cmd.stdin(stdin);
+        }
+        if n < last {
+            cmd.stdout(Stdio::piped());
+        }
+        match cmd.spawn() {
+            Ok(mut child) => {
+                upstream = child.stdout.take().map(Stdio::from);
+                children.push(child);
+            }
+            Err(e) => {
+                eprintln!("Cannot start {}: {:?}", commands[n][0], e);
+                // whatever already started sees end of input and finishes
+                drop(upstream);
+                for mut child in children {
+                    let _ = child.wait();
+                }
+                exit(2);
+            }
+        }
+    }
+    // like sh without pipefail, the pipeline's status is the last command's
+    let mut code = 0;
+    for mut child in children {
+        code = match child.wait() {
+            Ok(status) => status
+                .code()
+                .or_else(|| status.signal().map(|s| 128 + s))
+                .unwrap_or(1),
+            Err(_) => 1,
+        };
+    }
+    exit(code);
+}
@@ -0,0 +1,233 @@
+use crate::distrobox::DistroboxInstance;
+use crate::osrelease::{self, OsRelease};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum PackageManager {
+    Dnf,
+    Apt,
+    Pacman,
+    Zypper,
+    Apk,
+    Xbps,
+}
+
+// os-release IDs (or ID_LIKE entries) per package manager
+const BY_ID: [(&str, PackageManager); 17] = [
+    ("fedora", PackageManager::Dnf),
+    ("rhel", PackageManager::Dnf),
+    ("centos", PackageManager::Dnf),
+    ("rocky", PackageManager::Dnf),
+    ("almalinux", PackageManager::Dnf),
+    ("amzn", PackageManager::Dnf),
+    ("debian", PackageManager::Apt),
+    ("ubuntu", PackageManager::Apt),
+    ("arch", PackageManager::Pacman),
+    ("manjaro", PackageManager::Pacman),
+    ("suse", PackageManager::Zypper),
+    ("opensuse", PackageManager::Zypper),
+    ("sles", PackageManager::Zypper),
+    ("alpine", PackageManager::Apk),
+    ("wolfi", PackageManager::Apk),
+    ("chimera", PackageManager::Apk),
+    ("void", PackageManager::Xbps),
+];
+// fragments of the image references distrobox's compatibility list uses, for boxes whose
+// os-release has not been seen yet
+const BY_IMAGE: [(&str, PackageManager); 19] = [
+    ("fedora", PackageManager::Dnf),
+    ("centos", PackageManager::Dnf),
+    ("rockylinux", PackageManager::Dnf),
+    ("almalinux", PackageManager::Dnf),
+    ("ubi", PackageManager::Dnf),
+    ("amazonlinux", PackageManager::Dnf),
+    ("oraclelinux", PackageManager::Dnf),
+    ("debian", PackageManager::Apt),
+    ("ubuntu", PackageManager::Apt),
+    ("kali", PackageManager::Apt),
+    ("neurodebian", PackageManager::Apt),
+    ("arch", PackageManager::Pacman),
+    ("manjaro", PackageManager::Pacman),
+    ("steamos", PackageManager::Pacman),
+    ("opensuse", PackageManager::Zypper),
+    ("alpine", PackageManager::Apk),
+    ("wolfi", PackageManager::Apk),
+    ("chimera", PackageManager::Apk),
+    ("void", PackageManager::Xbps),
+];
+
+impl PackageManager {
+    pub fn name(self) -> &'static str {
+        match self {
+            PackageManager::Dnf => "dnf",
+            PackageManager::Apt => "apt",
+            PackageManager::Pacman => "pacman",
+            PackageManager::Zypper => "zypper",
+            PackageManager::Apk => "apk",
+            PackageManager::Xbps => "xbps",
+        }
+    }
+
+    // a non-interactive upgrade of everything; $s is "sudo -n" or empty for root
+    pub fn upgrade(self) -> &'static str {
+        match self {
+            PackageManager::Dnf => "$s dnf -y upgrade",
+            PackageManager::Apt => "$s apt-get update && $s apt-get -y upgrade",
+            PackageManager::Pacman => "$s pacman -Syu --noconfirm",
+            PackageManager::Zypper => "$s zypper --non-interactive update",
+            PackageManager::Apk => "$s apk upgrade --update-cache",
+            PackageManager::Xbps => "$s xbps-install -Syu",
+        }
+    }
+
+    // how a user finds the package shipping `command`, to be typed by hand
+    pub fn search_hint(self, command: &str) -> String {
+        match self {
+            PackageManager::Dnf => format!("dnf provides '*/bin/{}'", command),
+            PackageManager::Apt => format!("apt-file search bin/{}", command),
+            PackageManager::Pacman => format!("pacman -F {}", command),
+            PackageManager::Zypper => format!("zypper search --provides {}", command),
+            PackageManager::Apk => format!("apk search cmd:{}", command),
+            PackageManager::Xbps => format!("xlocate bin/{}", command),
+        }
+    }
+}
+
+pub fn from_release(release: &OsRelease) -> Option<PackageManager> {
+    std::iter::once(&release.id)
+        .chain(release.id_like.iter())
+        .find_map(|id| {
+            BY_ID
+                .iter()
+                .find(|(known, _)| id == known || id.starts_with(&format!("{}-", known)))
+                .map(|(_, pm)| *pm)
+        })
+}
+
+// "registry.fedoraproject.org/fedora-toolbox:40" -> dnf, going by the repository's name
+// and then by the namespaces above it, "ubi9/toolbox" or "opensuse/distrobox"; the
+// registry host is left out, so it cannot confuse it
+pub fn from_image(image: &str) -> Option<PackageManager> {
+    let mut parts: Vec<&str> = image.split('@').next()?.split('/').collect();
+    if parts.len() > 1 && (parts[0].contains(['.', ':']) || parts[0] == "localhost") {
+        parts.remove(0);
+    }
+    let repo = parts.pop()?.split(':').next()?;
+    std::iter::once(repo)
+        .chain(parts.into_iter().rev())
+        .find_map(by_name)
+}
+
+// a word of `name` is a distro's, give or take a version or "linux" after it: debian12,
+// ubi9, archlinux
+fn by_name(name: &str) -> Option<PackageManager> {
+    let name = name.to_lowercase();
+    let words: Vec<&str> = name
+        .split(['-', '_', '.'])
+        .map(|w| w.trim_end_matches(|c: char| c.is_ascii_digit()))
+        .collect();
+    BY_IMAGE
+        .iter()
+        .find(|(fragment, _)| {
+            words
+                .iter()
+                .any(|w| w == fragment || w.strip_suffix("linux") == Some(*fragment))
+        })
+        .map(|(_, pm)| *pm)
+}
+
+// os-release is authoritative, the image name is the fallback; `start` allows asking a
+// stopped box for its os-release
+pub fn for_box(dbx: &DistroboxInstance, start: bool) -> Option<PackageManager> {
+    osrelease::for_box(&dbx.name, dbx.running || start)
+        .as_ref()
+        .and_then(from_release)
+        .or_else(|| from_image(&dbx.image))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn managers_by_os_release() {
+        let cases = [
+            ("ID=fedora", Some("dnf")),
+            ("ID=\"rocky\"\nID_LIKE=\"rhel centos fedora\"", Some("dnf")),
+            ("ID=\"ol\"\nID_LIKE=\"fedora\"", Some("dnf")),
+            ("ID=\"amzn\"\nID_LIKE=\"fedora\"", Some("dnf")),
+            ("ID=debian", Some("apt")),
+            ("ID=kali\nID_LIKE=debian", Some("apt")),
+            ("ID=linuxmint\nID_LIKE=\"ubuntu debian\"", Some("apt")),
+            ("ID=arch", Some("pacman")),
+            ("ID=steamos\nID_LIKE=arch", Some("pacman")),
+            (
+                "ID=\"opensuse-tumbleweed\"\nID_LIKE=\"opensuse suse\"",
+                Some("zypper"),
+            ),
+            ("ID=\"sles\"\nID_LIKE=\"suse\"", Some("zypper")),
+            ("ID=alpine", Some("apk")),
+            ("ID=wolfi", Some("apk")),
+            ("ID=\"chimera\"", Some("apk")),
+            ("ID=\"void\"", Some("xbps")),
+            ("ID=gentoo", None),
+            ("ID=nixos", None),
+            ("", None),
+            // a longer ID is another distro, unless its ID_LIKE says whose family it is in
+            ("ID=archer", None),
+        ];
+        for (text, want) in cases {
+            let got = from_release(&OsRelease::parse(text)).map(PackageManager::name);
+            assert_eq!(got, want, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn managers_by_image() {
+        let cases = [
+            ("registry.fedoraproject.org/fedora-toolbox:40", Some("dnf")),
+            ("quay.io/fedora/fedora:latest", Some("dnf")),
+            ("quay.io/toolbx-images/centos-toolbox:stream9", Some("dnf")),
+            ("quay.io/rockylinux/rockylinux:9", Some("dnf")),
+            ("quay.io/almalinuxorg/almalinux:9", Some("dnf")),
+            ("docker.io/almalinux/9-init", Some("dnf")),
+            ("registry.access.redhat.com/ubi9/toolbox", Some("dnf")),
+            ("public.ecr.aws/amazonlinux/amazonlinux:2023", Some("dnf")),
+            (
+                "container-registry.oracle.com/os/oraclelinux:9",
+                Some("dnf"),
+            ),
+            ("quay.io/toolbx/ubuntu-toolbox:24.04", Some("apt")),
+            ("docker.io/library/debian:12", Some("apt")),
+            ("docker.io/kalilinux/kali-rolling:latest", Some("apt")),
+            ("quay.io/toolbx/arch-toolbox:latest", Some("pacman")),
+            ("docker.io/library/archlinux:latest", Some("pacman")),
+            ("docker.io/manjarolinux/base", Some("pacman")),
+            (
+                "registry.opensuse.org/opensuse/distrobox:latest",
+                Some("zypper"),
+            ),
+            (
+                "registry.opensuse.org/opensuse/tumbleweed:latest",
+                Some("zypper"),
+            ),
+            ("docker.io/library/alpine:3.20", Some("apk")),
+            ("cgr.dev/chainguard/wolfi-base:latest", Some("apk")),
+            ("docker.io/chimeralinux/chimera", Some("apk")),
+            ("ghcr.io/void-linux/void-glibc-full:latest", Some("xbps")),
+            ("docker.io/library/debian@sha256:0123abcd", Some("apt")),
+            ("localhost:5000/fedora:40", Some("dnf")),
+            ("docker.io/gentoo/stage3", None),
+            ("docker.io/nixos/nix", None),
+            ("my-app:latest", None),
+            ("", None),
+        ];
+        for (image, want) in cases {
+            assert_eq!(
+                from_image(image).map(PackageManager::name),
+                want,
+                "{}",
+                image
+            );
+        }
+    }
+}
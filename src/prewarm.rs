@@ -0,0 +1,67 @@
+use std::process::exit;
+use std::time::Instant;
+
+use crate::coldstart;
+use crate::color::{self, Style};
+use crate::config::Config;
+use crate::distrobox::{self, get_boxes, DistroboxInstance};
+use crate::tasks;
+
+const DEFAULT_COUNT: usize = 1;
+
+// `prewarm [--count <n>]`: start the n highest-priority stopped boxes, e.g. from a login script
+pub fn prewarm_command(args: &[String]) {
+    let config = Config::load().unwrap_or_default();
+    let mut count = config
+        .get_int("prewarm.count")
+        .and_then(|n| usize::try_from(n).ok())
+        .unwrap_or(DEFAULT_COUNT);
+    match args {
+        [] => {}
+        [flag, n] if flag == "--count" => match n.parse() {
+            Ok(n) => count = n,
+            Err(_) => usage(),
+        },
+        _ => usage(),
+    }
+    let mut boxes: Vec<DistroboxInstance> = match get_boxes() {
+        Ok(boxes) => boxes,
+        Err(e) => {
+            eprintln!("Cannot get boxes: {:?}", e);
+            exit(2);
+        }
+    };
+    // priority alone decides here; whatever is already running is simply skipped
+    boxes.sort_by_key(|b| b.priority);
+    let targets: Vec<String> = boxes
+        .into_iter()
+        .take(count)
+        .filter(|b| !b.running)
+        .map(|b| b.name)
+        .collect();
+    let mut failed = false;
+    for (name, result) in targets.iter().zip(tasks::parallel(&targets, |name| {
+        let started = Instant::now();
+        distrobox::start(name).map(|()| started.elapsed())
+    })) {
+        match result {
+            Some(Ok(took)) => {
+                let _ = coldstart::record(name, took);
+                println!("Started {}", color::out(name, Style::BoxName));
+            }
+            Some(Err(e)) => {
+                eprintln!("Cannot start {}: {}", color::err(name, Style::BoxName), e);
+                failed = true;
+            }
+            None => failed = true,
+        }
+    }
+    if failed {
+        exit(2);
+    }
+}
+
+fn usage() -> ! {
+    eprintln!("Usage: distrobox-cnf-handler prewarm [--count <n>]");
+    exit(1);
+}
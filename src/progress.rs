@@ -0,0 +1,97 @@
+use std::io::{self, IsTerminal, Write};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::msg;
+use crate::tasks;
+
+// resolutions faster than this never show anything
+const SHOW_AFTER: Duration = Duration::from_millis(300);
+const TICK: Duration = Duration::from_millis(100);
+const FRAMES: [char; 4] = ['|', '/', '-', '\\'];
+
+#[derive(Default)]
+struct State {
+    label: String,
+    visible: bool,
+    paused: bool,
+    done: bool,
+}
+
+// a one-line "searching arch… fedora…" status on stderr, drawn by a background thread
+pub struct Progress {
+    state: Option<Arc<Mutex<State>>>,
+}
+
+fn erase(state: &mut State) {
+    if state.visible {
+        eprint!("\r\x1b[K");
+        let _ = io::stderr().flush();
+        state.visible = false;
+    }
+}
+
+impl Progress {
+    pub fn new() -> Progress {
+        if msg::quiet() || !io::stderr().is_terminal() {
+            return Progress { state: None };
+        }
+        let state = Arc::new(Mutex::new(State::default()));
+        let shared = Arc::clone(&state);
+        let started = Instant::now();
+        tasks::spawn("progress", move || {
+            let mut frame = 0;
+            loop {
+                thread::sleep(TICK);
+                let Ok(mut state) = shared.lock() else {
+                    return;
+                };
+                if state.done {
+                    return;
+                }
+                if state.paused || state.label.is_empty() || started.elapsed() < SHOW_AFTER {
+                    continue;
+                }
+                eprint!("\r\x1b[K{} {}", FRAMES[frame % FRAMES.len()], state.label);
+                let _ = io::stderr().flush();
+                state.visible = true;
+                frame += 1;
+            }
+        });
+        Progress { state: Some(state) }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.state.is_some()
+    }
+
+    // note that another box is being searched and show the line again
+    pub fn searching(&self, name: &str) {
+        if let Some(Ok(mut state)) = self.state.as_ref().map(|s| s.lock()) {
+            if state.label.is_empty() {
+                state.label = format!("searching {}…", name);
+            } else {
+                state.label.push_str(&format!(" {}…", name));
+            }
+            state.paused = false;
+        }
+    }
+
+    // clear the line and keep it hidden, e.g. before the command's own output may start
+    pub fn pause(&self) {
+        if let Some(Ok(mut state)) = self.state.as_ref().map(|s| s.lock()) {
+            state.paused = true;
+            erase(&mut state);
+        }
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        if let Some(Ok(mut state)) = self.state.as_ref().map(|s| s.lock()) {
+            state.done = true;
+            erase(&mut state);
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use std::env;
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::distrobox::sh_quote;
+
+// set by the hook to the file the shell reads back before its next prompt
+const REPLAY_VAR: &str = "DISTROBOX_CNF_REPLAY";
+
+// the command_not_found contracts of the shells the handler is hooked into: the hook's
+// exit status becomes the command's, and "not found" is 127 plus the shell's own message
+#[derive(Clone, Copy)]
+pub enum Protocol {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl Protocol {
+    pub fn parse(shell: &str) -> Option<Protocol> {
+        match shell {
+            "bash" => Some(Protocol::Bash),
+            "zsh" => Some(Protocol::Zsh),
+            "fish" => Some(Protocol::Fish),
+            _ => None,
+        }
+    }
+
+    // `ran` is the command's exit code, or None when it never ran anywhere
+    pub fn exit(self, command: &str, ran: Option<i32>) -> i32 {
+        if let Some(code) = ran {
+            return code;
+        }
+        // printed even with --quiet: it is what the shell would have said without the hook
+        match self {
+            Protocol::Bash => eprintln!("bash: {}: command not found", command),
+            Protocol::Zsh => eprintln!("zsh: command not found: {}", command),
+            // fish sets $status to 127 itself and ignores ours, but keep it consistent
+            Protocol::Fish => eprintln!("fish: Unknown command: {}", command),
+        }
+        127
+    }
+
+    // a function that runs `command` in box `name` through `exe`, e.g.
+    //   htop() { '/usr/bin/distrobox-cnf-handler' run --box 'fedora' -- 'htop' "$@"; }
+    pub fn function_line(self, command: &str, name: &str, exe: &Path) -> Option<String> {
+        if !function_name(command) {
+            return None;
+        }
+        let run = format!(
+            "{} run --box {} -- {}",
+            sh_quote(&exe.to_string_lossy()),
+            sh_quote(name),
+            sh_quote(command)
+        );
+        Some(match self {
+            Protocol::Bash | Protocol::Zsh => format!("{}() {{ {} \"$@\"; }}", command, run),
+            Protocol::Fish => format!("function {}; {} $argv; end", command, run),
+        })
+    }
+
+    // fish keeps no table of commands
+    pub fn hashes(self) -> bool {
+        !matches!(self, Protocol::Fish)
+    }
+
+    // the line that makes the shell go straight to `path` for `command`
+    pub fn hash_line(self, command: &str, path: &Path) -> Option<String> {
+        let (command, path) = (sh_quote(command), sh_quote(&path.to_string_lossy()));
+        match self {
+            Protocol::Bash => Some(format!("hash -p {} {}", path, command)),
+            Protocol::Zsh => Some(format!("hash {}={}", command, path)),
+            Protocol::Fish => None,
+        }
+    }
+}
+
+// a name any of the shells takes for a function without quoting
+fn function_name(command: &str) -> bool {
+    !command.starts_with('-')
+        && command
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "_.+-".contains(c))
+}
+
+// leave `line` for the shell to run in itself; false under a hook that takes nothing in
+pub fn replay(line: &str) -> io::Result<bool> {
+    let Some(path) = env::var_os(REPLAY_VAR) else {
+        return Ok(false);
+    };
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", line)?;
+    Ok(true)
+}
@@ -0,0 +1,158 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use crate::paths;
+
+// the label distrobox create puts on its containers; a quadlet carrying it is a box
+const DISTROBOX_LABEL: &str = "manager=distrobox";
+const SYSTEM_DIRS: [&str; 2] = ["/etc/containers/systemd", "/usr/share/containers/systemd"];
+
+// a box defined as a podman quadlet: systemd creates its container when the unit starts
+// and usually removes it again on stop, so podman may not know about it at all
+pub struct Quadlet {
+    pub name: String,
+    pub unit: String,
+    pub image: String,
+}
+
+fn user_dirs() -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = paths::config_home()
+        .map(|home| home.join("containers").join("systemd"))
+        .into_iter()
+        .collect();
+    dirs.push(PathBuf::from("/etc/containers/systemd/users"));
+    dirs
+}
+
+// the [Container] settings that matter here; labels may be repeated and hold several
+// space-separated pairs
+fn parse(path: &Path, text: &str) -> Option<Quadlet> {
+    let stem = path.file_stem()?.to_str()?;
+    let mut in_container = false;
+    let mut name = None;
+    let mut image = String::new();
+    let mut is_box = false;
+    for line in text.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_container = line == "[Container]";
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=').filter(|_| in_container) else {
+            continue;
+        };
+        let value = value.trim();
+        match key.trim() {
+            "ContainerName" => name = Some(value.to_string()),
+            "Image" => image = value.to_string(),
+            "Label" => {
+                is_box |= value
+                    .split_whitespace()
+                    .any(|l| l.trim_matches('"') == DISTROBOX_LABEL)
+            }
+            _ => {}
+        }
+    }
+    is_box.then(|| Quadlet {
+        // quadlet's own default for an unnamed container
+        name: name.unwrap_or_else(|| format!("systemd-{}", stem)),
+        unit: format!("{}.service", stem),
+        image,
+    })
+}
+
+fn dirs(root: bool) -> Vec<PathBuf> {
+    if root {
+        SYSTEM_DIRS.iter().map(PathBuf::from).collect()
+    } else {
+        user_dirs()
+    }
+}
+
+// what discover found last, and the directories' modification times it went by
+struct Known {
+    root: bool,
+    stamps: Vec<Option<SystemTime>>,
+    quadlets: Arc<Vec<Quadlet>>,
+}
+
+static KNOWN: Mutex<Option<Known>> = Mutex::new(None);
+
+// every box quadlet the user (or, for rootful boxes, the system) has defined. A cache
+// hit asks on every run whether its box is one, and a daemon outlives new ones, so the
+// files are read again only once a unit is added to or removed from their directories
+pub fn known(root: bool) -> Arc<Vec<Quadlet>> {
+    let stamps: Vec<Option<SystemTime>> = dirs(root)
+        .iter()
+        .map(|dir| fs::metadata(dir).and_then(|m| m.modified()).ok())
+        .collect();
+    let Ok(mut known) = KNOWN.lock() else {
+        return Arc::new(discover(root));
+    };
+    if let Some(k) = known
+        .as_ref()
+        .filter(|k| k.root == root && k.stamps == stamps)
+    {
+        return Arc::clone(&k.quadlets);
+    }
+    let quadlets = Arc::new(discover(root));
+    *known = Some(Known {
+        root,
+        stamps,
+        quadlets: Arc::clone(&quadlets),
+    });
+    quadlets
+}
+
+fn discover(root: bool) -> Vec<Quadlet> {
+    let mut found: Vec<Quadlet> = vec![];
+    for dir in dirs(root) {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        let mut files: Vec<PathBuf> = entries
+            .flatten()
+            .map(|e| e.path())
+            .filter(|p| p.extension().is_some_and(|e| e == "container"))
+            .collect();
+        files.sort();
+        for file in files {
+            let Ok(text) = fs::read_to_string(&file) else {
+                continue;
+            };
+            // the first definition of a name wins, as with systemd's own search path
+            if let Some(quadlet) =
+                parse(&file, &text).filter(|q| !found.iter().any(|f| f.name == q.name))
+            {
+                found.push(quadlet);
+            }
+        }
+    }
+    found
+}
+
+// have systemd bring the unit up, so its container exists before distrobox-enter looks
+pub fn start(unit: &str, root: bool) -> io::Result<()> {
+    let mut cmd = if root {
+        let mut cmd = Command::new("sudo");
+        cmd.arg("systemctl");
+        cmd
+    } else {
+        let mut cmd = Command::new("systemctl");
+        cmd.arg("--user");
+        cmd
+    };
+    let status = cmd
+        .args(["start", unit])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(io::Error::other(format!("systemctl {}", status)))
+    }
+}
@@ -0,0 +1,77 @@
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use crate::tasks;
+
+// how long stderr is held back after the first bytes arrive
+const HOLD: Duration = Duration::from_millis(300);
+// more than a short complaint is output, not a complaint
+const HOLD_MAX: usize = 4096;
+
+#[derive(Default)]
+struct State {
+    held: Vec<u8>,
+    passing: bool,
+    done: bool,
+}
+
+// a boxed command's stderr, passed on byte for byte except for its first moments: a box
+// without the command, or one that will not start, says so there and exits at once, and
+// that is the handler's to report rather than something to show
+#[derive(Clone, Default)]
+pub struct Relay {
+    state: Arc<Mutex<State>>,
+}
+
+fn pass(bytes: &[u8]) {
+    let mut err = io::stderr().lock();
+    let _ = err.write_all(bytes).and_then(|_| err.flush());
+}
+
+impl Relay {
+    pub fn write(&self, bytes: &[u8]) {
+        let Ok(mut state) = self.state.lock() else {
+            return;
+        };
+        if state.passing {
+            pass(bytes);
+            return;
+        }
+        if state.held.is_empty() {
+            let relay = self.clone();
+            tasks::spawn("relay", move || {
+                thread::sleep(HOLD);
+                relay.release();
+            });
+        }
+        state.held.extend_from_slice(bytes);
+        if state.held.len() > HOLD_MAX {
+            drop(state);
+            self.release();
+        }
+    }
+
+    // the command is running for real, show what it said and everything after
+    fn release(&self) {
+        let Ok(mut state) = self.state.lock() else {
+            return;
+        };
+        if !state.done && !state.passing {
+            state.passing = true;
+            pass(&std::mem::take(&mut state.held));
+        }
+    }
+
+    // once the command exited: whatever is still held is shown only if it `ran`
+    pub fn finish(&self, ran: bool) {
+        let Ok(mut state) = self.state.lock() else {
+            return;
+        };
+        if ran && !state.passing {
+            pass(&state.held);
+        }
+        state.done = true;
+    }
+}
@@ -0,0 +1,153 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::color::{self, Style};
+use crate::i18n::tr;
+use crate::json::Json;
+use crate::msg::say;
+use crate::suggest::{Source, Suggestion};
+
+static JSON: AtomicBool = AtomicBool::new(false);
+
+// from --output json: the final report goes to stdout as JSON instead of prose
+pub fn set_json(json: bool) {
+    JSON.store(json, Ordering::Relaxed);
+}
+
+pub fn json() -> bool {
+    JSON.load(Ordering::Relaxed)
+}
+
+// everything known once a command was found nowhere
+pub struct Report {
+    pub command: String,
+    // box and what became of it: "not found", "cooling down", "box failed: ..."
+    pub searched: Vec<(String, String)>,
+    pub suggestions: Vec<Suggestion>,
+    // a versioned name of the command, such as python3 for python
+    pub alternative: Option<Suggestion>,
+    pub builtin: Option<&'static str>,
+    // a box and the command to type there to find a package
+    pub package_search: Option<(String, String)>,
+    pub no_boxes: bool,
+}
+
+impl Report {
+    pub fn show(&self) {
+        if json() {
+            println!("{}", self.to_json());
+            return;
+        }
+        say!(
+            "{}",
+            tr!(
+                "not-found",
+                command = color::err(&self.command, Style::Command)
+            )
+        );
+        if !self.searched.is_empty() {
+            let searched: Vec<String> = self
+                .searched
+                .iter()
+                .map(|(name, result)| {
+                    tr!(
+                        "searched-box",
+                        box = color::err(name, Style::BoxName),
+                        result = result
+                    )
+                })
+                .collect();
+            say!("{}", tr!("searched", boxes = searched.join(", ")));
+        }
+        if let Some(alternative) = &self.alternative {
+            say!(
+                "{}",
+                tr!(
+                    "versioned",
+                    hint = hint(alternative),
+                    command = &self.command
+                )
+            );
+        }
+        let hints: Vec<String> = self
+            .suggestions
+            .iter()
+            .filter(|s| {
+                self.alternative
+                    .as_ref()
+                    .is_none_or(|a| a.command != s.command)
+            })
+            .map(hint)
+            .collect();
+        if !hints.is_empty() {
+            let hints = hints.join(&format!(" {} ", tr!("or")));
+            say!("{}", tr!("did-you-mean", hints = hints));
+        }
+        if let Some(shell) = self.builtin {
+            say!(
+                "{}",
+                tr!("foreign-builtin", command = &self.command, shell = shell)
+            );
+        }
+        if let Some((name, search)) = &self.package_search {
+            say!("{}", tr!("package-hint", box = name, search = search));
+        }
+        if self.no_boxes {
+            say!("{}", tr!("create-box-hint"));
+        }
+    }
+
+    pub fn to_json(&self) -> Json {
+        let searched = self
+            .searched
+            .iter()
+            .map(|(name, result)| {
+                Json::obj(vec![
+                    ("box", Json::str(name)),
+                    ("result", Json::str(result)),
+                ])
+            })
+            .collect();
+        let suggestions = self.suggestions.iter().map(suggestion_json).collect();
+        let package_search = self.package_search.as_ref().map(|(name, search)| {
+            Json::obj(vec![
+                ("box", Json::str(name)),
+                ("command", Json::str(search)),
+            ])
+        });
+        Json::obj(vec![
+            ("command", Json::str(&self.command)),
+            ("found", false.into()),
+            ("searched", Json::Arr(searched)),
+            ("suggestions", Json::Arr(suggestions)),
+            (
+                "alternative",
+                self.alternative.as_ref().map(suggestion_json).into(),
+            ),
+            ("builtin", self.builtin.map(Json::str).into()),
+            ("package_search", package_search.into()),
+            ("no_boxes", self.no_boxes.into()),
+        ])
+    }
+}
+
+fn hint(s: &Suggestion) -> String {
+    match &s.source {
+        Source::Host => tr!(
+            "hint-host",
+            command = color::err(&s.command, Style::Command)
+        ),
+        Source::Box(name) => tr!(
+            "hint-box",
+            command = color::err(&s.command, Style::Command),
+            box = color::err(name, Style::BoxName)
+        ),
+    }
+}
+
+fn suggestion_json(s: &Suggestion) -> Json {
+    let place = match &s.source {
+        Source::Host => Json::Null,
+        Source::Box(name) => Json::str(name),
+    };
+    Json::obj(vec![("command", Json::str(&s.command)), ("box", place)])
+}
@@ -0,0 +1,486 @@
+use std::io::{self, BufRead};
+use std::path::PathBuf;
+use std::process::exit;
+use std::time::Instant;
+
+use crate::affinity;
+use crate::arch;
+use crate::cache::{Cache, Resolution};
+use crate::caller;
+use crate::coldstart;
+use crate::config::{self, Config};
+use crate::cooldown;
+use crate::display;
+use crate::distrobox::{self, get_boxes, DistroboxInstance, ProbeOutcome};
+use crate::gpu;
+use crate::groups;
+use crate::i18n::tr;
+use crate::json::Json;
+use crate::msg::say;
+use crate::osrelease;
+use crate::pins;
+use crate::strategy::Strategy;
+use crate::tasks;
+use crate::trace::Trace;
+
+const NOT_FOUND: &str = "NOT_FOUND";
+
+// search order: running boxes first, then by priority, with the box that served
+// this command last time ahead of everything
+pub fn order_boxes(
+    mut boxes: Vec<DistroboxInstance>,
+    cached: Option<&Resolution>,
+    config: &Config,
+) -> Vec<DistroboxInstance> {
+    // inside a directory tree with a group of its own, only that group is searched
+    if let Some(group) = groups::current(config) {
+        boxes.retain(|b| group.contains(&b.name));
+    }
+    // stopped_boxes = "skip" keeps the handler from ever starting a container, as does
+    // read_only
+    if config.get_str("stopped_boxes") == Some("skip") || config::read_only() {
+        boxes.retain(|b| b.running);
+    }
+    let strategy = Strategy::load(config);
+    boxes.retain(|b| strategy.searches(b.running));
+    boxes.sort();
+    if strategy.stopped_first() {
+        boxes.sort_by_key(|b| b.running);
+    }
+    // optionally, boxes of the host's own distro family go first so commands behave
+    // most like a native install; this beats the running-first rule
+    if config.get_bool("prefer_host_distro") == Some(true) {
+        if let Some(host) = osrelease::host() {
+            boxes.sort_by_key(|b| {
+                !osrelease::for_box(&b.name, b.running).is_some_and(|r| r.same_family(&host))
+            });
+        }
+    }
+    if let Some(Resolution::Found(name)) = cached {
+        if let Some(pos) = boxes.iter().position(|b| &b.name == name) {
+            let hit = boxes.remove(pos);
+            boxes.insert(0, hit);
+        }
+    }
+    boxes
+}
+
+// a box picked before any search: the caller's rule, else a pin, with why. Offline, one
+// naming a box that is gone must not make distrobox-enter offer to create (and pull) it,
+// and one made elsewhere does not take a directory out of its group
+pub fn pinned(
+    config: &Config,
+    command: &str,
+    caller: Option<&caller::Rule>,
+    trace: &mut Trace,
+) -> Option<(String, String)> {
+    let offline = config::offline(config);
+    caller
+        .and_then(|r| Some((r.name.clone()?, tr!("set-for", process = &r.process))))
+        .or_else(|| {
+            pins::lookup(command)
+                .map(|(name, scope)| (name, tr!("pinned-for", scope = scope.describe())))
+        })
+        .filter(|(name, _)| {
+            !offline || get_boxes().is_ok_and(|boxes| boxes.iter().any(|b| &b.name == name))
+        })
+        .filter(|(name, why)| {
+            let Some(group) = groups::excluding(config, name) else {
+                return true;
+            };
+            say!(
+                "{}",
+                tr!(
+                    "outside-group",
+                    box = name,
+                    reason = why,
+                    boxes = group.join(", ")
+                )
+            );
+            trace.step(
+                name,
+                "skip",
+                Instant::now(),
+                "outside this directory's group",
+            );
+            false
+        })
+}
+
+// what a search has to know about the boxes besides their order
+pub struct Route {
+    // the ones with GPU passthrough, when the command wants a GPU and it could be told
+    pub gpu_boxes: Option<Vec<String>>,
+    // the ones that cannot reach the display, when the command needs it
+    pub headless: Option<Vec<String>>,
+}
+
+// arrange `boxes`, from order_boxes, for `command` the way every search goes: emulated
+// boxes last or left out, the affine box first, slow cold starts last, then GPU and
+// display capable boxes ahead of the rest
+pub fn route(
+    config: &Config,
+    command: &str,
+    boxes: &mut Vec<DistroboxInstance>,
+    affine: Option<&str>,
+    wants_gpu: bool,
+    sockets: &[PathBuf],
+    trace: &mut Trace,
+) -> Route {
+    // boxes of another architecture run under qemu-user, slowly and not always correctly,
+    // so they go last or, under opt-in, only take the commands named for them
+    let policy = arch::policy(config);
+    if policy != arch::Policy::Allow {
+        let started = Instant::now();
+        let emulated: Vec<String> = boxes
+            .iter()
+            .filter(|b| arch::emulated(b))
+            .map(|b| b.name.clone())
+            .collect();
+        if !emulated.is_empty() {
+            if policy == arch::Policy::OptIn && !arch::opted_in(config, command) {
+                boxes.retain(|b| !emulated.contains(&b.name));
+                trace.step(
+                    "host",
+                    "arch",
+                    started,
+                    &format!("left out {}", emulated.join(", ")),
+                );
+            } else {
+                boxes.sort_by_key(|b| emulated.contains(&b.name));
+                trace.step(
+                    "host",
+                    "arch",
+                    started,
+                    &format!("{} last", emulated.join(", ")),
+                );
+            }
+        }
+    }
+    if let Some(pos) = affine.and_then(|a| boxes.iter().position(|b| b.name == a)) {
+        let kept = boxes.remove(pos);
+        trace.step("host", "affinity", Instant::now(), &kept.name);
+        boxes.insert(0, kept);
+    }
+    // a stopped box whose init always takes long would hold up every search that starts it
+    if config.get_bool("cold_start.demote") == Some(true) {
+        let slow: Vec<String> = coldstart::slow(config)
+            .into_iter()
+            .map(|s| s.name)
+            .filter(|name| boxes.iter().any(|b| &b.name == name && !b.running))
+            .collect();
+        if !slow.is_empty() {
+            boxes.sort_by_key(|b| slow.contains(&b.name));
+            let last = format!("{} last", slow.join(", "));
+            trace.step("host", "cold start", Instant::now(), &last);
+        }
+    }
+    // GPU commands go to boxes with GPU passthrough first, ahead of the cached box;
+    // None when it does not matter or cannot be told
+    let gpu_boxes = if wants_gpu {
+        let started = Instant::now();
+        let capable = gpu::capable(boxes.iter().map(|b| b.name.as_str())).ok();
+        if let Some(capable) = &capable {
+            boxes.sort_by_key(|b| !capable.contains(&b.name));
+            trace.step(
+                "host",
+                "gpu",
+                started,
+                &format!("{} with gpu", capable.len()),
+            );
+        }
+        capable
+    } else {
+        None
+    };
+    // a graphical program must not start where it cannot open a window, so boxes that
+    // cannot reach the display go last and are only probed
+    let headless: Option<Vec<String>> = if sockets.is_empty() {
+        None
+    } else {
+        let started = Instant::now();
+        let capable = display::capable(boxes.iter().map(|b| b.name.as_str()), sockets).ok();
+        if let Some(capable) = &capable {
+            boxes.sort_by_key(|b| !capable.contains(&b.name));
+            trace.step(
+                "host",
+                "display",
+                started,
+                &format!("{} with display", capable.len()),
+            );
+        }
+        capable.map(|c| {
+            boxes
+                .iter()
+                .filter(|b| !c.contains(&b.name))
+                .map(|b| b.name.clone())
+                .collect()
+        })
+    };
+    Route {
+        gpu_boxes,
+        headless,
+    }
+}
+
+pub enum Resolved {
+    Found(String),
+    NotFound,
+    // not found, but some boxes could not be searched
+    Incomplete,
+}
+
+// find the box providing `command` without running it
+pub fn resolve(
+    command: &str,
+    boxes: &[DistroboxInstance],
+    cache: &mut Cache,
+    config: &Config,
+) -> Resolved {
+    let cached = cache.lookup(command).map(|e| e.resolution.clone());
+    if cached == Some(Resolution::NotFound) {
+        return Resolved::NotFound;
+    }
+    let mut ordered: Vec<&DistroboxInstance> = boxes.iter().collect();
+    if let Some(Resolution::Found(name)) = &cached {
+        ordered.sort_by_key(|b| &b.name != name);
+    }
+    search(command, ordered, false, cache, config)
+}
+
+// where the handler would run `command`: its pin, else a search of the boxes in the
+// handler's order, leaving out those that cannot open its window, so a launch from
+// here goes where typing it into the shell would
+fn routed(
+    command: &str,
+    boxes: &[DistroboxInstance],
+    caller: Option<&caller::Rule>,
+    cache: &mut Cache,
+    config: &Config,
+) -> Resolved {
+    // the steps are the handler's to record, this trace only takes them
+    let mut trace = Trace::new(command);
+    let cached = cache.lookup(command).map(|e| e.resolution.clone());
+    if cached == Some(Resolution::NotFound) {
+        return Resolved::NotFound;
+    }
+    if let Some((name, _)) = pinned(config, command, caller, &mut trace) {
+        if let Ok(ProbeOutcome::Found) = distrobox::probe_retrying(&name, command) {
+            return Resolved::Found(name);
+        }
+    }
+    let mut ordered = order_boxes(boxes.to_vec(), cached.as_ref(), config);
+    let affine = affinity::preferred(config, command);
+    let sockets = if display::wanted(config, command) {
+        display::sockets()
+    } else {
+        vec![]
+    };
+    let wants_gpu = gpu::wanted(config, command);
+    let route = route(
+        config,
+        command,
+        &mut ordered,
+        affine.as_deref(),
+        wants_gpu,
+        &sockets,
+        &mut trace,
+    );
+    let headless = route.headless.unwrap_or_default();
+    let reachable = ordered.iter().filter(|b| !headless.contains(&b.name));
+    search(command, reachable, !headless.is_empty(), cache, config)
+}
+
+// probe `boxes` in order until one has `command`; with `left_out`, boxes not among them
+// might have it, so not finding it is no answer to cache. parallel_probe = true asks the
+// running ones all at once and reads the answers in the same order; a stopped box is
+// still only started when its turn comes
+fn search<'a>(
+    command: &str,
+    boxes: impl IntoIterator<Item = &'a DistroboxInstance>,
+    left_out: bool,
+    cache: &mut Cache,
+    config: &Config,
+) -> Resolved {
+    let (cooling, boxes): (Vec<&DistroboxInstance>, Vec<_>) =
+        boxes.into_iter().partition(|b| cooldown::cooling(&b.name));
+    let mut failed = left_out || !cooling.is_empty();
+    let ahead: Vec<&str> = match config.get_bool("parallel_probe") {
+        Some(true) => boxes
+            .iter()
+            .filter(|b| b.running)
+            .map(|b| b.name.as_str())
+            .collect(),
+        _ => vec![],
+    };
+    let mut answers: Vec<(&str, Option<io::Result<ProbeOutcome>>)> = ahead
+        .iter()
+        .copied()
+        .zip(tasks::parallel(&ahead, |name| {
+            distrobox::probe_retrying(name, command)
+        }))
+        .collect();
+    for dbx in boxes {
+        // a probe that panicked is asked again here
+        let answer = answers
+            .iter_mut()
+            .find(|(name, _)| *name == dbx.name)
+            .and_then(|(_, answer)| answer.take());
+        match answer.unwrap_or_else(|| distrobox::probe_retrying(&dbx.name, command)) {
+            Ok(ProbeOutcome::Found) => {
+                let _ = cooldown::recovered(&dbx.name);
+                cache.record(command, Resolution::Found(dbx.name.clone()));
+                return Resolved::Found(dbx.name.clone());
+            }
+            Ok(ProbeOutcome::NotFound) => {
+                let _ = cooldown::recovered(&dbx.name);
+            }
+            Ok(ProbeOutcome::Unknown) => {
+                say!("{}", tr!("probe-by-running", box = &dbx.name));
+                failed = true;
+            }
+            Ok(ProbeOutcome::BoxFailed(reason)) => {
+                say!(
+                    "{}",
+                    tr!("box-failed-named", box = &dbx.name, reason = &reason)
+                );
+                cooldown::failed(config, &dbx.name);
+                failed = true;
+            }
+            Err(e) => {
+                say!("{}", tr!("cannot-enter", error = format!("{:?}", e)));
+                failed = true;
+            }
+        }
+    }
+    if failed {
+        return Resolved::Incomplete;
+    }
+    if groups::current(config).is_none() {
+        cache.record(command, Resolution::NotFound);
+    }
+    Resolved::NotFound
+}
+
+// `resolve [--batch] [--output json] [command...]`: print "command<TAB>box" or
+// "command<TAB>NOT_FOUND" per command
+pub fn resolve_command(args: &[String]) {
+    let mut batch = false;
+    let mut json = false;
+    let mut rest = args.iter().peekable();
+    while let Some(flag) = rest.peek() {
+        match flag.as_str() {
+            "--batch" => batch = true,
+            "--output" => {
+                rest.next();
+                match rest.peek().map(|s| s.as_str()) {
+                    Some("json") => json = true,
+                    Some("text") => json = false,
+                    _ => {
+                        eprintln!("Usage: distrobox-cnf-handler resolve --output text|json");
+                        exit(1);
+                    }
+                }
+            }
+            _ => break,
+        }
+        rest.next();
+    }
+    let mut commands: Vec<String> = rest.cloned().collect();
+    if batch {
+        for line in io::stdin().lock().lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            let line = line.trim();
+            if !line.is_empty() {
+                commands.push(line.to_string());
+            }
+        }
+    }
+    if commands.is_empty() {
+        eprintln!(
+            "Usage: distrobox-cnf-handler resolve [--batch] [--output text|json] [command...]"
+        );
+        exit(1);
+    }
+    let config = Config::load().unwrap_or_default();
+    let boxes = match get_boxes() {
+        Ok(boxes) => boxes,
+        Err(e) => {
+            eprintln!("Cannot get boxes: {:?}", e);
+            exit(2);
+        }
+    };
+    let caller = caller::rule(&config);
+    let mut cache = Cache::load().unwrap_or_else(|_| Cache::empty());
+    let mut all_found = true;
+    let mut results = vec![];
+    for command in &commands {
+        let resolved = routed(command, &boxes, caller.as_ref(), &mut cache, &config);
+        all_found &= matches!(resolved, Resolved::Found(_));
+        if json {
+            results.push(to_json(command, &resolved));
+            continue;
+        }
+        match resolved {
+            Resolved::Found(name) => println!("{}\t{}", command, name),
+            Resolved::NotFound | Resolved::Incomplete => println!("{}\t{}", command, NOT_FOUND),
+        }
+    }
+    if json {
+        let doc = Json::obj(vec![
+            ("version", JSON_VERSION.into()),
+            ("results", Json::Arr(results)),
+        ]);
+        println!("{}", doc);
+    }
+    let _ = cache.save();
+    if !all_found {
+        exit(3);
+    }
+}
+
+// bumped only when a field changes meaning or goes away; editor plugins rely on the rest
+const JSON_VERSION: u64 = 1;
+
+// one command for editor plugins: where it lives and the argv that runs it there the way
+// the shell would, to which the plugin appends its own arguments
+fn to_json(command: &str, resolved: &Resolved) -> Json {
+    let status = match resolved {
+        Resolved::Found(_) => "found",
+        Resolved::NotFound => "not_found",
+        Resolved::Incomplete => "incomplete",
+    };
+    let mut fields = vec![
+        ("command", Json::str(command)),
+        ("status", Json::str(status)),
+    ];
+    let Resolved::Found(name) = resolved else {
+        return Json::obj(fields);
+    };
+    let path = distrobox::locate(name, command).ok().flatten();
+    let launch = distrobox::run_command(name, &[command.to_string()]);
+    let argv = std::iter::once(launch.get_program())
+        .chain(launch.get_args())
+        .map(|word| Json::str(word.to_string_lossy()))
+        .collect();
+    let mut env = vec![];
+    // host variables [environment] keeps out, which the plugin has to unset for the launch
+    let mut env_remove = vec![];
+    for (key, value) in launch.get_envs() {
+        let key = key.to_string_lossy().into_owned();
+        match value {
+            Some(value) => env.push((key, Json::str(value.to_string_lossy()))),
+            None => env_remove.push(Json::str(key)),
+        }
+    }
+    fields.extend([
+        ("box", Json::str(name)),
+        ("path", path.map(Json::str).into()),
+        ("argv", Json::Arr(argv)),
+        ("env", Json::Obj(env)),
+        ("env_remove", Json::Arr(env_remove)),
+    ]);
+    Json::obj(fields)
+}
@@ -0,0 +1,356 @@
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::path::PathBuf;
+use std::process::exit;
+
+use crate::builtins;
+use crate::config;
+use crate::distrobox::{get_boxes, DistroboxInstance};
+use crate::paths;
+
+// marks the hook in rc files so it is never added twice
+const HOOK_MARKER: &str = "# distrobox-cnf-handler hook";
+// `--` keeps a command named like a flag, `-i` or `--help`, from being read as one. The
+// handler runs in a child of the shell, so what it wants done in the shell itself, such
+// as `hash -p`, it leaves in a file the shell takes in before its next prompt
+const BASH_HOOK: &str = r#"_distrobox_cnf_replay="${XDG_RUNTIME_DIR:-$HOME/.cache}/distrobox-cnf-replay-$$"
+command_not_found_handle() {
+    DISTROBOX_CNF_REPLAY="$_distrobox_cnf_replay" distrobox-cnf-handler --shell-protocol bash -- "$@"
+}
+_distrobox_cnf_take() {
+    local status=$?
+    [ -f "$_distrobox_cnf_replay" ] && { . "$_distrobox_cnf_replay"; rm -f "$_distrobox_cnf_replay"; }
+    return $status
+}
+PROMPT_COMMAND="_distrobox_cnf_take${PROMPT_COMMAND:+;$PROMPT_COMMAND}"
+"#;
+const ZSH_HOOK: &str = r#"_distrobox_cnf_replay="${XDG_RUNTIME_DIR:-$HOME/.cache}/distrobox-cnf-replay-$$"
+command_not_found_handler() {
+    DISTROBOX_CNF_REPLAY="$_distrobox_cnf_replay" distrobox-cnf-handler --shell-protocol zsh -- "$@"
+}
+_distrobox_cnf_take() {
+    local status=$?
+    [ -f "$_distrobox_cnf_replay" ] && { . "$_distrobox_cnf_replay"; rm -f "$_distrobox_cnf_replay"; }
+    return $status
+}
+precmd_functions+=(_distrobox_cnf_take)
+"#;
+const FISH_HOOK: &str = r#"if set -q XDG_RUNTIME_DIR
+    set -g _distrobox_cnf_replay $XDG_RUNTIME_DIR/distrobox-cnf-replay-$fish_pid
+else
+    set -g _distrobox_cnf_replay $HOME/.cache/distrobox-cnf-replay-$fish_pid
+end
+function fish_command_not_found
+    DISTROBOX_CNF_REPLAY=$_distrobox_cnf_replay distrobox-cnf-handler --shell-protocol fish -- $argv
+end
+function _distrobox_cnf_take --on-event fish_prompt
+    test -f $_distrobox_cnf_replay; and source $_distrobox_cnf_replay; and rm -f $_distrobox_cnf_replay
+end
+"#;
+// the hooks as written before, newest first, so setup can replace them and uninstall
+// remove them: with `--` but nothing to take in, and as first written, without `--`
+const OLD_BASH_HOOKS: [&str; 2] = [
+    "command_not_found_handle() {\n    distrobox-cnf-handler --shell-protocol bash -- \"$@\"\n}\n",
+    "command_not_found_handle() {\n    distrobox-cnf-handler --shell-protocol bash \"$@\"\n}\n",
+];
+const OLD_ZSH_HOOKS: [&str; 2] = [
+    "command_not_found_handler() {\n    distrobox-cnf-handler --shell-protocol zsh -- \"$@\"\n}\n",
+    "command_not_found_handler() {\n    distrobox-cnf-handler --shell-protocol zsh \"$@\"\n}\n",
+];
+const OLD_FISH_HOOKS: [&str; 2] = [
+    "function fish_command_not_found\n    distrobox-cnf-handler --shell-protocol fish -- $argv\nend\n",
+    "function fish_command_not_found\n    distrobox-cnf-handler --shell-protocol fish $argv\nend\n",
+];
+
+fn interactive() -> bool {
+    io::stdin().is_terminal() && io::stderr().is_terminal()
+}
+
+// called when the handler runs without a config; asks once, a "no" still writes a
+// config so the question does not come back on every typo
+pub fn offer() {
+    if !interactive() {
+        return;
+    }
+    let result = match confirm(
+        "distrobox-cnf-handler is not set up yet. Set it up now?",
+        false,
+    ) {
+        Ok(true) => wizard(),
+        Ok(false) => write_config(&render(None, true)),
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
+        eprintln!("Setup failed: {}", e);
+    }
+}
+
+// `setup`: run the wizard again at any time
+pub fn setup_command(args: &[String]) {
+    if !args.is_empty() {
+        eprintln!("Usage: distrobox-cnf-handler setup");
+        exit(1);
+    }
+    if !interactive() {
+        eprintln!("Setup needs a terminal");
+        exit(1);
+    }
+    let exists = config::config_path().is_ok_and(|p| p.exists());
+    if exists && !confirm("Replace the existing config?", false).unwrap_or(false) {
+        return;
+    }
+    if let Err(e) = wizard() {
+        eprintln!("Setup failed: {}", e);
+        exit(1);
+    }
+}
+
+fn wizard() -> io::Result<()> {
+    let mut boxes: Vec<DistroboxInstance> = get_boxes().unwrap_or_else(|e| {
+        eprintln!("Cannot get boxes ({:?}), skipping box order", e);
+        vec![]
+    });
+    boxes.sort_by_key(|b| b.priority);
+    let order = if boxes.is_empty() {
+        None
+    } else {
+        Some(ask_order(&boxes)?)
+    };
+    let start_stopped = confirm("Start stopped boxes when searching them?", true)?;
+    write_config(&render(order.as_deref(), start_stopped))?;
+    let shell = builtins::invoking_shell()
+        .or_else(|| env::var("SHELL").ok())
+        .map(|s| s.rsplit('/').next().unwrap_or(&s).to_string());
+    match shell.as_deref() {
+        Some(shell @ ("bash" | "zsh" | "fish")) => {
+            if confirm(
+                &format!("Install the command-not-found hook for {}?", shell),
+                true,
+            )? {
+                install_hook(shell)?;
+            }
+        }
+        _ => eprintln!("Unknown shell, the command-not-found hook has to be installed by hand"),
+    }
+    Ok(())
+}
+
+fn ask_order(boxes: &[DistroboxInstance]) -> io::Result<Vec<String>> {
+    eprintln!("Boxes are searched in this order:");
+    for (n, dbx) in boxes.iter().enumerate() {
+        let state = if dbx.running { "running" } else { "stopped" };
+        eprintln!("  {}. {} ({})", n + 1, dbx.name, state);
+    }
+    loop {
+        let answer = ask("New order as numbers, e.g. \"2 1 3\" (Enter keeps it): ")?;
+        if answer.trim().is_empty() {
+            return Ok(boxes.iter().map(|b| b.name.clone()).collect());
+        }
+        let picked: Option<Vec<usize>> = answer
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|w| !w.is_empty())
+            .map(|w| w.parse().ok().filter(|n| (1..=boxes.len()).contains(n)))
+            .collect();
+        match picked {
+            Some(mut picked) => {
+                // whatever was left out keeps its place after the ones given
+                picked.dedup();
+                let mut order: Vec<String> =
+                    picked.iter().map(|n| boxes[n - 1].name.clone()).collect();
+                for dbx in boxes {
+                    if !order.contains(&dbx.name) {
+                        order.push(dbx.name.clone());
+                    }
+                }
+                return Ok(order);
+            }
+            None => eprintln!("Please use numbers between 1 and {}", boxes.len()),
+        }
+    }
+}
+
+fn ask(prompt: &str) -> io::Result<String> {
+    eprint!("{}", prompt);
+    io::stderr().flush()?;
+    let mut line = String::new();
+    io::stdin().lock().read_line(&mut line)?;
+    Ok(line.trim().to_string())
+}
+
+fn confirm(question: &str, default: bool) -> io::Result<bool> {
+    let hint = if default { "[Y/n]" } else { "[y/N]" };
+    loop {
+        match ask(&format!("{} {} ", question, hint))?
+            .to_lowercase()
+            .as_str()
+        {
+            "" => return Ok(default),
+            "y" | "yes" => return Ok(true),
+            "n" | "no" => return Ok(false),
+            _ => {}
+        }
+    }
+}
+
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn render(order: Option<&[String]>, start_stopped: bool) -> String {
+    let mut text = String::from(
+        "# distrobox-cnf-handler configuration\n\
+         # run `distrobox-cnf-handler setup` to go through the questions again\n\n",
+    );
+    text.push_str("# boxes are searched in this order; boxes not listed come after\n");
+    match order {
+        Some(order) => {
+            let names: Vec<String> = order.iter().map(|n| quote(n)).collect();
+            text.push_str(&format!("box_order = [{}]\n\n", names.join(", ")));
+        }
+        None => text.push_str("# box_order = [\"fedora\", \"arch\"]\n\n"),
+    }
+    text.push_str("# \"start\" stopped boxes while searching, or \"skip\" them entirely\n");
+    let policy = if start_stopped { "start" } else { "skip" };
+    text.push_str(&format!("stopped_boxes = {}\n\n", quote(policy)));
+    text.push_str("# what to try, in order; \"flatpak\" adds host flatpak apps\n");
+    text.push_str(
+        "# strategy = [\"cache\", \"running-boxes\", \"stopped-boxes\", \"suggest\"]\n\n",
+    );
+    text.push_str("# silence the handler's own messages\n# quiet = true\n");
+    text
+}
+
+fn write_config(text: &str) -> io::Result<()> {
+    let path = config::config_path()?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(&path, text)?;
+    eprintln!("Wrote {}", path.display());
+    Ok(())
+}
+
+// the rc file, the hook, and the hooks it replaces
+fn hook_file(shell: &str) -> io::Result<(PathBuf, &'static str, &'static [&'static str])> {
+    Ok(match shell {
+        "bash" => (paths::home()?.join(".bashrc"), BASH_HOOK, &OLD_BASH_HOOKS),
+        "zsh" => {
+            let dir = env::var("ZDOTDIR")
+                .map(PathBuf::from)
+                .or_else(|_| paths::home())?;
+            (dir.join(".zshrc"), ZSH_HOOK, &OLD_ZSH_HOOKS)
+        }
+        _ => (
+            paths::config_home()?
+                .join("fish")
+                .join("conf.d")
+                .join("distrobox-cnf-handler.fish"),
+            FISH_HOOK,
+            &OLD_FISH_HOOKS,
+        ),
+    })
+}
+
+fn install_hook(shell: &str) -> io::Result<()> {
+    let (path, hook, old) = hook_file(shell)?;
+    if let Ok(rc) = fs::read_to_string(&path) {
+        if let Some(rest) = old.iter().find_map(|old| without_hook(&rc, old)) {
+            fs::write(&path, format!("{}\n{}\n{}", rest, HOOK_MARKER, hook))?;
+            eprintln!("Updated the hook in {}", path.display());
+            return Ok(());
+        }
+        if rc.contains(HOOK_MARKER) {
+            eprintln!("The hook is already in {}", path.display());
+            return Ok(());
+        }
+    }
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut rc = OpenOptions::new().create(true).append(true).open(&path)?;
+    write!(rc, "\n{}\n{}", HOOK_MARKER, hook)?;
+    eprintln!(
+        "Added the hook to {}; it takes effect in new shells",
+        path.display()
+    );
+    Ok(())
+}
+
+// the rc file text without the hook `install_hook` appended, None when it is not there
+fn without_hook(rc: &str, hook: &str) -> Option<String> {
+    let block = format!("\n{}\n{}", HOOK_MARKER, hook);
+    match rc.find(&block) {
+        Some(at) => Some(format!("{}{}", &rc[..at], &rc[at + block.len()..])),
+        None => rc.strip_prefix(&block[1..]).map(|rest| rest.to_string()),
+    }
+}
+
+fn remove_hook(shell: &str) -> io::Result<()> {
+    let (path, hook, old) = hook_file(shell)?;
+    let Ok(rc) = fs::read_to_string(&path) else {
+        return Ok(());
+    };
+    if !rc.contains(HOOK_MARKER) {
+        return Ok(());
+    }
+    let rest =
+        without_hook(&rc, hook).or_else(|| old.iter().find_map(|old| without_hook(&rc, old)));
+    match rest {
+        // fish's hook has a file of its own
+        Some(rest) if rest.trim().is_empty() && shell == "fish" => fs::remove_file(&path)?,
+        Some(rest) => fs::write(&path, rest)?,
+        None => {
+            eprintln!(
+                "The hook in {} was changed since it was added, remove it by hand",
+                path.display()
+            );
+            return Ok(());
+        }
+    }
+    eprintln!("Removed the hook from {}", path.display());
+    Ok(())
+}
+
+fn remove_dir(dir: io::Result<PathBuf>) -> io::Result<()> {
+    let dir = dir?;
+    match fs::remove_dir_all(&dir) {
+        Ok(()) => eprintln!("Removed {}", dir.display()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e),
+    }
+    Ok(())
+}
+
+// `uninstall [--keep-config]`: undo what setup and the handler itself put on disk
+pub fn uninstall_command(args: &[String]) {
+    let keep_config = match args {
+        [] => false,
+        [flag] if flag == "--keep-config" => true,
+        _ => {
+            eprintln!("Usage: distrobox-cnf-handler uninstall [--keep-config]");
+            exit(1);
+        }
+    };
+    let mut failed = false;
+    for shell in ["bash", "zsh", "fish"] {
+        if let Err(e) = remove_hook(shell) {
+            eprintln!("Cannot remove the {} hook: {}", shell, e);
+            failed = true;
+        }
+    }
+    let mut dirs = vec![paths::runtime_dir(), paths::cache_dir()];
+    if !keep_config {
+        dirs.push(paths::config_dir());
+    }
+    for dir in dirs {
+        if let Err(e) = remove_dir(dir) {
+            eprintln!("Cannot remove state: {}", e);
+            failed = true;
+        }
+    }
+    if failed {
+        exit(2);
+    }
+    eprintln!("A running daemon keeps running until it is stopped or the session ends");
+}
@@ -0,0 +1,57 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use crate::dbxconf::find_in_path;
+
+// as much of a script as the kernel reads for the #! line
+const LINE_MAX: usize = 256;
+
+// a script's #! line: the interpreter and the one argument the kernel passes it
+pub struct Shebang {
+    pub interpreter: String,
+    pub arg: Option<String>,
+}
+
+pub fn read(path: &Path) -> Option<Shebang> {
+    let mut head = [0u8; LINE_MAX];
+    let n = File::open(path).ok()?.read(&mut head).ok()?;
+    let head = String::from_utf8_lossy(&head[..n]);
+    let line = head.strip_prefix("#!")?.lines().next()?.trim();
+    let (interpreter, arg) = match line.split_once([' ', '\t']) {
+        Some((interpreter, arg)) => (interpreter, Some(arg.trim().to_string())),
+        None => (line, None),
+    };
+    (!interpreter.is_empty()).then(|| Shebang {
+        interpreter: interpreter.to_string(),
+        arg: arg.filter(|a| !a.is_empty()),
+    })
+}
+
+impl Shebang {
+    // for `#!/usr/bin/env [-S] python2`, the program env looks for on PATH
+    pub fn env_program(&self) -> Option<&str> {
+        if !self.interpreter.ends_with("/env") {
+            return None;
+        }
+        self.arg
+            .as_deref()?
+            .split_whitespace()
+            .find(|word| !word.starts_with('-'))
+    }
+
+    pub fn missing_on_host(&self) -> bool {
+        match self.env_program() {
+            Some(program) => find_in_path(program).is_none(),
+            None => !Path::new(&self.interpreter).is_file(),
+        }
+    }
+
+    // the command line the kernel would have built for `args`, a script and its arguments
+    pub fn command(&self, args: &[String]) -> Vec<String> {
+        let mut command = vec![self.interpreter.clone()];
+        command.extend(self.arg.clone());
+        command.extend(args.iter().cloned());
+        command
+    }
+}
@@ -0,0 +1,94 @@
+use std::process::exit;
+
+use crate::cache::{Cache, Resolution};
+use crate::color::{self, Style};
+use crate::config::Config;
+use crate::distrobox::{self, get_boxes, DistroboxInstance};
+use crate::groups;
+use crate::i18n::tr;
+use crate::pins::{self, Scope};
+use crate::resolve::{self, order_boxes, Resolved};
+
+// the box whose command ran most recently, for picking up where the handler left off
+fn last_used(boxes: &[DistroboxInstance], cache: &Cache) -> Option<String> {
+    cache
+        .entries
+        .iter()
+        .filter_map(|e| match &e.resolution {
+            Resolution::Found(name) if boxes.iter().any(|b| &b.name == name) => {
+                Some((e.timestamp, name))
+            }
+            _ => None,
+        })
+        .max_by_key(|(timestamp, _)| *timestamp)
+        .map(|(_, name)| name.clone())
+}
+
+// a pin made elsewhere does not take the working directory out of its group
+fn allowed(config: &Config, name: &str, scope: Scope) -> bool {
+    let Some(group) = groups::excluding(config, name) else {
+        return true;
+    };
+    let reason = tr!("pinned-for", scope = scope.describe());
+    eprintln!(
+        "{}",
+        tr!("outside-group", box = name, reason = reason, boxes = group.join(", "))
+    );
+    false
+}
+
+// `shell [box | command]`: an interactive shell in a box by name, in the box a command
+// resolves to, or without either in the box that ran the last command
+pub fn shell_command(args: &[String]) {
+    let target = match args {
+        [] => None,
+        [target] => Some(target.as_str()),
+        _ => {
+            eprintln!("Usage: distrobox-cnf-handler shell [box | command]");
+            exit(1);
+        }
+    };
+    let config = Config::load().unwrap_or_default();
+    let boxes = match get_boxes() {
+        Ok(boxes) => order_boxes(boxes, None, &config),
+        Err(e) => {
+            eprintln!("Cannot get boxes: {:?}", e);
+            exit(2);
+        }
+    };
+    let mut cache = Cache::load().unwrap_or_else(|_| Cache::empty());
+    let name = match target {
+        Some(name) if boxes.iter().any(|b| b.name == name) => name.to_string(),
+        // a command goes where running it would: its pin, else the search
+        Some(command) => {
+            match pins::lookup(command).filter(|(name, scope)| allowed(&config, name, *scope)) {
+                Some((name, _)) => name,
+                None => match resolve::resolve(command, &boxes, &mut cache, &config) {
+                    Resolved::Found(name) => {
+                        let _ = cache.save();
+                        name
+                    }
+                    Resolved::NotFound | Resolved::Incomplete => {
+                        eprintln!("There is no box named {} and no box has it", command);
+                        exit(3);
+                    }
+                },
+            }
+        }
+        None => match last_used(&boxes, &cache).or_else(|| boxes.first().map(|b| b.name.clone())) {
+            Some(name) => name,
+            None => {
+                eprintln!("There are no boxes to enter; make one with: distrobox create");
+                exit(3);
+            }
+        },
+    };
+    eprintln!("Entering {}", color::err(&name, Style::BoxName));
+    match distrobox::interactive(&name) {
+        Ok(code) => exit(code),
+        Err(e) => {
+            eprintln!("Cannot run distrobox-enter: {}", e);
+            exit(1);
+        }
+    }
+}
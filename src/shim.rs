@@ -0,0 +1,129 @@
+use std::env;
+use std::fs;
+use std::io::{self, Error, ErrorKind};
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::exit;
+
+use crate::cache::now;
+use crate::distrobox::{self, get_boxes, sh_quote, ProbeOutcome};
+use crate::paths;
+
+const SHIM_DIR: &str = "shims";
+const HASHED_DIR: &str = "hashed";
+
+// a script named after the command that hands it to `run --box`, so whatever finds it on
+// PATH, make included, goes through the handler's usual routing
+fn write_shim(dir: &Path, exe: &Path, name: &str, command: &str) -> io::Result<()> {
+    // a path would be joined onto nothing, or out of `dir`
+    if !plain_name(command) {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("{} is not a plain command name", command),
+        ));
+    }
+    let path = dir.join(command);
+    let script = format!(
+        "#!/bin/sh\nexec {} run --box {} -- {} \"$@\"\n",
+        sh_quote(&exe.to_string_lossy()),
+        sh_quote(name),
+        sh_quote(command)
+    );
+    fs::write(&path, script)?;
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o755))
+}
+
+// a name looked up on PATH, not a path of its own
+pub fn plain_name(command: &str) -> bool {
+    !command.is_empty() && !command.contains('/') && command != "." && command != ".."
+}
+
+// a shim for `command` in `name` that the shell can be told to hash, so the next time it
+// skips the handler's lookup; one per command, pointing at the box it was last found in
+pub fn hashed(name: &str, command: &str) -> io::Result<PathBuf> {
+    let dir = paths::runtime_dir()?.join(SHIM_DIR).join(HASHED_DIR);
+    fs::create_dir_all(&dir)?;
+    write_shim(&dir, &env::current_exe()?, name, command)?;
+    Ok(dir.join(command))
+}
+
+// a fresh directory per call, so two sessions pointed at different boxes do not clash
+fn shim_dir(name: &str) -> io::Result<PathBuf> {
+    let dir = paths::runtime_dir()?.join(SHIM_DIR).join(format!(
+        "{}-{}-{}",
+        name,
+        now(),
+        std::process::id()
+    ));
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+fn usage() -> ! {
+    eprintln!("Usage: distrobox-cnf-handler env --box <name> --commands <command,...>");
+    exit(1);
+}
+
+// `env --box <name> --commands a,b,c`: print exports that put shims for these commands
+// first on PATH, for `eval "$(distrobox-cnf-handler env ...)"`
+pub fn env_command(args: &[String]) {
+    let mut name = None;
+    let mut commands = vec![];
+    let mut rest = args.iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "--box" => name = rest.next(),
+            "--commands" => commands.extend(
+                rest.next()
+                    .into_iter()
+                    .flat_map(|list| list.split(','))
+                    .map(str::trim)
+                    .filter(|c| !c.is_empty())
+                    .map(String::from),
+            ),
+            _ => usage(),
+        }
+    }
+    let Some(name) = name.filter(|_| !commands.is_empty()) else {
+        usage();
+    };
+    match get_boxes() {
+        Ok(boxes) if boxes.iter().any(|b| &b.name == name) => {}
+        Ok(_) => {
+            eprintln!("There is no box named {}", name);
+            exit(3);
+        }
+        Err(e) => {
+            eprintln!("Cannot get boxes: {:?}", e);
+            exit(2);
+        }
+    }
+    let exe = match env::current_exe() {
+        Ok(exe) => exe,
+        Err(e) => {
+            eprintln!("Cannot find the handler's own binary: {}", e);
+            exit(1);
+        }
+    };
+    let dir = match shim_dir(name) {
+        Ok(dir) => dir,
+        Err(e) => {
+            eprintln!("Cannot create the shim directory: {}", e);
+            exit(1);
+        }
+    };
+    for command in &commands {
+        // a shim for a command the box lacks would only shadow the host's copy
+        if let Ok(ProbeOutcome::NotFound) = distrobox::probe(name, command) {
+            eprintln!("Box {} does not have {}, leaving it out", name, command);
+            continue;
+        }
+        if let Err(e) = write_shim(&dir, &exe, name, command) {
+            eprintln!("Cannot write the shim for {}: {}", command, e);
+            exit(1);
+        }
+    }
+    let dir = sh_quote(&dir.to_string_lossy());
+    println!("export DISTROBOX_CNF_SHIMS={}", dir);
+    println!("export PATH={}:\"$PATH\"", dir);
+}
@@ -0,0 +1,224 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::process::exit;
+
+use crate::coldstart;
+use crate::color::{self, Style};
+use crate::json::Json;
+use crate::trace;
+
+// histogram bucket bounds, in milliseconds
+const BUCKETS: [f64; 11] = [
+    5.0, 10.0, 25.0, 50.0, 100.0, 250.0, 500.0, 1000.0, 2500.0, 5000.0, 10000.0,
+];
+
+// step durations in milliseconds, by phase and by box, from the saved traces
+#[derive(Default)]
+struct Samples {
+    phases: BTreeMap<&'static str, Vec<f64>>,
+    boxes: BTreeMap<String, Vec<f64>>,
+}
+
+// the trace methods that make up a lookup; "run" is entering the box
+fn phase(method: &str) -> Option<&'static str> {
+    match method {
+        "list" => Some("list"),
+        "probe" => Some("probe"),
+        "run" => Some("enter"),
+        _ => None,
+    }
+}
+
+fn collect(traces: &[Json]) -> Samples {
+    let mut samples = Samples::default();
+    for trace in traces {
+        if let Some(total) = trace.get("duration_ms").and_then(Json::as_f64) {
+            samples.phases.entry("total").or_default().push(total);
+        }
+        for step in trace
+            .get("steps")
+            .and_then(Json::as_array)
+            .unwrap_or_default()
+        {
+            let field = |key| step.get(key).and_then(Json::as_str);
+            let (Some(target), Some(method), Some(ms)) = (
+                field("target"),
+                field("method"),
+                step.get("duration_ms").and_then(Json::as_f64),
+            ) else {
+                continue;
+            };
+            let Some(phase) = phase(method) else {
+                continue;
+            };
+            samples.phases.entry(phase).or_default().push(ms);
+            if !matches!(target, "host" | "cache" | "daemon") {
+                samples
+                    .boxes
+                    .entry(target.to_string())
+                    .or_default()
+                    .push(ms);
+            }
+        }
+    }
+    for values in samples
+        .phases
+        .values_mut()
+        .chain(samples.boxes.values_mut())
+    {
+        values.sort_by(f64::total_cmp);
+    }
+    samples
+}
+
+// nearest rank, on sorted values
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    let rank = (p * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+// names are padded before they are coloured, so escapes do not throw the columns off
+fn table<'a>(
+    out: &mut String,
+    title: &str,
+    style: Option<Style>,
+    rows: impl Iterator<Item = (&'a str, &'a Vec<f64>)>,
+) {
+    let _ = writeln!(
+        out,
+        "{:<20} {:>6} {:>10} {:>10}",
+        title, "count", "p50", "p95"
+    );
+    for (name, values) in rows {
+        let name = format!("{:<20}", name);
+        let _ = writeln!(
+            out,
+            "{} {:>6} {:>8.1}ms {:>8.1}ms",
+            style.map_or(name.clone(), |style| color::out(&name, style)),
+            values.len(),
+            percentile(values, 0.5),
+            percentile(values, 0.95)
+        );
+    }
+}
+
+fn label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+// one Prometheus histogram, in seconds, with a series per phase or box
+fn histogram<'a>(
+    out: &mut String,
+    name: &str,
+    help: &str,
+    key: &str,
+    series: impl Iterator<Item = (&'a str, &'a Vec<f64>)>,
+) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} histogram", name);
+    for (value, samples) in series {
+        let value = label(value);
+        for bound in BUCKETS {
+            let below = samples.iter().filter(|ms| **ms <= bound).count();
+            let _ = writeln!(
+                out,
+                "{}_bucket{{{}=\"{}\",le=\"{}\"}} {}",
+                name,
+                key,
+                value,
+                bound / 1000.0,
+                below
+            );
+        }
+        let _ = writeln!(
+            out,
+            "{}_bucket{{{}=\"{}\",le=\"+Inf\"}} {}",
+            name,
+            key,
+            value,
+            samples.len()
+        );
+        let sum: f64 = samples.iter().sum();
+        let _ = writeln!(
+            out,
+            "{}_sum{{{}=\"{}\"}} {}",
+            name,
+            key,
+            value,
+            sum / 1000.0
+        );
+        let _ = writeln!(
+            out,
+            "{}_count{{{}=\"{}\"}} {}",
+            name,
+            key,
+            value,
+            samples.len()
+        );
+    }
+}
+
+// `stats [--prometheus]`: latency percentiles per phase and per box from the saved traces,
+// the latest lookup of each command
+pub fn stats_command(args: &[String]) {
+    let prometheus = match args {
+        [] => false,
+        [flag] if flag == "--prometheus" => true,
+        _ => {
+            eprintln!("Usage: distrobox-cnf-handler stats [--prometheus]");
+            exit(1);
+        }
+    };
+    let samples = collect(&trace::saved());
+    let mut out = String::new();
+    if prometheus {
+        histogram(
+            &mut out,
+            "distrobox_cnf_phase_duration_seconds",
+            "Time spent in each phase of a lookup.",
+            "phase",
+            samples.phases.iter().map(|(k, v)| (*k, v)),
+        );
+        histogram(
+            &mut out,
+            "distrobox_cnf_box_duration_seconds",
+            "Time spent probing and entering each box.",
+            "box",
+            samples.boxes.iter().map(|(k, v)| (k.as_str(), v)),
+        );
+        print!("{}", out);
+        return;
+    }
+    if samples.phases.is_empty() {
+        println!("Nothing has been looked up yet");
+        return;
+    }
+    table(
+        &mut out,
+        "phase",
+        None,
+        samples.phases.iter().map(|(k, v)| (*k, v)),
+    );
+    out.push('\n');
+    let boxes = samples.boxes.iter().map(|(k, v)| (k.as_str(), v));
+    table(&mut out, "box", Some(Style::BoxName), boxes);
+    let starts = coldstart::load();
+    if !starts.is_empty() {
+        out.push('\n');
+        let _ = writeln!(out, "{:<20} {:>6} {:>10}", "cold start", "count", "median");
+        for s in starts {
+            let _ = writeln!(
+                out,
+                "{} {:>6} {:>8.1}s",
+                color::out(&format!("{:<20}", s.name), Style::BoxName),
+                s.millis.len(),
+                s.median() as f64 / 1000.0
+            );
+        }
+    }
+    out.push_str("\nenter includes the command itself when it ran\n");
+    print!("{}", out);
+}
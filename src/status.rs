@@ -0,0 +1,222 @@
+use std::io::{self, Write};
+#[cfg(feature = "daemon")]
+use std::os::unix::net::UnixStream;
+use std::process::exit;
+
+use crate::cache::now;
+#[cfg(feature = "daemon")]
+use crate::daemon;
+use crate::events;
+use crate::json::Json;
+use crate::trace;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Format {
+    Text,
+    Waybar,
+    Polybar,
+}
+
+// the most recent thing the handler did
+#[derive(Default)]
+struct Activity {
+    command: String,
+    // the box it ran or is running in
+    name: Option<String>,
+    running: bool,
+    // what became of it when it did not run
+    failed: Option<String>,
+    at: u64,
+}
+
+impl Activity {
+    // the newest saved trace
+    fn last() -> Activity {
+        let Some(trace) = trace::saved().into_iter().max_by_key(|t| {
+            t.get("timestamp")
+                .and_then(Json::as_f64)
+                .unwrap_or_default() as u64
+        }) else {
+            return Activity::default();
+        };
+        let field = |json: &Json, key| {
+            json.get(key)
+                .and_then(Json::as_str)
+                .unwrap_or_default()
+                .to_string()
+        };
+        let name = trace
+            .get("steps")
+            .and_then(Json::as_array)
+            .unwrap_or_default()
+            .iter()
+            .rev()
+            .find(|s| field(s, "method") == "run" && field(s, "result") == "ran")
+            .map(|s| field(s, "target"));
+        let decision = field(&trace, "decision");
+        Activity {
+            command: field(&trace, "command"),
+            failed: name.is_none().then_some(decision),
+            name,
+            running: false,
+            at: trace
+                .get("timestamp")
+                .and_then(Json::as_f64)
+                .unwrap_or_default() as u64,
+        }
+    }
+
+    // take in an event; false for those that say nothing a bar shows
+    fn update(&mut self, event: &Json) -> bool {
+        let field = |key| event.get(key).and_then(Json::as_str).map(str::to_string);
+        let (name, running, failed) = match field("event").as_deref() {
+            Some("executed") => (field("box"), true, None),
+            Some("finished") => (field("box"), false, None),
+            Some("failed") => (None, false, Some(field("reason").unwrap_or_default())),
+            _ => return false,
+        };
+        *self = Activity {
+            command: field("command").unwrap_or_default(),
+            name,
+            running,
+            failed,
+            at: now(),
+        };
+        true
+    }
+}
+
+#[cfg(feature = "daemon")]
+fn daemon_health() -> &'static str {
+    if daemon::socket_path().is_ok_and(|p| UnixStream::connect(p).is_ok()) {
+        "running"
+    } else {
+        "not running"
+    }
+}
+
+#[cfg(not(feature = "daemon"))]
+fn daemon_health() -> &'static str {
+    "not built in"
+}
+
+fn render(activity: &Activity, format: Format) -> String {
+    let (text, class) = match activity {
+        a if a.command.is_empty() => (String::new(), "idle"),
+        Activity {
+            command,
+            name: Some(name),
+            running: true,
+            ..
+        } => (format!("{} ▸ {}", command, name), "running"),
+        Activity {
+            command,
+            name: Some(name),
+            ..
+        } => (format!("{}: {}", command, name), "idle"),
+        Activity { command, .. } => (format!("{}: -", command), "failed"),
+    };
+    let last = match activity {
+        a if a.command.is_empty() => "nothing yet".to_string(),
+        Activity {
+            name: Some(name),
+            running: true,
+            command,
+            ..
+        } => format!("running {} in {}", command, name),
+        Activity {
+            name: Some(name),
+            command,
+            at,
+            ..
+        } => format!(
+            "ran {} in {}, {}s ago",
+            command,
+            name,
+            now().saturating_sub(*at)
+        ),
+        Activity {
+            command,
+            failed,
+            at,
+            ..
+        } => format!(
+            "{}: {}, {}s ago",
+            command,
+            failed.as_deref().unwrap_or("did not run"),
+            now().saturating_sub(*at)
+        ),
+    };
+    let tooltip = format!("Last: {}\nDaemon: {}", last, daemon_health());
+    match format {
+        Format::Waybar => Json::obj(vec![
+            ("text", Json::str(text)),
+            ("alt", Json::str(class)),
+            ("tooltip", Json::str(tooltip)),
+            ("class", Json::str(class)),
+        ])
+        .to_string(),
+        Format::Polybar => text,
+        Format::Text => format!(
+            "{}\n{}",
+            if text.is_empty() { "idle" } else { &text },
+            tooltip
+        ),
+    }
+}
+
+// `status [--format text|waybar|polybar] [--follow]`: what the handler last did, once from
+// the saved traces or, following, again on every event
+pub fn status_command(args: &[String]) {
+    let usage = || -> ! {
+        eprintln!("Usage: distrobox-cnf-handler status [--format text|waybar|polybar] [--follow]");
+        exit(1);
+    };
+    let mut format = Format::Text;
+    let mut follow = false;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--follow" => follow = true,
+            "--format" => {
+                format = match args.next().map(String::as_str) {
+                    Some("text") => Format::Text,
+                    Some("waybar") => Format::Waybar,
+                    Some("polybar") => Format::Polybar,
+                    _ => usage(),
+                }
+            }
+            _ => usage(),
+        }
+    }
+    // listening before reading the history, so nothing falls in between
+    let listener = follow.then(events::listen_or_exit);
+    let mut activity = Activity::last();
+    if show(&render(&activity, format)).is_err() {
+        exit(0);
+    }
+    let Some(mut listener) = listener else {
+        return;
+    };
+    loop {
+        let event = match listener.recv() {
+            Ok(event) => Json::parse(event),
+            Err(e) => {
+                eprintln!("Cannot read events: {}", e);
+                break;
+            }
+        };
+        if event.is_some_and(|e| activity.update(&e)) && show(&render(&activity, format)).is_err() {
+            break;
+        }
+    }
+    drop(listener);
+    exit(1);
+}
+
+// the bar reads a line at a time, and stops reading when it restarts
+fn show(line: &str) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    writeln!(stdout, "{}", line)?;
+    stdout.flush()
+}
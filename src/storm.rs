@@ -0,0 +1,78 @@
+use std::fs::{self, File, OpenOptions};
+use std::io;
+use std::path::PathBuf;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::config::Config;
+use crate::paths;
+
+const STORM_DIR: &str = "storm";
+const DEFAULT_MAX_SEARCHES: usize = 4;
+// a slot that stays taken this long belongs to a search that is stuck, not busy
+const SLOT_WAIT: Duration = Duration::from_secs(10);
+const SLOT_POLL: Duration = Duration::from_millis(50);
+
+// runtime locks, not state: read_only leaves them be
+fn storm_dir() -> io::Result<PathBuf> {
+    let dir = paths::runtime_dir()?.join(STORM_DIR);
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+// whether the same command came through moments ago, as when a script or prompt plugin
+// loops on it; every call counts as the latest sighting. Off unless [storm] debounce_ms
+// sets a window: a quick second call, as from make -j or `a; a`, is a repeat too, and a
+// repeat is only answered from the cache
+pub fn repeated(config: &Config, command: &str) -> bool {
+    let window = config
+        .get_int("storm.debounce_ms")
+        .and_then(|ms| u64::try_from(ms).ok())
+        .unwrap_or(0);
+    if window == 0 {
+        return false;
+    }
+    let Ok(dir) = storm_dir() else {
+        return false;
+    };
+    let path = dir.join(format!("seen-{}", command.replace('/', "%")));
+    let last = fs::metadata(&path).and_then(|m| m.modified()).ok();
+    let _ = File::create(&path);
+    last.and_then(|t| SystemTime::now().duration_since(t).ok())
+        .is_some_and(|age| age < Duration::from_millis(window))
+}
+
+// one of a few slots for handlers searching at the same time, held until dropped
+pub struct Slot {
+    _file: File,
+}
+
+// waits for a free slot; None when they all stayed taken
+pub fn slot(config: &Config) -> io::Result<Option<Slot>> {
+    let count = config
+        .get_int("storm.max_searches")
+        .and_then(|n| usize::try_from(n).ok())
+        .filter(|n| *n > 0)
+        .unwrap_or(DEFAULT_MAX_SEARCHES);
+    let dir = storm_dir()?;
+    let files = (0..count)
+        .map(|n| {
+            OpenOptions::new()
+                .create(true)
+                .truncate(false)
+                .write(true)
+                .open(dir.join(format!("slot-{}", n)))
+        })
+        .collect::<io::Result<Vec<File>>>()?;
+    let started = Instant::now();
+    loop {
+        // the lock goes with the process, so a killed handler cannot leak its slot
+        if let Some(at) = files.iter().position(|f| f.try_lock().is_ok()) {
+            return Ok(files.into_iter().nth(at).map(|file| Slot { _file: file }));
+        }
+        if started.elapsed() > SLOT_WAIT {
+            return Ok(None);
+        }
+        thread::sleep(SLOT_POLL);
+    }
+}
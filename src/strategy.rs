@@ -0,0 +1,103 @@
+use std::sync::Once;
+
+use crate::config::Config;
+use crate::i18n::tr;
+use crate::msg::say;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Step {
+    // trust the cache's answer before searching
+    Cache,
+    RunningBoxes,
+    StoppedBoxes,
+    // host flatpak apps, matched by the last part of their ID, e.g. org.gimp.GIMP for gimp
+    Flatpak,
+    // "did you mean" and package hints once nothing had it
+    Suggest,
+}
+
+const DEFAULT: [Step; 4] = [
+    Step::Cache,
+    Step::RunningBoxes,
+    Step::StoppedBoxes,
+    Step::Suggest,
+];
+
+impl Step {
+    fn parse(name: &str) -> Option<Step> {
+        Some(match name {
+            "cache" => Step::Cache,
+            "running-boxes" => Step::RunningBoxes,
+            "stopped-boxes" => Step::StoppedBoxes,
+            "flatpak" => Step::Flatpak,
+            "suggest" | "suggest-install" => Step::Suggest,
+            _ => return None,
+        })
+    }
+}
+
+// what the handler tries and in which order, from `strategy = [...]`; leaving a step
+// out turns it off
+pub struct Strategy {
+    steps: Vec<Step>,
+}
+
+impl Strategy {
+    pub fn load(config: &Config) -> Strategy {
+        let Some(names) = config.get_list("strategy") else {
+            return Strategy {
+                steps: DEFAULT.to_vec(),
+            };
+        };
+        let unknown: Vec<&String> = names.iter().filter(|n| Step::parse(n).is_none()).collect();
+        // loaded in several places, but one complaint is enough
+        static WARNED: Once = Once::new();
+        if !unknown.is_empty() {
+            WARNED.call_once(|| {
+                for name in unknown {
+                    say!("{}", tr!("unknown-step", step = name));
+                }
+            });
+        }
+        Strategy {
+            steps: names.iter().filter_map(|n| Step::parse(n)).collect(),
+        }
+    }
+
+    pub fn has(&self, step: Step) -> bool {
+        self.steps.contains(&step)
+    }
+
+    fn position(&self, step: Step) -> Option<usize> {
+        self.steps.iter().position(|s| *s == step)
+    }
+
+    // whether boxes of this state are searched at all
+    pub fn searches(&self, running: bool) -> bool {
+        self.has(if running {
+            Step::RunningBoxes
+        } else {
+            Step::StoppedBoxes
+        })
+    }
+
+    pub fn stopped_first(&self) -> bool {
+        matches!(
+            (self.position(Step::StoppedBoxes), self.position(Step::RunningBoxes)),
+            (Some(stopped), Some(running)) if stopped < running
+        )
+    }
+
+    // whether flatpak comes before the boxes in the given state
+    pub fn flatpak_before(&self, running: bool) -> bool {
+        let boxes = if running {
+            Step::RunningBoxes
+        } else {
+            Step::StoppedBoxes
+        };
+        match (self.position(Step::Flatpak), self.position(boxes)) {
+            (Some(flatpak), Some(boxes)) => flatpak < boxes,
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,77 @@
+// sudo and doas options that take a value, so the value is not mistaken for the command
+const SUDO_VALUE_FLAGS: [&str; 12] = [
+    "-u", "-g", "-h", "-p", "-C", "-D", "-R", "-T", "-U", "-r", "-t", "--user",
+];
+const DOAS_VALUE_FLAGS: [&str; 2] = ["-u", "-C"];
+
+// `-u`, or a cluster of short options such as `-iu` whose last one takes a value
+fn takes_value(flag: &str, value_flags: &[&str]) -> bool {
+    if value_flags.contains(&flag) {
+        return true;
+    }
+    let Some(letters) = flag.strip_prefix('-').filter(|l| !l.starts_with('-')) else {
+        return false;
+    };
+    letters
+        .chars()
+        .next_back()
+        .is_some_and(|last| value_flags.contains(&format!("-{}", last).as_str()))
+}
+
+// where the command proper starts in `sudo [options] command ...`, None when `args` is
+// not a privileged invocation or names no command
+pub fn target(args: &[String]) -> Option<usize> {
+    let value_flags: &[&str] = match args.first()?.as_str() {
+        "sudo" => &SUDO_VALUE_FLAGS,
+        "doas" => &DOAS_VALUE_FLAGS,
+        _ => return None,
+    };
+    let mut at = 1;
+    while let Some(arg) = args.get(at) {
+        match arg.as_str() {
+            "--" => return (at + 1 < args.len()).then_some(at + 1),
+            flag if takes_value(flag, value_flags) => at += 2,
+            flag if flag.starts_with('-') => at += 1,
+            // VAR=value settings come before the command
+            setting if setting.contains('=') => at += 1,
+            _ => return Some(at),
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn argv(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn where_the_command_starts() {
+        for (line, at) in [
+            ("sudo htop", Some(1)),
+            ("sudo -E htop -d 5", Some(2)),
+            ("sudo -u root htop", Some(3)),
+            ("sudo --user root htop", Some(3)),
+            ("sudo -iu root", None),
+            ("sudo -Eu deploy make", Some(3)),
+            ("sudo -g wheel -u root -- htop", Some(6)),
+            ("sudo FOO=1 BAR=2 htop", Some(3)),
+            ("sudo --preserve-env=PATH htop", Some(2)),
+            ("sudo -- -weird", Some(2)),
+            ("sudo --", None),
+            ("sudo", None),
+            ("sudo -u", None),
+            ("doas -u root htop", Some(3)),
+            ("doas -n htop", Some(2)),
+            ("doas -C /etc/doas.conf", None),
+            ("htop", None),
+            ("sudoedit file", None),
+        ] {
+            assert_eq!(target(&argv(line)), at, "{}", line);
+        }
+        assert_eq!(target(&[]), None);
+    }
+}
@@ -0,0 +1,270 @@
+use std::env;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+
+use crate::distrobox::DistroboxInstance;
+use crate::index;
+use crate::tasks;
+
+const MAX_SUGGESTIONS: usize = 3;
+
+pub enum Source {
+    Host,
+    Box(String),
+}
+
+pub struct Suggestion {
+    pub command: String,
+    pub source: Source,
+    distance: usize,
+}
+
+fn host_commands() -> Vec<String> {
+    let Ok(path) = env::var("PATH") else {
+        return vec![];
+    };
+    let mut commands = vec![];
+    for dir in env::split_paths(&path) {
+        let Ok(entries) = fs::read_dir(dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let executable = entry
+                .metadata()
+                .map(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+                .unwrap_or(false);
+            if executable {
+                commands.push(entry.file_name().to_string_lossy().into_owned());
+            }
+        }
+    }
+    commands
+}
+
+// what a miss has to suggest from: the host's commands, then those of every box we have
+// (or can cheaply get) an index for, gathered once for all the hints
+pub struct Commands {
+    host: Vec<String>,
+    boxes: Vec<(String, Vec<String>)>,
+}
+
+pub fn commands(boxes: &[DistroboxInstance]) -> Commands {
+    Commands {
+        host: host_commands(),
+        boxes: boxes
+            .iter()
+            .zip(box_commands(boxes))
+            .filter_map(|(dbx, commands)| Some((dbx.name.clone(), commands?)))
+            .collect(),
+    }
+}
+
+// close matches for `command` among `known`
+pub fn suggest(command: &str, known: &Commands) -> Vec<Suggestion> {
+    let mut found: Vec<Suggestion> = vec![];
+    push_matches(&mut found, command, &known.host, || Source::Host);
+    for (name, commands) in &known.boxes {
+        push_matches(&mut found, command, commands, || Source::Box(name.clone()));
+    }
+    // stable sort keeps the host ahead of boxes, so a name available on both is reported once
+    found.sort_by_key(|s| s.distance);
+    let mut seen: Vec<String> = vec![];
+    found.retain(|s| {
+        let new = !seen.contains(&s.command);
+        seen.push(s.command.clone());
+        new
+    });
+    found.truncate(MAX_SUGGESTIONS);
+    found
+}
+
+// every box's commands, its index refreshed where it is stale, all boxes at once so a
+// slow one does not hold up the others
+fn box_commands(boxes: &[DistroboxInstance]) -> Vec<Option<Vec<String>>> {
+    tasks::parallel(boxes, indexed)
+        .into_iter()
+        .map(Option::flatten)
+        .collect()
+}
+
+fn indexed(dbx: &DistroboxInstance) -> Option<Vec<String>> {
+    match index::load(&dbx.name) {
+        Some(idx) if idx.is_fresh() => Some(idx.commands),
+        // only running boxes are indexed on the spot, starting one for a hint is too slow
+        _ if dbx.running => index::refresh(&dbx.name).ok().map(|idx| idx.commands),
+        idx => idx.map(|idx| idx.commands),
+    }
+}
+
+// whether `rest` is what one of the rules adds to a name, "#" in a rule standing for a
+// version number such as 3 or 3.12
+fn versioned_suffix(rest: &str, rules: &[String]) -> bool {
+    rules.iter().any(|rule| match rule.split_once('#') {
+        Some((before, after)) => rest
+            .strip_prefix(before)
+            .and_then(|r| r.strip_suffix(after))
+            .is_some_and(|version| {
+                version.starts_with(|c: char| c.is_ascii_digit())
+                    && version.chars().all(|c| c.is_ascii_digit() || c == '.')
+            }),
+        None => rest == rule,
+    })
+}
+
+// the nearest versioned name of `command`, e.g. python3 for python, on the host or in a
+// box; the shortest suffix wins, then the highest version, then the host and the boxes
+// in search order
+pub fn versioned(command: &str, known: &Commands, rules: &[String]) -> Option<Suggestion> {
+    let mut best: Option<Suggestion> = None;
+    let mut consider = |candidates: &[String], source: &dyn Fn() -> Source| {
+        for candidate in candidates {
+            let Some(rest) = candidate.strip_prefix(command) else {
+                continue;
+            };
+            if !versioned_suffix(rest, rules) {
+                continue;
+            }
+            // of equally long ones the newest, python3 over python2
+            let better = best
+                .as_ref()
+                .is_none_or(|b| (rest.len(), &b.command[command.len()..]) < (b.distance, rest));
+            if better {
+                best = Some(Suggestion {
+                    distance: rest.len(),
+                    command: candidate.clone(),
+                    source: source(),
+                });
+            }
+        }
+    };
+    consider(&known.host, &|| Source::Host);
+    for (name, commands) in &known.boxes {
+        consider(commands, &|| Source::Box(name.clone()));
+    }
+    best
+}
+
+fn push_matches(
+    found: &mut Vec<Suggestion>,
+    command: &str,
+    candidates: &[String],
+    source: impl Fn() -> Source,
+) {
+    // one typo for short names, two for longer ones
+    let limit = if command.chars().count() <= 4 { 1 } else { 2 };
+    let mut best: Option<Suggestion> = None;
+    for candidate in candidates {
+        if candidate == command {
+            continue;
+        }
+        let distance = edit_distance(command, candidate);
+        if distance <= limit && best.as_ref().is_none_or(|b| distance < b.distance) {
+            best = Some(Suggestion {
+                command: candidate.clone(),
+                source: source(),
+                distance,
+            });
+        }
+    }
+    found.extend(best);
+}
+
+// optimal string alignment distance: levenshtein plus adjacent transpositions
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut d = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    d[0] = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            d[i][j] = (d[i - 1][j] + 1)
+                .min(d[i][j - 1] + 1)
+                .min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    d[a.len()][b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edits_between_names() {
+        for (a, b, distance) in [
+            ("git", "git", 0),
+            ("gti", "git", 1),
+            ("gi", "git", 1),
+            ("gitt", "git", 1),
+            ("got", "git", 1),
+            ("sl", "ls", 1),
+            ("pyhton", "python", 1),
+            ("dokcer", "docker", 1),
+            ("", "ls", 2),
+            ("vim", "emacs", 5),
+            ("日本", "日木", 1),
+        ] {
+            assert_eq!(edit_distance(a, b), distance, "{} {}", a, b);
+            assert_eq!(edit_distance(b, a), distance, "{} {}", b, a);
+        }
+    }
+
+    #[test]
+    fn versioned_suffixes_by_rule() {
+        let rules: Vec<String> = ["#", "-#", "-dev"].map(String::from).to_vec();
+        for (rest, versioned) in [
+            ("3", true),
+            ("3.12", true),
+            ("2.7.18", true),
+            ("-14", true),
+            ("-dev", true),
+            ("", false),
+            (".3", false),
+            ("3a", false),
+            ("-", false),
+            ("-x", false),
+            ("-3-dev", false),
+            ("config", false),
+        ] {
+            assert_eq!(versioned_suffix(rest, &rules), versioned, "{:?}", rest);
+        }
+        assert!(!versioned_suffix("3", &[]));
+    }
+
+    #[test]
+    fn both_hints_from_one_gathering() {
+        let known = Commands {
+            host: ["python2", "git", "ls"].map(String::from).to_vec(),
+            boxes: vec![
+                (
+                    "arch".to_string(),
+                    ["python3", "python3.12", "htop"].map(String::from).to_vec(),
+                ),
+                ("fedora".to_string(), vec!["htop".to_string()]),
+            ],
+        };
+        let names = |found: Vec<Suggestion>| -> Vec<String> {
+            found
+                .into_iter()
+                .map(|s| match s.source {
+                    Source::Host => s.command,
+                    Source::Box(name) => format!("{}@{}", s.command, name),
+                })
+                .collect()
+        };
+        assert_eq!(names(suggest("gti", &known)), ["git"]);
+        assert_eq!(names(suggest("hotp", &known)), ["htop@arch"]);
+        let rules = ["#".to_string()];
+        let python = versioned("python", &known, &rules).expect("python3 is there");
+        assert_eq!(python.command, "python3");
+        assert!(matches!(python.source, Source::Box(ref name) if name == "arch"));
+        assert!(versioned("htop", &known, &rules).is_none());
+    }
+}
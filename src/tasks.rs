@@ -0,0 +1,147 @@
+use std::io::{self, Error, ErrorKind, Read};
+use std::process::{Child, Command, Output, Stdio};
+use std::sync::mpsc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+// every thread the handler starts goes through here: parallel work over a list, tasks that
+// run beside the caller, and child processes that get a deadline. Without a runtime to
+// lean on, the rules live in one place instead: a task has a name, a panic ends only it,
+// and whatever has a deadline is killed when it passes rather than left behind
+
+// run `work` on every item at once and collect the results in order. Scoped threads
+// keep this dependency-free: every thread is joined before returning, so nothing
+// outlives the caller, and a panicking one costs only its own result
+pub fn parallel<T: Sync, R: Send>(items: &[T], work: impl Fn(&T) -> R + Sync) -> Vec<Option<R>> {
+    thread::scope(|scope| {
+        let work = &work;
+        let handles: Vec<_> = items
+            .iter()
+            .map(|item| scope.spawn(move || work(item)))
+            .collect();
+        handles.into_iter().map(|h| h.join().ok()).collect()
+    })
+}
+
+// a task running beside its caller; dropping it leaves it to finish on its own
+pub struct Task<R>(Option<JoinHandle<R>>);
+
+impl<R> Task<R> {
+    // its result, None when it panicked or could not be started
+    pub fn join(self) -> Option<R> {
+        self.0?.join().ok()
+    }
+}
+
+// start `work` on a thread of its own, named for panics and debuggers. Failing to get a
+// thread is reported and costs only that task
+pub fn spawn<R: Send + 'static>(name: &str, work: impl FnOnce() -> R + Send + 'static) -> Task<R> {
+    match thread::Builder::new().name(name.to_string()).spawn(work) {
+        Ok(handle) => Task(Some(handle)),
+        Err(e) => {
+            eprintln!("Cannot start {}: {:?}", name, e);
+            Task(None)
+        }
+    }
+}
+
+fn drain(
+    name: &str,
+    mut pipe: impl Read + Send + 'static,
+    done: mpsc::Sender<()>,
+) -> Task<Vec<u8>> {
+    spawn(name, move || {
+        let mut buf = vec![];
+        let _ = pipe.read_to_end(&mut buf);
+        let _ = done.send(());
+        buf
+    })
+}
+
+// Command::output with a deadline: a child still running `limit` after it started is
+// killed and reported as TimedOut, so a hung box costs one probe and not the search.
+// Both pipes are read on their own tasks while waiting, so neither can fill and stall it
+pub fn output(cmd: &mut Command, limit: Duration) -> io::Result<Output> {
+    let mut child = cmd.stdout(Stdio::piped()).stderr(Stdio::piped()).spawn()?;
+    let (done, finished) = mpsc::channel();
+    let stdout = child
+        .stdout
+        .take()
+        .map(|p| drain("stdout", p, done.clone()));
+    let stderr = child
+        .stderr
+        .take()
+        .map(|p| drain("stderr", p, done.clone()));
+    drop(done);
+    let deadline = Instant::now() + limit;
+    let pipes = usize::from(stdout.is_some()) + usize::from(stderr.is_some());
+    for _ in 0..pipes {
+        let left = deadline.saturating_duration_since(Instant::now());
+        match finished.recv_timeout(left) {
+            // a reader that panicked has hung up, its pipe is as good as closed
+            Ok(()) | Err(mpsc::RecvTimeoutError::Disconnected) => {}
+            Err(mpsc::RecvTimeoutError::Timeout) => return Err(cancel(child, limit)),
+        }
+    }
+    // the pipes can close before the child exits, when it hands them off or closes them
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            return Err(cancel(child, limit));
+        }
+        thread::sleep(Duration::from_millis(1));
+    };
+    Ok(Output {
+        status,
+        stdout: stdout.and_then(Task::join).unwrap_or_default(),
+        stderr: stderr.and_then(Task::join).unwrap_or_default(),
+    })
+}
+
+// the readers end with the pipes the killed child held; a grandchild still holding
+// them keeps only its reader, which nobody waits for
+fn cancel(mut child: Child, limit: Duration) -> Error {
+    let _ = child.kill();
+    let _ = child.wait();
+    Error::new(
+        ErrorKind::TimedOut,
+        format!("no answer within {}s", limit.as_secs()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn output_collects_both_streams() {
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", "echo out; echo err >&2; exit 3"]);
+        let out = output(&mut cmd, Duration::from_secs(10)).expect("sh runs");
+        assert_eq!(out.stdout, b"out\n");
+        assert_eq!(out.stderr, b"err\n");
+        assert_eq!(out.status.code(), Some(3));
+    }
+
+    #[test]
+    fn output_gives_up_at_the_deadline() {
+        let started = Instant::now();
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", "exec sleep 10"]);
+        let e = output(&mut cmd, Duration::from_millis(200)).expect_err("sleep outlasts it");
+        assert_eq!(e.kind(), ErrorKind::TimedOut);
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn a_panic_costs_only_its_task() {
+        let results = parallel(&[1, 2, 3], |&n| {
+            assert_ne!(n, 2, "the second one fails");
+            n * 10
+        });
+        assert_eq!(results, [Some(10), None, Some(30)]);
+        assert_eq!(spawn("ok", || 7).join(), Some(7));
+    }
+}
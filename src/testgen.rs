@@ -0,0 +1,73 @@
+// generated inputs for the parsers' tests: a small deterministic generator, so a failing
+// case comes back with the same seed every run
+
+pub const CASES: u64 = 2000;
+
+// characters none of the parsers expect, on top of the ones each treats specially
+const ODD: [&str; 12] = [
+    "\0",
+    "\r",
+    "\n",
+    "\t",
+    "\x1b",
+    "\x1b[1;31m",
+    "é",
+    "日本",
+    "\u{fffd}",
+    "\u{85}",
+    "🦀",
+    " ",
+];
+
+pub struct Gen(u64);
+
+impl Gen {
+    pub fn new(seed: u64) -> Gen {
+        Gen(seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1)
+    }
+
+    // xorshift64*
+    pub fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next() % n.max(1) as u64) as usize
+    }
+
+    pub fn chance(&mut self, percent: usize) -> bool {
+        self.below(100) < percent
+    }
+
+    pub fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len())]
+    }
+
+    // up to `max` pieces of `special`'s characters, letters, digits and odd characters
+    pub fn text(&mut self, special: &str, max: usize) -> String {
+        let special: Vec<char> = special.chars().collect();
+        let mut out = String::new();
+        for _ in 0..self.below(max + 1) {
+            match self.below(4) {
+                0 if !special.is_empty() => out.push(*self.pick(&special)),
+                1 => out.push_str(ODD[self.below(ODD.len())]),
+                2 => out.push(char::from(b'0' + self.below(10) as u8)),
+                _ => out.push(char::from(b'a' + self.below(26) as u8)),
+            }
+        }
+        out
+    }
+
+    // a name made only of the characters in `alphabet`, starting with a letter
+    pub fn word(&mut self, alphabet: &str, max: usize) -> String {
+        let alphabet: Vec<char> = alphabet.chars().collect();
+        let mut out = String::from(char::from(b'a' + self.below(26) as u8));
+        for _ in 0..self.below(max) {
+            out.push(*self.pick(&alphabet));
+        }
+        out
+    }
+}
@@ -0,0 +1,44 @@
+use std::io::{self, IsTerminal, Write};
+
+// sets the terminal title while a boxed command runs; the previous title is saved on the
+// xterm title stack and restored on drop
+pub struct Title {
+    enabled: bool,
+    pushed: bool,
+}
+
+impl Title {
+    pub fn new(enabled: bool) -> Title {
+        Title {
+            enabled: enabled && io::stderr().is_terminal(),
+            pushed: false,
+        }
+    }
+
+    pub fn set(&mut self, command: &str, box_name: &str) {
+        if !self.enabled {
+            return;
+        }
+        let mut err = io::stderr();
+        if !self.pushed {
+            let _ = write!(err, "\x1b[22;0t");
+            self.pushed = true;
+        }
+        // control characters would end the escape sequence early
+        let title: String = format!("{} ({})", command, box_name)
+            .chars()
+            .filter(|c| !c.is_control())
+            .collect();
+        let _ = write!(err, "\x1b]2;{}\x07", title);
+        let _ = err.flush();
+    }
+}
+
+impl Drop for Title {
+    fn drop(&mut self) {
+        if self.pushed {
+            let _ = write!(io::stderr(), "\x1b[23;0t");
+            let _ = io::stderr().flush();
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt::Write as _;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::process::exit;
+use std::time::Instant;
+
+use crate::color::{self, Style};
+use crate::daemon;
+
+// one answered request, as the daemon tells it
+struct Event {
+    at: u64,
+    command: String,
+    reply: String,
+    millis: String,
+    pid: String,
+    caller: String,
+}
+
+fn parse(line: &str, at: u64) -> Option<Event> {
+    let mut fields = line.split('\t');
+    let mut next = || fields.next().map(str::to_string);
+    Some(Event {
+        at,
+        command: next()?,
+        reply: next()?,
+        millis: next()?,
+        pid: next().unwrap_or_default(),
+        caller: next().unwrap_or_default(),
+    })
+}
+
+// the box it was found in, "-" when it was not, "?" when the search did not finish
+fn outcome(reply: &str) -> String {
+    match reply.split_once(' ') {
+        Some(("found", name)) => color::out(&format!("{:<16}", name), Style::BoxName),
+        _ if reply == "notfound" => format!("{:<16}", "-"),
+        _ => format!("{:<16}", "?"),
+    }
+}
+
+fn row(out: &mut String, e: &Event) {
+    let caller = match (e.pid.as_str(), e.caller.as_str()) {
+        ("", _) => "?".to_string(),
+        (pid, "") => pid.to_string(),
+        (pid, name) => format!("{} {}", pid, name),
+    };
+    let _ = writeln!(
+        out,
+        "{:>2}:{:02} {:<20} {} {:>8}ms  {}",
+        e.at / 60,
+        e.at % 60,
+        e.command,
+        outcome(&e.reply),
+        e.millis,
+        caller
+    );
+}
+
+// the screen: the callers asking most, then as many of the latest requests as fit
+fn redraw(events: &[Event], callers: &BTreeMap<String, usize>, rows: usize) {
+    let mut busiest: Vec<_> = callers.iter().collect();
+    busiest.sort_by(|a, b| b.1.cmp(a.1));
+    let busiest: Vec<String> = busiest
+        .iter()
+        .take(5)
+        .map(|(name, count)| format!("{} ({})", name, count))
+        .collect();
+    let mut out = String::from("\x1b[H\x1b[2J");
+    let _ = writeln!(
+        out,
+        "{} requests; busiest callers: {}",
+        events.len(),
+        if busiest.is_empty() {
+            "none yet".to_string()
+        } else {
+            busiest.join(", ")
+        }
+    );
+    let _ = writeln!(
+        out,
+        "{:>5} {:<20} {:<16} {:>10}  caller",
+        "time", "command", "box", "latency"
+    );
+    for e in events
+        .iter()
+        .skip(events.len().saturating_sub(rows.saturating_sub(3)))
+    {
+        row(&mut out, e);
+    }
+    print!("{}", out);
+    let _ = io::stdout().flush();
+}
+
+// `top`: the daemon's lookups as it answers them, with who asked; to a pipe, one line each
+pub fn top_command(args: &[String]) {
+    if !args.is_empty() {
+        eprintln!("Usage: distrobox-cnf-handler top");
+        exit(1);
+    }
+    let stream = match daemon::watch_stream() {
+        Ok(stream) => stream,
+        Err(e) => {
+            eprintln!(
+                "Cannot reach the daemon ({}); start it with: distrobox-cnf-handler daemon",
+                e
+            );
+            exit(2);
+        }
+    };
+    let live = io::stdout().is_terminal();
+    let rows = env::var("LINES")
+        .ok()
+        .and_then(|l| l.parse().ok())
+        .unwrap_or(24);
+    let started = Instant::now();
+    let mut events = vec![];
+    let mut callers: BTreeMap<String, usize> = BTreeMap::new();
+    if live {
+        redraw(&events, &callers, rows);
+    }
+    for line in stream.lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        let Some(event) = parse(&line, started.elapsed().as_secs()) else {
+            continue;
+        };
+        if !live {
+            let mut out = String::new();
+            row(&mut out, &event);
+            print!("{}", out);
+            let _ = io::stdout().flush();
+            continue;
+        }
+        let name = if event.caller.is_empty() {
+            "?".to_string()
+        } else {
+            event.caller.clone()
+        };
+        *callers.entry(name).or_default() += 1;
+        events.push(event);
+        redraw(&events, &callers, rows);
+    }
+    eprintln!("The daemon went away");
+    exit(2);
+}
@@ -0,0 +1,194 @@
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use crate::cache::now;
+use crate::config;
+use crate::events;
+use crate::json::Json;
+use crate::paths;
+
+const TRACE_DIR: &str = "traces";
+
+pub enum TraceTarget {
+    Stderr,
+    File(PathBuf),
+}
+
+struct Step {
+    // a box name, or "host"/"cache" for steps that do not touch a box
+    target: String,
+    method: &'static str,
+    duration: Duration,
+    result: String,
+}
+
+// a record of every step a single resolution took, for debugging slow or wrong routings
+pub struct Trace {
+    command: String,
+    timestamp: u64,
+    started: Instant,
+    steps: Vec<Step>,
+    decision: String,
+}
+
+fn millis(d: Duration) -> Json {
+    Json::Num(d.as_micros() as f64 / 1000.0)
+}
+
+impl Trace {
+    pub fn new(command: &str) -> Trace {
+        Trace {
+            command: command.to_string(),
+            timestamp: now(),
+            started: Instant::now(),
+            steps: vec![],
+            decision: String::new(),
+        }
+    }
+
+    // record a step that began at `started` and just finished
+    pub fn step(&mut self, target: &str, method: &'static str, started: Instant, result: &str) {
+        // running a command is often how a box is probed, and it ends the same way when missing
+        if method == "probe" || (method == "run" && result != "ran") {
+            events::emit(
+                "box-probed",
+                vec![
+                    ("command", Json::str(&self.command)),
+                    ("box", Json::str(target)),
+                    ("result", Json::str(result)),
+                ],
+            );
+        }
+        self.steps.push(Step {
+            target: target.to_string(),
+            method,
+            duration: started.elapsed(),
+            result: result.to_string(),
+        });
+    }
+
+    pub fn decide(&mut self, decision: &str) {
+        self.decision = decision.to_string();
+    }
+
+    pub fn decision(&self) -> &str {
+        &self.decision
+    }
+
+    // the box the command ended up running in, if it ran in one
+    pub fn ran_in(&self) -> Option<&str> {
+        self.steps
+            .iter()
+            .rev()
+            .find(|s| s.method == "run" && s.result == "ran")
+            .map(|s| s.target.as_str())
+            .filter(|t| !matches!(*t, "host" | "cache" | "daemon"))
+    }
+
+    // each box the search got to, with how its last step there ended
+    pub fn searched(&self) -> Vec<(String, String)> {
+        let mut searched: Vec<(String, String)> = vec![];
+        let boxes = self.steps.iter().filter(|s| {
+            matches!(s.method, "run" | "probe" | "skip")
+                && !matches!(s.target.as_str(), "host" | "cache" | "daemon")
+        });
+        for step in boxes {
+            match searched.iter_mut().find(|(name, _)| *name == step.target) {
+                Some((_, result)) => result.clone_from(&step.result),
+                None => searched.push((step.target.clone(), step.result.clone())),
+            }
+        }
+        searched
+    }
+
+    pub fn to_json(&self) -> Json {
+        let steps = self
+            .steps
+            .iter()
+            .map(|s| {
+                Json::obj(vec![
+                    ("target", Json::str(&s.target)),
+                    ("method", Json::str(s.method)),
+                    ("duration_ms", millis(s.duration)),
+                    ("result", Json::str(&s.result)),
+                ])
+            })
+            .collect();
+        Json::obj(vec![
+            ("command", Json::str(&self.command)),
+            ("timestamp", self.timestamp.into()),
+            ("duration_ms", millis(self.started.elapsed())),
+            ("steps", Json::Arr(steps)),
+            ("decision", Json::str(&self.decision)),
+        ])
+    }
+
+    // a human-readable summary of where the time went
+    pub fn timings(&self) -> String {
+        let mut out = String::from("Timings:\n");
+        for step in &self.steps {
+            let what = format!("{} {}", step.method, step.target);
+            let ran = if step.method == "run" && step.result == "ran" {
+                " (includes the command itself)"
+            } else {
+                ""
+            };
+            out.push_str(&format!(
+                "  {:<24} {:>9.1}ms  {}{}\n",
+                what,
+                step.duration.as_secs_f64() * 1000.0,
+                step.result,
+                ran
+            ));
+        }
+        out.push_str(&format!(
+            "  {:<24} {:>9.1}ms",
+            "total",
+            self.started.elapsed().as_secs_f64() * 1000.0
+        ));
+        out
+    }
+
+    // kept as the command's latest resolution, for `why`
+    pub fn save(&self) -> io::Result<()> {
+        config::writable()?;
+        let path = saved_path(&self.command)?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, format!("{}\n", self.to_json()))
+    }
+
+    pub fn write(&self, target: &TraceTarget) -> io::Result<()> {
+        match target {
+            TraceTarget::Stderr => {
+                eprintln!("{}", self.to_json());
+                Ok(())
+            }
+            TraceTarget::File(path) => fs::write(path, format!("{}\n", self.to_json())),
+        }
+    }
+}
+
+fn saved_path(command: &str) -> io::Result<PathBuf> {
+    let file = format!("{}.json", command.replace('/', "%"));
+    Ok(paths::cache_dir()?.join(TRACE_DIR).join(file))
+}
+
+// the trace `save` left for `command`, None when it never ran or the file is unreadable
+pub fn last(command: &str) -> Option<Json> {
+    Json::parse(&fs::read_to_string(saved_path(command).ok()?).ok()?)
+}
+
+// every saved trace, one per command that was looked up, for `stats`
+pub fn saved() -> Vec<Json> {
+    let Ok(entries) = paths::cache_dir().and_then(|d| fs::read_dir(d.join(TRACE_DIR))) else {
+        return vec![];
+    };
+    entries
+        .flatten()
+        .filter_map(|e| Json::parse(&fs::read_to_string(e.path()).ok()?))
+        .collect()
+}
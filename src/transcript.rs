@@ -0,0 +1,126 @@
+use std::fs::{self, File};
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::cache::now;
+use crate::config::{self, Config};
+use crate::dbxconf::find_in_path;
+use crate::distrobox::sh_quote;
+use crate::paths;
+
+const TRANSCRIPT_DIR: &str = "transcripts";
+const DEFAULT_MAX_KB: u64 = 1024;
+const DEFAULT_KEEP: usize = 20;
+
+// `cmd` as the --command of script(1), with its environment; the caller adds the log
+// file, /dev/null when only the pty is wanted
+pub fn under_script(cmd: &Command) -> Option<Command> {
+    let script = find_in_path("script")?;
+    let line: Vec<String> = std::iter::once(cmd.get_program())
+        .chain(cmd.get_args())
+        .map(|word| sh_quote(&word.to_string_lossy()))
+        .collect();
+    let mut pty = Command::new(script);
+    pty.args(["--quiet", "--return", "--command"])
+        .arg(line.join(" "));
+    for (key, value) in cmd.get_envs() {
+        match value {
+            Some(value) => pty.env(key, value),
+            None => pty.env_remove(key),
+        };
+    }
+    Some(pty)
+}
+
+// [transcripts] enabled = true keeps a copy of what boxed commands print, for all of
+// them or only those in `commands`
+pub fn wanted(config: &Config, command: &str) -> bool {
+    config.get_bool("transcripts.enabled") == Some(true)
+        && config
+            .get_list("transcripts.commands")
+            .is_none_or(|commands| commands.iter().any(|c| c == command))
+}
+
+// output of one run, cut off at the size limit so a chatty command cannot fill the disk
+pub struct Transcript {
+    path: PathBuf,
+    file: File,
+    left: u64,
+}
+
+impl Transcript {
+    pub fn create(config: &Config, name: &str, args: &[String]) -> io::Result<Transcript> {
+        config::writable()?;
+        let dir = paths::cache_dir()?.join(TRANSCRIPT_DIR);
+        fs::create_dir_all(&dir)?;
+        let keep = config
+            .get_int("transcripts.keep")
+            .and_then(|n| usize::try_from(n).ok())
+            .unwrap_or(DEFAULT_KEEP);
+        rotate(&dir, keep.saturating_sub(1))?;
+        let file_name = format!(
+            "{}-{}-{}-{}.log",
+            now(),
+            std::process::id(),
+            name,
+            args[0].replace('/', "%")
+        );
+        let path = dir.join(file_name);
+        let mut file = File::create(&path)?;
+        writeln!(file, "# box {}: {:?}", name, args)?;
+        let max_kb = config
+            .get_int("transcripts.max_kb")
+            .and_then(|n| u64::try_from(n).ok())
+            .unwrap_or(DEFAULT_MAX_KB);
+        Ok(Transcript {
+            path,
+            file,
+            left: max_kb * 1024,
+        })
+    }
+
+    // `cmd` under script(1), which records through a pty of its own; None when stdout is
+    // not a terminal, where a plain pipe loses nothing, or script is missing
+    pub fn under_pty(&self, cmd: &Command) -> Option<Command> {
+        if !io::stdout().is_terminal() || !io::stdin().is_terminal() {
+            return None;
+        }
+        let mut pty = under_script(cmd)?;
+        pty.args(["--append", "--flush"])
+            .arg("--output-limit")
+            .arg(self.left.to_string())
+            .arg(&self.path);
+        Some(pty)
+    }
+
+    pub fn record(&mut self, bytes: &[u8]) {
+        if self.left == 0 {
+            return;
+        }
+        let n = bytes.len().min(self.left as usize);
+        self.left -= n as u64;
+        let _ = self.file.write_all(&bytes[..n]);
+        if self.left == 0 {
+            let _ = self
+                .file
+                .write_all(b"\n# transcript cut off at the size limit\n");
+        }
+    }
+}
+
+// drop the oldest transcripts until at most `keep` are left
+fn rotate(dir: &Path, keep: usize) -> io::Result<()> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)?
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|e| e == "log"))
+        .collect();
+    // names start with the time, so they sort oldest first
+    files.sort();
+    let excess = files.len().saturating_sub(keep);
+    for file in &files[..excess] {
+        let _ = fs::remove_file(file);
+    }
+    Ok(())
+}
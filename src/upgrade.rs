@@ -0,0 +1,76 @@
+use std::process::exit;
+
+use crate::color::{self, Style};
+use crate::config::{self, Config};
+use crate::distrobox::{self, get_boxes};
+use crate::pkgmgr;
+
+// runs as root when the box user is root, through passwordless sudo otherwise
+const AS_ROOT: &str = r#"s=; [ "$(id -u)" -ne 0 ] && s="sudo -n"; "#;
+
+enum Outcome {
+    Upgraded(&'static str),
+    Failed(String),
+    Skipped(&'static str),
+}
+
+// `upgrade-all`: run each box's own package manager upgrade, one box after the other
+pub fn upgrade_all_command(args: &[String]) {
+    if !args.is_empty() {
+        eprintln!("Usage: distrobox-cnf-handler upgrade-all");
+        exit(1);
+    }
+    if config::offline(&Config::load().unwrap_or_default()) {
+        eprintln!("Not upgrading in offline mode, package managers need the network");
+        exit(1);
+    }
+    let mut boxes = match get_boxes() {
+        Ok(boxes) => boxes,
+        Err(e) => {
+            eprintln!("Cannot get boxes: {:?}", e);
+            exit(2);
+        }
+    };
+    boxes.sort_by_key(|b| b.priority);
+    let mut results = vec![];
+    for dbx in &boxes {
+        // the box is started anyway for the upgrade, so it may as well be asked
+        let result = match pkgmgr::for_box(dbx, true) {
+            None => Outcome::Skipped("unknown package manager"),
+            Some(pm) => {
+                let manager = pm.name();
+                eprintln!(
+                    "==> Upgrading {} with {}",
+                    color::err(&dbx.name, Style::BoxName),
+                    manager
+                );
+                match distrobox::exec(&dbx.name, &format!("{}{}", AS_ROOT, pm.upgrade())) {
+                    Ok(0) => Outcome::Upgraded(manager),
+                    Ok(code) => Outcome::Failed(format!("{} exited with {}", manager, code)),
+                    Err(e) => Outcome::Failed(format!("{:?}", e)),
+                }
+            }
+        };
+        results.push((&dbx.name, result));
+    }
+    println!("Summary:");
+    let width = results.iter().map(|(n, _)| n.len()).max().unwrap_or(0);
+    let mut failed = false;
+    for (name, result) in &results {
+        let padded = format!("{:width$}", name, width = width);
+        let line = match result {
+            Outcome::Upgraded(manager) => {
+                color::out(&format!("upgraded ({})", manager), Style::Good)
+            }
+            Outcome::Failed(reason) => {
+                failed = true;
+                color::out(&format!("failed: {}", reason), Style::Bad)
+            }
+            Outcome::Skipped(reason) => color::out(&format!("skipped: {}", reason), Style::Dim),
+        };
+        println!("  {}  {}", color::out(&padded, Style::BoxName), line);
+    }
+    if failed {
+        exit(2);
+    }
+}
@@ -0,0 +1,69 @@
+use std::process::exit;
+
+use crate::distrobox;
+use crate::json::Json;
+
+// the cargo features this binary was built with
+fn features() -> Vec<&'static str> {
+    [
+        ("daemon", cfg!(feature = "daemon")),
+        ("tui", cfg!(feature = "tui")),
+    ]
+    .into_iter()
+    .filter(|(_, enabled)| *enabled)
+    .map(|(name, _)| name)
+    .collect()
+}
+
+fn dotted((major, minor, patch): distrobox::Version) -> String {
+    format!("{}.{}.{}", major, minor, patch)
+}
+
+// `--version` or `version [--json]`: what is needed to make sense of a bug report
+pub fn version_command(args: &[String]) {
+    let json = match args {
+        [] => false,
+        [flag] if flag == "--json" => true,
+        _ => {
+            eprintln!("Usage: distrobox-cnf-handler version [--json]");
+            exit(1);
+        }
+    };
+    let features = features();
+    let distrobox = distrobox::version().map(dotted);
+    let manager = distrobox::manager();
+    let manager_version = distrobox::manager_version();
+    let rootful = distrobox::rootful();
+    if json {
+        let report = Json::obj(vec![
+            ("version", Json::str(env!("CARGO_PKG_VERSION"))),
+            (
+                "features",
+                Json::Arr(features.iter().map(|f| Json::str(*f)).collect()),
+            ),
+            ("distrobox", distrobox.map(Json::str).into()),
+            ("manager", manager.map(Json::str).into()),
+            ("manager_version", manager_version.map(Json::str).into()),
+            ("rootful", rootful.into()),
+        ]);
+        println!("{}", report);
+        return;
+    }
+    let unknown = || "unknown".to_string();
+    println!("distrobox-cnf-handler {}", env!("CARGO_PKG_VERSION"));
+    println!(
+        "features: {}",
+        if features.is_empty() {
+            "none".to_string()
+        } else {
+            features.join(", ")
+        }
+    );
+    println!("distrobox: {}", distrobox.unwrap_or_else(unknown));
+    println!(
+        "backend: {} ({}){}",
+        manager.unwrap_or("none"),
+        manager_version.unwrap_or_else(unknown),
+        if rootful { ", rootful" } else { "" }
+    );
+}
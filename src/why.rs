@@ -0,0 +1,91 @@
+use std::process::exit;
+
+use crate::cache::now;
+use crate::color::{self, Style};
+use crate::json::Json;
+use crate::trace;
+
+fn field<'a>(step: &'a Json, key: &str) -> &'a str {
+    step.get(key).and_then(|v| v.as_str()).unwrap_or_default()
+}
+
+// one step of a saved trace in words
+fn explain(method: &str, target: &str, result: &str) -> String {
+    let name = color::out(target, Style::BoxName);
+    match (method, result) {
+        ("lookup", "miss") => "The cache had no entry for it".to_string(),
+        ("lookup", "hit not found") => "The cache remembered that no box has it".to_string(),
+        ("lookup", hit) => format!(
+            "The cache said box {} has it",
+            color::out(hit.trim_start_matches("hit "), Style::BoxName)
+        ),
+        ("caller", process) => format!("Called from {}, which has rules of its own", process),
+        ("forward", exit) => format!("Inside a container, so it went to the host ({})", exit),
+        ("query", result) => format!("Asked the daemon, which answered: {}", result),
+        ("slot", "busy") => "Too many other searches were running".to_string(),
+        // taking a slot is routine, left out
+        ("slot", _) => String::new(),
+        ("list", listed) => format!("Listed the boxes: {}", listed),
+        ("arch", result) => format!("Emulated boxes, another architecture: {}", result),
+        ("cold start", result) => format!("Boxes slow to start: {}", result),
+        ("affinity", name) => format!("Put {}, the box this terminal last used, first", name),
+        ("gpu", capable) => format!("Put boxes with GPU passthrough first ({})", capable),
+        ("display", capable) => format!("Put boxes that reach the display first ({})", capable),
+        ("pre_handler", result) => format!("Asked the pre handler first ({})", result),
+        ("fallback", result) => format!("Handed it to the fallback handler ({})", result),
+        ("order", order) => format!("Searched in this order: {}", order),
+        ("interpreter", result) => format!("Looked for the script's interpreter: {}", result),
+        ("resolve", result) => format!("Looked for the command behind sudo: {}", result),
+        ("flatpak", "no app") => "No flatpak app matched".to_string(),
+        ("flatpak", result) => format!("Flatpak: {}", result),
+        ("probe all", providing) => format!("Probed every box for the picker ({})", providing),
+        ("skip", why) => format!("Skipped box {}: {}", name, why),
+        ("probe", "found") => format!("Box {} has it", name),
+        ("probe", result) => format!("Probed box {}: {}", name, result),
+        ("run", "ran") => format!("Ran it in box {}", name),
+        ("run", "not found") => format!("Box {} does not have it", name),
+        ("run", result) => format!("Box {}: {}", name, result),
+        (method, result) => format!("{} {}: {}", method, target, result),
+    }
+}
+
+// `why <command>`: walk through how the last resolution of a command went
+pub fn why_command(args: &[String]) {
+    let [command] = args else {
+        eprintln!("Usage: distrobox-cnf-handler why <command>");
+        exit(1);
+    };
+    let Some(trace) = trace::last(command) else {
+        eprintln!("{} has not been looked up yet", command);
+        exit(3);
+    };
+    let ago = trace
+        .get("timestamp")
+        .and_then(|t| t.as_f64())
+        .map(|t| now().saturating_sub(t as u64));
+    let took = trace.get("duration_ms").and_then(|d| d.as_f64());
+    println!(
+        "Last lookup of {}, {}s ago, took {:.1}ms:",
+        color::out(command, Style::Command),
+        ago.unwrap_or_default(),
+        took.unwrap_or_default()
+    );
+    let steps = trace.get("steps").and_then(|s| s.as_array()).unwrap_or(&[]);
+    let mut n = 0;
+    for step in steps {
+        let line = explain(
+            field(step, "method"),
+            field(step, "target"),
+            field(step, "result"),
+        );
+        if line.is_empty() {
+            continue;
+        }
+        n += 1;
+        println!("  {}. {}", n, line);
+    }
+    match trace.get("decision").and_then(|d| d.as_str()) {
+        Some(decision) if !decision.is_empty() => println!("Outcome: {}", decision),
+        _ => println!("Outcome: unknown, the lookup did not finish"),
+    }
+}
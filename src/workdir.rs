@@ -0,0 +1,71 @@
+use std::env;
+use std::path::{Path, PathBuf};
+use std::sync::{Once, OnceLock};
+
+use crate::color::{self, Style};
+use crate::distrobox;
+use crate::i18n::tr;
+use crate::msg::say;
+use crate::pathmap::{self, Mounts};
+use crate::paths;
+
+static COPY_BACK: OnceLock<String> = OnceLock::new();
+
+// from --copy-back: files matching this are brought back from a box that does not
+// share the current directory
+pub fn set_copy_back(pattern: String) {
+    let _ = COPY_BACK.set(pattern);
+}
+
+// where the box sees the host's `dir`, None when it does not; the home is shared by every
+// box distrobox makes without --home, so only other directories cost an inspect
+fn seen_at(name: &str, dir: &Path) -> Option<PathBuf> {
+    if paths::home().is_ok_and(|home| dir.starts_with(home)) && pathmap::custom_home(name).is_none()
+    {
+        return Some(dir.to_path_buf());
+    }
+    match Mounts::of(name) {
+        Ok(mounts) => mounts.translate(dir),
+        // nothing to go by, so assume it is as usual
+        Err(_) => Some(dir.to_path_buf()),
+    }
+}
+
+// before a run in `name`: the current directory when the box does not see it, so
+// whatever the command writes there ends up inside the box
+pub fn before_run(name: &str) -> Option<PathBuf> {
+    let dir = env::current_dir().ok()?;
+    seen_at(name, &dir).is_none().then_some(dir)
+}
+
+// the current directory as the box sees it, when that is somewhere else, e.g. a project
+// in the host's home under /run/host for a box with a home of its own; distrobox-enter
+// would start such a box in its own home instead
+pub fn in_box(name: &str) -> Option<PathBuf> {
+    let dir = env::current_dir().ok()?;
+    seen_at(name, &dir).filter(|seen| seen != &dir)
+}
+
+// after the command ran: bring back what --copy-back asks for, or say where files went
+pub fn after_run(name: &str, dir: &Path) {
+    let Some(pattern) = COPY_BACK.get() else {
+        // once is enough when a run is retried
+        static WARNED: Once = Once::new();
+        WARNED.call_once(|| {
+            say!(
+                "{}",
+                tr!(
+                    "unshared-dir",
+                    box = color::err(name, Style::BoxName),
+                    dir = dir.display()
+                )
+            )
+        });
+        return;
+    };
+    match distrobox::copy_back(name, dir, pattern) {
+        Ok(true) => {}
+        Ok(false) => say!("{}", tr!("copy-back-none", pattern = pattern, box = name)),
+        Err(e) => say!("{}", tr!("cannot-copy-back", error = format!("{:?}", e))),
+    }
+}
@@ -0,0 +1,52 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+// where WSLg keeps its X and Wayland sockets, which /tmp/.X11-unix only points to
+pub const WSLG_DIR: &str = "/mnt/wslg";
+const INTEROP: &str = "/proc/sys/fs/binfmt_misc/WSLInterop";
+
+// the host is a WSL distribution; it says so in the environment, and its kernel in its name
+pub fn detected() -> bool {
+    static WSL: OnceLock<bool> = OnceLock::new();
+    *WSL.get_or_init(|| {
+        env::var_os("WSL_DISTRO_NAME").is_some()
+            || Path::new(INTEROP).exists()
+            || fs::read_to_string("/proc/sys/kernel/osrelease")
+                .is_ok_and(|r| r.to_lowercase().contains("microsoft"))
+    })
+}
+
+pub fn wslg() -> bool {
+    detected() && Path::new(WSLG_DIR).is_dir()
+}
+
+// where Windows drives are mounted, /mnt/ unless /etc/wsl.conf moves them
+fn automount_root() -> PathBuf {
+    let conf = fs::read_to_string("/etc/wsl.conf").unwrap_or_default();
+    let mut automount = false;
+    for line in conf.lines().map(str::trim) {
+        if line.starts_with('[') {
+            automount = line == "[automount]";
+        } else if let Some(("root", root)) = line.split_once('=').map(|(k, v)| (k.trim(), v)) {
+            if automount {
+                return PathBuf::from(root.trim().trim_matches('"'));
+            }
+        }
+    }
+    PathBuf::from("/mnt/")
+}
+
+// a Windows path pasted into the shell, C:\Users\me or C:/Users/me, as WSL mounts it
+pub fn unix_path(arg: &str) -> Option<PathBuf> {
+    let mut chars = arg.chars();
+    let drive = chars.next().filter(char::is_ascii_alphabetic)?;
+    let rest = chars.as_str().strip_prefix(':')?;
+    if !(rest.is_empty() || rest.starts_with(['\\', '/'])) {
+        return None;
+    }
+    let mut path = automount_root().join(drive.to_ascii_lowercase().to_string());
+    path.extend(rest.split(['\\', '/']).filter(|p| !p.is_empty()));
+    Some(path)
+}
@@ -0,0 +1,190 @@
+// the wrapped command line has to reach the program in the box byte for byte, whichever
+// way the handler gets there; a stand-in box records the argv it was given
+#[allow(dead_code)]
+#[path = "../benches/common/mod.rs"]
+mod common;
+
+use std::fs;
+use std::process::exit;
+
+use common::{Sandbox, HANDLER};
+
+// distrobox-enter <box> -- <command...>, like the benches' stand-in; login shells are a
+// plain sh whose profile, in the box directory as its home, keeps the box on PATH
+const ENTER: &str = r#"box="$1"; shift; [ "$1" = "--" ] && shift
+dir="$ARGV_BOXES/$box"
+printf '%s\n' "${AWS_SECRET_ACCESS_KEY-unset}" >> "$ARGV_ENV"
+export SHELL=/bin/sh HOME="$dir"
+if [ "$1" = "sh" ]; then shift; PATH="$dir:/usr/bin:/bin" exec /bin/sh "$@"; fi
+[ -x "$dir/$1" ] && { cmd="$dir/$1"; shift; exec "$cmd" "$@"; }
+echo "sh: $1: command not found" >&2; exit 127"#;
+
+const ARGS: [&str; 14] = [
+    "two words",
+    "line\nbreak",
+    "it's \"quoted\"",
+    "*.rs",
+    "[a-z]?",
+    "$HOME `id` $(id)",
+    "ünïcödé ✓ 日本",
+    "",
+    " leading and trailing ",
+    "tab\there",
+    "back\\slash\\",
+    "-x",
+    "--",
+    ";&|<>",
+];
+
+// a host variable every case hands in, and only "filtered env" keeps from the box
+const SECRET: (&str, &str) = ("AWS_SECRET_ACCESS_KEY", "host-secret");
+
+// the ways a command can be run: how the handler is called, and the config it runs under
+const CASES: [(&str, &[&str], &str); 9] = [
+    ("search", &[], ""),
+    ("cached", &[], ""),
+    ("run --box", &["run", "--box", "argvbox", "--"], ""),
+    ("run", &["run", "--"], ""),
+    ("login shell", &[], "[login_shell]\ndefault = true\n"),
+    ("wrapped", &[], "[limits]\numask = true\nnice = 0\n"),
+    ("transcript", &[], "[transcripts]\nenabled = true\n"),
+    ("untranslated", &[], "path_translation = false\n"),
+    (
+        "filtered env",
+        &[],
+        "[environment]\ndeny = [\"AWS_*\", \"SSH_AUTH_SOCK\"]\n",
+    ),
+];
+
+// commands named like the handler's own flags and subcommands, in the forms that have to
+// pass them on as the command: the case, how the handler is called, and the command
+const NAMED: [(&str, &[&str], &str); 8] = [
+    ("-i after --", &["--"], "-i"),
+    ("--help after --", &["--"], "--help"),
+    ("gc after --", &["--"], "gc"),
+    (
+        "-i from the hook",
+        &["--shell-protocol", "bash", "--"],
+        "-i",
+    ),
+    (
+        "--help from the hook",
+        &["--shell-protocol", "zsh", "--"],
+        "--help",
+    ),
+    (
+        "-i from a hook without --",
+        &["--shell-protocol", "bash"],
+        "-i",
+    ),
+    ("--help with run", &["run", "--"], "--help"),
+    ("status with run", &["run", "--"], "status"),
+];
+
+fn main() {
+    let sandbox = Sandbox::new("argv");
+    let boxes = sandbox.root.join("boxes");
+    let dir = boxes.join("argvbox");
+    fs::create_dir_all(&dir).expect("create box");
+    fs::write(
+        dir.join(".profile"),
+        format!("PATH='{}':$PATH\n", dir.display()),
+    )
+    .expect("write profile");
+    let out = sandbox.root.join("argv");
+    let env = sandbox.root.join("env");
+    common::write_script(
+        &dir.join("argv-dump"),
+        &format!("printf '%s\\0' \"$@\" > '{}'", out.display()),
+    );
+    // each writes its own name before its arguments
+    for (_, _, command) in NAMED {
+        common::write_script(
+            &dir.join(command),
+            &format!("printf '%s\\0' \"${{0##*/}}\" \"$@\" > '{}'", out.display()),
+        );
+    }
+    sandbox.script("distrobox-enter", ENTER);
+    sandbox.script("podman", "exit 1");
+    sandbox.script(
+        "distrobox-list",
+        "[ \"$1\" = --version ] && { echo 'distrobox: 1.7.2.1'; exit 0; }\n\
+         echo 'ID | NAME | STATUS | IMAGE'\necho 'argvbox | argvbox | Up 1 hour | argv:latest'",
+    );
+    let expected: Vec<u8> = ARGS
+        .iter()
+        .flat_map(|a| [a.as_bytes(), b"\0"].concat())
+        .collect();
+    let mut failed = 0;
+    for (name, prefix, config) in CASES {
+        sandbox.config(&format!("login_path = false\nquadlets = false\n{}", config));
+        if name == "search" {
+            let _ = fs::remove_file(sandbox.cache_file("resolutions"));
+        }
+        let _ = fs::remove_file(&out);
+        let _ = fs::remove_file(&env);
+        let status = sandbox
+            .command(HANDLER)
+            .args(prefix)
+            .arg("argv-dump")
+            .args(ARGS)
+            .env("ARGV_BOXES", &boxes)
+            .env("ARGV_ENV", &env)
+            .env(SECRET.0, SECRET.1)
+            .status()
+            .expect("spawn");
+        let got = fs::read(&out).unwrap_or_default();
+        // every distrobox-enter the case ran, probes included, saw the variable or not
+        let secret = if name == "filtered env" {
+            "unset"
+        } else {
+            SECRET.1
+        };
+        let entered = fs::read_to_string(&env).unwrap_or_default();
+        let env_kept = !entered.is_empty() && entered.lines().all(|l| l == secret);
+        if status.success() && got == expected && env_kept {
+            println!("{}: ok", name);
+            continue;
+        }
+        failed += 1;
+        println!("{}: {:?}", name, status);
+        if !env_kept {
+            println!("  {} arrived as {:?}, not {:?}", SECRET.0, entered, secret);
+        }
+        for (want, got) in ARGS.iter().zip(got.split(|b| *b == 0)) {
+            if want.as_bytes() != got {
+                println!("  {:?} arrived as {:?}", want, String::from_utf8_lossy(got));
+            }
+        }
+    }
+    sandbox.config("login_path = false\nquadlets = false\n");
+    for (name, prefix, command) in NAMED {
+        let _ = fs::remove_file(&out);
+        let status = sandbox
+            .command(HANDLER)
+            .args(prefix)
+            .arg(command)
+            .args(ARGS)
+            .env("ARGV_BOXES", &boxes)
+            .env("ARGV_ENV", &env)
+            .status()
+            .expect("spawn");
+        let want = [format!("{}\0", command).as_bytes(), &expected].concat();
+        let got = fs::read(&out).unwrap_or_default();
+        if status.success() && got == want {
+            println!("{}: ok", name);
+            continue;
+        }
+        failed += 1;
+        let got = String::from_utf8_lossy(&got);
+        println!("{}: {:?}, got {:?}", name, status, got.split('\0').next());
+    }
+    if failed > 0 {
+        eprintln!(
+            "{} of {} ways mangled the command line or environment",
+            failed,
+            CASES.len() + NAMED.len()
+        );
+        exit(1);
+    }
+}
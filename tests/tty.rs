@@ -0,0 +1,119 @@
+// a curses program run through the handler on a terminal has to find one where it draws
+// and reads its keys, as it would on the host, while a box that cannot start is still
+// told from one without the command: stderr stays the handler's to watch. script(1)
+// provides the terminal, stand-in boxes the program
+#[allow(dead_code)]
+#[path = "../benches/common/mod.rs"]
+mod common;
+
+use std::fs;
+use std::io::Write;
+use std::process::{exit, Command, ExitStatus, Stdio};
+use std::thread;
+use std::time::Duration;
+
+use common::{Sandbox, HANDLER};
+
+// distrobox-enter <box> -- <command...>, running the box's programs from its directory
+const ENTER: &str = r#"box="$1"; shift; [ "$1" = "--" ] && shift
+[ "$box" = broken ] && { echo "Error: unable to start container: OCI runtime error" >&2; exit 125; }
+dir="$TTY_BOXES/$box"
+if [ "$1" = "sh" ]; then shift; PATH="$dir:$PATH" exec /bin/sh "$@"; fi
+[ -x "$dir/$1" ] && { cmd="$dir/$1"; shift; exec "$cmd" "$@"; }
+echo "sh: $1: command not found" >&2; exit 127"#;
+
+// whether stdin and stdout are a terminal, and the key curses read
+const CURSES: &str = r#"exec python3 -c '
+import curses, os
+ttys = " ".join(str(os.isatty(fd)) for fd in (0, 1))
+def main(screen):
+    screen.addstr(0, 0, "press a key")
+    screen.refresh()
+    return screen.getkey()
+key = curses.wrapper(main)
+open(os.environ["TTY_OUT"], "w").write(ttys + " " + key)
+'"#;
+
+fn available(program: &str, args: &[&str]) -> bool {
+    Command::new(program)
+        .args(args)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|s| s.success())
+}
+
+// the handler run on a terminal of script(1)'s, with `input` typed into it after `wait`
+fn on_terminal(sandbox: &Sandbox, command: &str, input: &[u8], wait: u64) -> (ExitStatus, String) {
+    let mut child = sandbox
+        .command("script")
+        .args(["--quiet", "--return", "--command"])
+        .arg(format!("'{}' {}", HANDLER, command))
+        .arg("/dev/null")
+        .env("TTY_BOXES", sandbox.root.join("boxes"))
+        .env("TTY_OUT", sandbox.root.join("tty"))
+        .env("TERM", "xterm")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("spawn script");
+    let mut stdin = child.stdin.take().expect("stdin was piped");
+    thread::sleep(Duration::from_secs(wait));
+    let _ = stdin.write_all(input);
+    let out = child.wait_with_output().expect("wait for script");
+    drop(stdin);
+    (
+        out.status,
+        String::from_utf8_lossy(&out.stdout).into_owned(),
+    )
+}
+
+fn main() {
+    if !available("script", &["--version"]) || !available("python3", &["-c", "import curses"]) {
+        println!("curses: skipped, needs script(1) and python3 with curses");
+        return;
+    }
+    let sandbox = Sandbox::new("tty");
+    let dir = sandbox.root.join("boxes").join("ttybox");
+    fs::create_dir_all(&dir).expect("create box");
+    common::write_script(&dir.join("curses-demo"), CURSES);
+    sandbox.script("distrobox-enter", ENTER);
+    sandbox.script("podman", "exit 1");
+    sandbox.script(
+        "distrobox-list",
+        "[ \"$1\" = --version ] && { echo 'distrobox: 1.7.2.1'; exit 0; }\n\
+         echo 'ID | NAME | STATUS | IMAGE'\n\
+         echo 'broken | broken | Up 1 hour | tty:latest'\n\
+         echo 'ttybox | ttybox | Up 1 hour | tty:latest'",
+    );
+    sandbox.config("login_path = false\nquadlets = false\n");
+    let mut failed = false;
+
+    // a box that cannot start is a failure to report, not the command having run
+    let (status, shown) = on_terminal(&sandbox, "frob", b"", 0);
+    let cached = fs::read_to_string(sandbox.cache_file("resolutions")).unwrap_or_default();
+    if status.success() || cached.contains("frob") || shown.contains("sh: frob") {
+        println!(
+            "broken box: {:?}, cached {:?}, shown {:?}",
+            status, cached, shown
+        );
+        eprintln!("a box that could not start was taken for one that ran the command");
+        failed = true;
+    } else {
+        println!("broken box: ok");
+    }
+
+    // curses has to be up before the key arrives, or it could be read before initscr
+    let (status, _) = on_terminal(&sandbox, "curses-demo", b"q", 2);
+    let got = fs::read_to_string(sandbox.root.join("tty")).unwrap_or_default();
+    if status.success() && got == "True True q" {
+        println!("curses: ok");
+    } else {
+        println!("curses: {:?}, got {:?}", status, got);
+        eprintln!("the program in the box did not get the terminal");
+        failed = true;
+    }
+    if failed {
+        exit(1);
+    }
+}